@@ -0,0 +1,37 @@
+use crate::rng::Rng;
+
+/// The rule by which the annealer decides whether to move to a proposed
+/// state, given the change in energy and the temperature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Acceptance {
+    /// Accept every move that does not make the state worse, and the others
+    /// with probability `exp(-ΔE / T)`.
+    #[default]
+    Metropolis,
+    /// The heat-bath rule: accept every move, better or worse, with
+    /// probability `1 / (1 + exp(ΔE / T))`, so that even moves that make the
+    /// state better are rejected some of the time at high temperatures.
+    Glauber,
+}
+
+impl Acceptance {
+    /// Whether to move from a state of energy `current` to one of energy
+    /// `proposed`, at the given temperature.
+    pub(crate) fn accepts(
+        self,
+        current: usize,
+        proposed: usize,
+        temperature: f64,
+        rng: &mut impl Rng,
+    ) -> bool {
+        let decrease = f64::from(
+            i32::try_from(current as isize - proposed as isize).expect("Over or underflow"),
+        );
+        match self {
+            Acceptance::Metropolis => {
+                proposed < current || rng.f64() <= (decrease / temperature).exp().min(1.)
+            }
+            Acceptance::Glauber => rng.f64() < 1. / (1. + (-decrease / temperature).exp()),
+        }
+    }
+}
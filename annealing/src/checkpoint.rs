@@ -0,0 +1,226 @@
+//! Snapshots of an anneal in progress, so that long anneals can be resumed
+//! after an interruption; see [`crate::anneal_checkpointed`].
+
+use crate::cooling::CoolingState;
+use crate::rng::WyRand;
+use crate::solver::SolveStats;
+use std::io::{self, Read, Write};
+use std::time::Duration;
+use sudoku::{parsing, Sudoku, SudokuCell, SudokuCellValue};
+
+/// Everything needed to resume an anneal: the current and best boards, the
+/// state of the random number generator and of the cooling, and the
+/// statistics so far.
+#[derive(Clone)]
+pub struct Checkpoint<R> {
+    pub board: Sudoku,
+    /// The lowest-energy state visited so far.
+    pub best_board: Sudoku,
+    pub rng: R,
+    /// Statistics of the anneal up to the checkpoint; `final_energy` is the
+    /// energy of `board`.
+    pub stats: SolveStats,
+    pub cooling: CoolingState,
+}
+
+impl Checkpoint<WyRand> {
+    /// Writes the checkpoint in a plain text format, which can be read back
+    /// with [`Checkpoint::parse`].
+    ///
+    /// The format is one `key value...` line for each field, followed by a
+    /// `board` line and the board, in .sudoku format. The best board is
+    /// written on a single line, as its digits in reading order.
+    pub fn write(&self, mut writer: impl Write) -> io::Result<()> {
+        let stats = &self.stats;
+        writeln!(writer, "# annealing checkpoint")?;
+        writeln!(writer, "rng {}", self.rng.state())?;
+        writeln!(writer, "elapsed {}", stats.elapsed.as_secs_f64())?;
+        writeln!(writer, "iterations {}", stats.iterations)?;
+        writeln!(writer, "accepted {}", stats.accepted)?;
+        writeln!(writer, "temperature_steps {}", stats.temperature_steps)?;
+        writeln!(
+            writer,
+            "best_energy {} {}",
+            stats.best_energy, stats.best_energy_iteration
+        )?;
+        writeln!(writer, "energy {}", stats.final_energy)?;
+        let side = self.best_board.side();
+        let best_board = (0..side * side)
+            .map(|raw| match self.best_board.get_raw(raw).value() {
+                Some(value) => value.to_string(),
+                None => "_".to_string(),
+            })
+            .collect::<Vec<_>>();
+        writeln!(writer, "best_board {}", best_board.join(" "))?;
+        // Floats are written in their shortest representation that parses
+        // back exactly.
+        match &self.cooling {
+            CoolingState::Fixed { iteration } => {
+                writeln!(writer, "cooling fixed {}", iteration)?;
+            }
+            CoolingState::Adaptive {
+                temperature,
+                acceptance,
+                iteration,
+            } => {
+                writeln!(
+                    writer,
+                    "cooling adaptive {} {} {}",
+                    temperature, acceptance, iteration
+                )?;
+            }
+            CoolingState::RateAdaptive {
+                temperature,
+                rate,
+                step_accepted,
+                step_iterations,
+                iteration,
+            } => {
+                writeln!(
+                    writer,
+                    "cooling rate-adaptive {} {} {} {} {}",
+                    temperature, rate, step_accepted, step_iterations, iteration
+                )?;
+            }
+        }
+        writeln!(writer, "board")?;
+        writeln!(writer, "{}", self.board)
+    }
+
+    /// Reads a checkpoint written by [`Checkpoint::write`].
+    pub fn parse(mut reader: impl Read) -> Result<Self, String> {
+        let mut contents = String::new();
+        reader
+            .read_to_string(&mut contents)
+            .map_err(|e| format!("Could not read the checkpoint: {}", e))?;
+
+        let mut rng = None;
+        let mut elapsed = None;
+        let mut iterations = None;
+        let mut accepted = None;
+        let mut temperature_steps = None;
+        let mut best_energy = None;
+        let mut energy = None;
+        let mut cooling = None;
+        let mut board = None;
+        let mut best_board: Option<Vec<Option<usize>>> = None;
+
+        let mut lines = contents.split_inclusive('\n').enumerate();
+        while let Some((number, line)) = lines.next() {
+            let error = |what: &str| format!("Line {}: {}", number + 1, what);
+            let mut fields = line.split_whitespace();
+            let key = match fields.next() {
+                Some(key) if !key.starts_with('#') => key,
+                _ => continue,
+            };
+            let values = fields.collect::<Vec<_>>();
+            let int = |i: usize| -> Result<usize, String> {
+                values
+                    .get(i)
+                    .and_then(|v| v.parse().ok())
+                    .ok_or_else(|| error("expected an integer."))
+            };
+            let float = |i: usize| -> Result<f64, String> {
+                values
+                    .get(i)
+                    .and_then(|v| v.parse().ok())
+                    .ok_or_else(|| error("expected a float."))
+            };
+
+            match key {
+                "rng" => {
+                    let state = values.first().and_then(|v| v.parse::<u64>().ok());
+                    rng = Some(state.ok_or_else(|| error("expected an integer."))?);
+                }
+                "elapsed" => {
+                    let seconds = Duration::try_from_secs_f64(float(0)?);
+                    elapsed = Some(seconds.map_err(|_| error("expected a duration in seconds."))?);
+                }
+                "iterations" => iterations = Some(int(0)?),
+                "accepted" => accepted = Some(int(0)?),
+                "temperature_steps" => temperature_steps = Some(int(0)?),
+                "best_energy" => best_energy = Some((int(0)?, int(1)?)),
+                "energy" => energy = Some(int(0)?),
+                "best_board" => {
+                    let digits = values
+                        .iter()
+                        .map(|v| match *v {
+                            "_" => Ok(None),
+                            v => v.parse().map(Some),
+                        })
+                        .collect::<Result<Vec<_>, _>>();
+                    best_board = Some(digits.map_err(|_| error("expected digits or `_`."))?);
+                }
+                "cooling" => {
+                    cooling = Some(match values.first() {
+                        Some(&"fixed") => CoolingState::Fixed { iteration: int(1)? },
+                        Some(&"adaptive") => CoolingState::Adaptive {
+                            temperature: float(1)?,
+                            acceptance: float(2)?,
+                            iteration: int(3)?,
+                        },
+                        Some(&"rate-adaptive") => CoolingState::RateAdaptive {
+                            temperature: float(1)?,
+                            rate: float(2)?,
+                            step_accepted: int(3)?,
+                            step_iterations: int(4)?,
+                            iteration: int(5)?,
+                        },
+                        _ => return Err(error("unknown kind of cooling.")),
+                    })
+                }
+                "board" => {
+                    let rest = (&mut lines).map(|(_, line)| line).collect::<String>();
+                    board = Some(parsing::sudoku::parse(rest.as_bytes())?);
+                    break;
+                }
+                _ => return Err(error(&format!("unknown field `{}`.", key))),
+            }
+        }
+
+        let missing = |field: &str| format!("The checkpoint has no `{}`.", field);
+        let (best_energy, best_energy_iteration) =
+            best_energy.ok_or_else(|| missing("best_energy"))?;
+        let board = board.ok_or_else(|| missing("board"))?;
+        let best_board = {
+            let digits = best_board.ok_or_else(|| missing("best_board"))?;
+            if digits.len() != board.side() * board.side() {
+                return Err("The best board is not the same size as the board.".to_string());
+            }
+            let mut best_board = board.clone();
+            for (raw, digit) in digits.into_iter().enumerate() {
+                match digit {
+                    Some(digit) if (1..=board.side()).contains(&digit) => {
+                        best_board.set_raw(raw, SudokuCell::digit(digit))
+                    }
+                    Some(digit) => {
+                        return Err(format!(
+                            "The best board has a {}, which does not fit a {}x{} board.",
+                            digit,
+                            board.side(),
+                            board.side()
+                        ))
+                    }
+                    None => best_board.set_raw(raw, SudokuCell::Empty),
+                }
+            }
+            best_board
+        };
+        Ok(Checkpoint {
+            board,
+            best_board,
+            rng: WyRand::with_seed(rng.ok_or_else(|| missing("rng"))?),
+            stats: SolveStats {
+                elapsed: elapsed.ok_or_else(|| missing("elapsed"))?,
+                iterations: iterations.ok_or_else(|| missing("iterations"))?,
+                accepted: accepted.ok_or_else(|| missing("accepted"))?,
+                temperature_steps: temperature_steps.ok_or_else(|| missing("temperature_steps"))?,
+                best_energy,
+                best_energy_iteration,
+                final_energy: energy.ok_or_else(|| missing("energy"))?,
+                best_board: None,
+            },
+            cooling: cooling.ok_or_else(|| missing("cooling"))?,
+        })
+    }
+}
@@ -0,0 +1,373 @@
+//! The ways the temperature can evolve during an anneal, and their state as
+//! the anneal runs; the fixed schedules themselves, and their parser, are in
+//! [`crate::schedule`].
+
+use crate::schedule::Schedule;
+
+/// A schedule that, instead of following a fixed ladder, adjusts the
+/// temperature on the fly so that the observed acceptance ratio follows Lam's
+/// target curve: it quickly falls from 1 to 0.44, stays there for about half
+/// of the run, and then decays towards zero.
+///
+/// See Boyan, J. A., Learning Evaluation Functions for Global Optimization
+/// (1998), for the formulation used here.
+#[derive(Clone)]
+pub struct AdaptiveSchedule {
+    pub initial_temperature: f64,
+    /// Total number of iterations of the run.
+    pub iterations: usize,
+}
+
+impl AdaptiveSchedule {
+    pub fn new(iterations: usize) -> Self {
+        AdaptiveSchedule {
+            initial_temperature: 1.,
+            iterations,
+        }
+    }
+
+    /// Lam's target acceptance ratio, after a `fraction` of the run.
+    fn target_acceptance(&self, fraction: f64) -> f64 {
+        if fraction < 0.15 {
+            0.44 + 0.56 * 560_f64.powf(-fraction / 0.15)
+        } else if fraction < 0.65 {
+            0.44
+        } else {
+            0.44 * 440_f64.powf(-(fraction - 0.65) / 0.35)
+        }
+    }
+}
+
+/// A geometric schedule whose cooling rate adapts to the acceptance ratio.
+///
+/// The temperature is held for `rounds_per_step` iterations, and then
+/// multiplied by the current rate. If the fraction of moves accepted during
+/// the step fell below `low_acceptance`, the rate is brought closer to 1 (so
+/// cooling slows down); if it was above `high_acceptance`, the rate is moved
+/// away from 1 (so cooling speeds up).
+#[derive(Clone)]
+pub struct RateAdaptiveSchedule {
+    pub initial_temperature: f64,
+    pub initial_rate: f64,
+    pub rounds_per_step: usize,
+    /// Total number of iterations of the run.
+    pub iterations: usize,
+    pub low_acceptance: f64,
+    pub high_acceptance: f64,
+}
+
+impl RateAdaptiveSchedule {
+    pub fn new(iterations: usize, low_acceptance: f64, high_acceptance: f64) -> Self {
+        RateAdaptiveSchedule {
+            initial_temperature: 1.,
+            initial_rate: 0.99,
+            rounds_per_step: 100,
+            iterations,
+            low_acceptance,
+            high_acceptance,
+        }
+    }
+}
+
+/// How the temperature evolves during an anneal.
+#[derive(Clone)]
+pub enum Cooling {
+    Fixed(Schedule),
+    Adaptive(AdaptiveSchedule),
+    RateAdaptive(RateAdaptiveSchedule),
+}
+
+impl From<Schedule> for Cooling {
+    fn from(schedule: Schedule) -> Self {
+        Cooling::Fixed(schedule)
+    }
+}
+
+impl From<AdaptiveSchedule> for Cooling {
+    fn from(schedule: AdaptiveSchedule) -> Self {
+        Cooling::Adaptive(schedule)
+    }
+}
+
+impl From<RateAdaptiveSchedule> for Cooling {
+    fn from(schedule: RateAdaptiveSchedule) -> Self {
+        Cooling::RateAdaptive(schedule)
+    }
+}
+
+impl Cooling {
+    pub fn total_iterations(&self) -> usize {
+        match self {
+            Cooling::Fixed(schedule) => schedule.total_iterations(),
+            Cooling::Adaptive(schedule) => schedule.iterations,
+            Cooling::RateAdaptive(schedule) => schedule.iterations,
+        }
+    }
+
+    pub fn start(self) -> CoolingRun {
+        match self {
+            Cooling::Fixed(schedule) => CoolingRun::Fixed {
+                run: Box::new(schedule.into_run()),
+                iteration: 0,
+            },
+            Cooling::Adaptive(schedule) => CoolingRun::Adaptive {
+                temperature: schedule.initial_temperature,
+                acceptance: 0.5,
+                iteration: 0,
+                schedule,
+            },
+            Cooling::RateAdaptive(schedule) => CoolingRun::RateAdaptive {
+                temperature: schedule.initial_temperature,
+                rate: schedule.initial_rate,
+                step_accepted: 0,
+                step_iterations: 0,
+                iteration: 0,
+                schedule,
+            },
+        }
+    }
+
+    /// Picks the run back up from where `state` was taken, or returns `None`
+    /// if `state` was taken from a different kind of cooling.
+    pub fn resume(self, state: &CoolingState) -> Option<CoolingRun> {
+        let mut run = self.start();
+        match (&mut run, state) {
+            (
+                CoolingRun::Fixed { run, iteration },
+                CoolingState::Fixed {
+                    iteration: resumed_iteration,
+                },
+            ) => {
+                if *resumed_iteration > 0 {
+                    run.nth(*resumed_iteration - 1);
+                }
+                *iteration = *resumed_iteration;
+            }
+            (
+                CoolingRun::Adaptive {
+                    temperature,
+                    acceptance,
+                    iteration,
+                    ..
+                },
+                CoolingState::Adaptive {
+                    temperature: resumed_temperature,
+                    acceptance: resumed_acceptance,
+                    iteration: resumed_iteration,
+                },
+            ) => {
+                *temperature = *resumed_temperature;
+                *acceptance = *resumed_acceptance;
+                *iteration = *resumed_iteration;
+            }
+            (
+                CoolingRun::RateAdaptive {
+                    temperature,
+                    rate,
+                    step_accepted,
+                    step_iterations,
+                    iteration,
+                    ..
+                },
+                CoolingState::RateAdaptive {
+                    temperature: resumed_temperature,
+                    rate: resumed_rate,
+                    step_accepted: resumed_step_accepted,
+                    step_iterations: resumed_step_iterations,
+                    iteration: resumed_iteration,
+                },
+            ) => {
+                *temperature = *resumed_temperature;
+                *rate = *resumed_rate;
+                *step_accepted = *resumed_step_accepted;
+                *step_iterations = *resumed_step_iterations;
+                *iteration = *resumed_iteration;
+            }
+            _ => return None,
+        }
+        Some(run)
+    }
+}
+
+/// Where a [`Cooling`] is at during an anneal, so that it can be resumed
+/// from there.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CoolingState {
+    Fixed {
+        /// Number of temperatures of the schedule used so far.
+        iteration: usize,
+    },
+    Adaptive {
+        temperature: f64,
+        acceptance: f64,
+        iteration: usize,
+    },
+    RateAdaptive {
+        temperature: f64,
+        rate: f64,
+        step_accepted: usize,
+        step_iterations: usize,
+        iteration: usize,
+    },
+}
+
+/// The state of a [`Cooling`] during an anneal.
+pub enum CoolingRun {
+    Fixed {
+        run: Box<dyn Iterator<Item = f64>>,
+        iteration: usize,
+    },
+    Adaptive {
+        schedule: AdaptiveSchedule,
+        temperature: f64,
+        /// Running average of the acceptance ratio.
+        acceptance: f64,
+        iteration: usize,
+    },
+    RateAdaptive {
+        schedule: RateAdaptiveSchedule,
+        temperature: f64,
+        rate: f64,
+        /// Accepted moves and iterations so far in the current step.
+        step_accepted: usize,
+        step_iterations: usize,
+        iteration: usize,
+    },
+}
+
+impl CoolingRun {
+    /// The number of iterations that make up one temperature step, if steps
+    /// are not simply delimited by changes in temperature.
+    ///
+    /// The adaptive schedule changes its temperature on every iteration, so
+    /// its steps are instead taken to be as long as its acceptance window.
+    pub fn step_length(&self) -> Option<usize> {
+        match self {
+            CoolingRun::Fixed { .. } => None,
+            CoolingRun::Adaptive { .. } => Some(500),
+            CoolingRun::RateAdaptive { schedule, .. } => Some(schedule.rounds_per_step),
+        }
+    }
+
+    pub fn next_temperature(&mut self) -> Option<f64> {
+        match self {
+            CoolingRun::Fixed { run, iteration } => {
+                let temperature = run.next();
+                if temperature.is_some() {
+                    *iteration += 1;
+                }
+                temperature
+            }
+            CoolingRun::Adaptive {
+                schedule,
+                temperature,
+                iteration,
+                ..
+            } => {
+                if *iteration >= schedule.iterations {
+                    None
+                } else {
+                    Some(*temperature)
+                }
+            }
+            CoolingRun::RateAdaptive {
+                schedule,
+                temperature,
+                iteration,
+                ..
+            } => {
+                if *iteration >= schedule.iterations {
+                    None
+                } else {
+                    Some(*temperature)
+                }
+            }
+        }
+    }
+
+    /// A snapshot of where the run is at.
+    pub fn state(&self) -> CoolingState {
+        match self {
+            CoolingRun::Fixed { iteration, .. } => CoolingState::Fixed {
+                iteration: *iteration,
+            },
+            CoolingRun::Adaptive {
+                temperature,
+                acceptance,
+                iteration,
+                ..
+            } => CoolingState::Adaptive {
+                temperature: *temperature,
+                acceptance: *acceptance,
+                iteration: *iteration,
+            },
+            CoolingRun::RateAdaptive {
+                temperature,
+                rate,
+                step_accepted,
+                step_iterations,
+                iteration,
+                ..
+            } => CoolingState::RateAdaptive {
+                temperature: *temperature,
+                rate: *rate,
+                step_accepted: *step_accepted,
+                step_iterations: *step_iterations,
+                iteration: *iteration,
+            },
+        }
+    }
+
+    /// Tell the schedule whether the last proposed move was accepted.
+    pub fn record(&mut self, accepted: bool) {
+        match self {
+            CoolingRun::Fixed { .. } => {}
+            CoolingRun::Adaptive {
+                schedule,
+                temperature,
+                acceptance,
+                iteration,
+            } => {
+                // Exponential moving average over roughly the last 500 moves.
+                let accepted = if accepted { 1. } else { 0. };
+                *acceptance = *acceptance * (1. - 1. / 500.) + accepted / 500.;
+
+                let fraction = *iteration as f64 / schedule.iterations as f64;
+                if *acceptance > schedule.target_acceptance(fraction) {
+                    *temperature *= 0.999;
+                } else {
+                    *temperature /= 0.999;
+                }
+
+                *iteration += 1;
+            }
+            CoolingRun::RateAdaptive {
+                schedule,
+                temperature,
+                rate,
+                step_accepted,
+                step_iterations,
+                iteration,
+            } => {
+                *iteration += 1;
+                *step_iterations += 1;
+                if accepted {
+                    *step_accepted += 1;
+                }
+
+                if *step_iterations >= schedule.rounds_per_step {
+                    let acceptance = *step_accepted as f64 / *step_iterations as f64;
+                    // Halving or doubling the distance of the rate to 1
+                    if acceptance < schedule.low_acceptance {
+                        *rate = 1. - (1. - *rate) / 2.;
+                    } else if acceptance > schedule.high_acceptance {
+                        *rate = (1. - (1. - *rate) * 2.).max(0.5);
+                    }
+                    *temperature *= *rate;
+                    *step_accepted = 0;
+                    *step_iterations = 0;
+                }
+            }
+        }
+    }
+}
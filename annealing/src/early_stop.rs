@@ -0,0 +1,50 @@
+use std::time::{Duration, Instant};
+
+/// When an anneal may stop before its schedule runs out, reporting how far it
+/// got, rather than being cancelled outright like when its
+/// [`Budget`](crate::Budget) is spent.
+///
+/// By default, the anneal never stops early.
+#[derive(Debug, Clone, Default)]
+pub struct EarlyStop {
+    /// Wall-clock time after which the anneal stops at the end of the current
+    /// temperature step, with the lowest-energy state it visited.
+    pub time_limit: Option<Duration>,
+    /// Number of iterations in a row without an accepted move that lowers
+    /// the energy after which the anneal is taken to be stuck, and stops.
+    /// This only happens at low temperatures, where the state is frozen.
+    pub plateau: Option<usize>,
+}
+
+impl EarlyStop {
+    pub fn never() -> Self {
+        Self::default()
+    }
+
+    pub fn with_time_limit(mut self, time_limit: Duration) -> Self {
+        self.time_limit = Some(time_limit);
+        self
+    }
+
+    pub fn with_plateau(mut self, plateau: usize) -> Self {
+        self.plateau = Some(plateau);
+        self
+    }
+
+    /// Whether the anneal is stuck, given how many iterations it has done
+    /// since a move that lowered the energy was last accepted.
+    pub fn is_on_plateau(&self, iterations_since_improvement: usize) -> bool {
+        match self.plateau {
+            Some(plateau) => iterations_since_improvement >= plateau,
+            None => false,
+        }
+    }
+
+    /// Whether the time limit has been reached, given when the anneal started.
+    pub fn is_past_time_limit(&self, started: &Instant) -> bool {
+        match self.time_limit {
+            Some(time_limit) => started.elapsed() >= time_limit,
+            None => false,
+        }
+    }
+}
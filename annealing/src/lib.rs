@@ -0,0 +1,28 @@
+//! Simulated annealing solver for sudoku, as a library.
+//!
+//! The `annealing` binary is a thin command line wrapper around [`anneal`].
+
+pub mod acceptance;
+pub mod checkpoint;
+pub mod cooling;
+pub mod early_stop;
+pub mod moves;
+pub mod progress;
+pub mod rng;
+pub mod solver;
+pub mod weights;
+
+pub use acceptance::Acceptance;
+pub use checkpoint::Checkpoint;
+pub use cooling::{AdaptiveSchedule, Cooling, CoolingRun, CoolingState, RateAdaptiveSchedule};
+pub use early_stop::EarlyStop;
+pub use moves::Moves;
+pub use progress::{CsvTrace, Iteration, LiveProgress, Progress, TemperatureStep};
+pub use rng::{Rng, WyRand};
+pub use solver::{
+    anneal, anneal_best_of, anneal_chains, anneal_checkpointed, auto_schedule, descend,
+    rate_schedule, Annealer, SolveError, SolveStats,
+};
+pub use sudoku::parsing::schedule::{self, Schedule, ScheduleBuilder};
+pub use sudoku::{Budget, NoProgress, ProgressSink};
+pub use weights::Weights;
@@ -1,28 +1,122 @@
-use schedule::Schedule;
-use solver::SolveError;
-use std::path::PathBuf;
+use annealing::schedule::{self, Schedule};
+use annealing::{
+    rng, Acceptance, AdaptiveSchedule, Budget, Checkpoint, Cooling, CsvTrace, EarlyStop,
+    LiveProgress, Moves, NoProgress, Progress, ProgressSink, RateAdaptiveSchedule, Rng, Weights,
+    WyRand,
+};
+use annealing::solver::{self, SolveError, SolveStats};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use sudoku::constraints::Constraints;
 use sudoku::*;
 
-mod schedule;
-mod solver;
-
 const HEADER: &'static str = r#"annealing solver for sudoku
 "#;
 
 const USAGE: &'static str = r#"
 Usage:
-    annealing <input file> <schedule file> [<init file>]
+    annealing [--constraints <file>] [--non-consecutive] [--verify]
+              [--seed <n>] [--chains <n>]
+              <input file> [<schedule file> [<init file>]]
+    annealing [--constraints <file>] [--non-consecutive] [--verify]
+              [--seed <n>] [--chains <n>]
+              --adaptive <iterations> [--acceptance <low>,<high>]
+              <input file> [<init file>]
+    annealing [--constraints <file>] [--non-consecutive] [--verify]
+              [--seed <n>] [--chains <n>]
+              (--auto-schedule | --rate <rate>) <input file> [<init file>]
+    annealing [--constraints <file>] [--non-consecutive] [--verify]
+              [--seed <n>] [--jobs <n>]
+              [--adaptive <iterations> | --auto-schedule | --rate <rate>]
+              (<directory> | <.sdm file>) [<schedule file>]
     annealing --help
 
 Options:
+    --input <file>      The input file, or collection of puzzles.
+    --schedule <file>   The schedule file.
+    --init <file>       The init (hint) file, whose digits are kept fixed.
+    --adaptive <n>      Anneal for n iterations with an adaptive schedule,
+                        instead of following a schedule file.
+    --auto-schedule     Generate a schedule for the input, instead of
+                        following a schedule file.
+    --rounds-scale <kind>
+                        Scale the rounds of each temperature of the schedule
+                        file to the input, by its number of cells, with
+                        "cells", or of free cells, with "free".
+    --rate <rate>       Like --auto-schedule, but lower the temperature by a
+                        fraction rate of itself after every iteration (e.g.,
+                        0.001).
+    --seed <n>          Seed for the random number generator. Runs with the
+                        same inputs and seed are identical.
+    --acceptance <low>,<high>
+                        With --adaptive, cool in steps, and adapt the
+                        cooling rate to keep the acceptance ratio of each
+                        step between low and high.
+    --moves <kind>      Which moves to propose; either "global" (the
+                        default), to swap any two free cells, "box", to
+                        only swap free cells within the same box, or
+                        "conflicted", to swap a free cell that is in
+                        conflict with any other free cell.
+    --acceptance-rule <rule>
+                        Which moves to accept; either "metropolis" (the
+                        default) or "glauber". See below.
+    --weights <row>,<column>,<box>[,<variant>]
+                        How much a conflict in a row, column or box, or a
+                        broken variant constraint, counts towards the energy
+                        (by default, 1,1,1,1).
+    --chains <n>        Run n independent anneals in parallel, and report
+                        the first solution, or the lowest-energy state.
+    --trace <file>      Write the iteration, temperature, energy, and whether
+                        the move was accepted, for every iteration, to file,
+                        as CSV.
+    --stats             Print statistics about the anneal to stderr.
+    --progress          Keep a line on stderr up to date with the progress of
+                        the anneal.
+    --best-of <n>       Anneal up to n times, one after the other, from
+                        different random initial states, and report the
+                        first solution, or the lowest-energy state.
+    --checkpoint <file> Periodically save the state of the anneal to file.
+    --checkpoint-every <n>
+                        Save a checkpoint every n iterations (by default,
+                        every 1_000_000).
+    --resume <file>     Continue the anneal saved in the checkpoint file.
+    --glass-out <file>  On GLASS, write the final state to file, instead of
+                        to stdout.
+    --show-best         On GLASS, also print the lowest-energy state visited.
+    --time-limit <s>    Stop after s seconds, at the end of the current
+                        temperature step, and report the best state found.
+    --plateau <n>       Stop once no move that lowers the energy has been
+                        accepted for n iterations.
+    --jobs <n>          With a collection of puzzles, anneal n puzzles at a
+                        time, in parallel.
+    --descend <n>       On GLASS, descend greedily from the final state,
+                        making up to n moves that keep the energy the same.
     --help              Print help information.
+    --constraints <file>
+                        Read variant constraints from <file>; each broken
+                        constraint counts towards the energy.
+    --non-consecutive   Forbid consecutive digits in orthogonally adjacent
+                        cells; each such pair counts towards the energy.
+    --verify            Check the solution against the input before reporting
+                        success.
 "#;
 
 const LONG_HELP: &'static str = concat!(
     r#"
 An input file of "-" denotes the input data should be read from the standard
-input. The schedule file is expected to be in .schedule format, and the input
-file and init file are expected to be in .soduku format.
+input. The schedule file is expected to be in .schedule format, the input
+file and init file are expected to be in .soduku format, and the constraints
+file in .constraints format.
+
+The input, schedule and init files can also be given with --input, --schedule
+and --init, in any order, in which case the files given without a flag fill in
+the others, in the usual order. This way any one of them can be read from the
+standard input, e.g., with
+
+    annealing --schedule slow.schedule --init hint.sudoku -
+
+Only one file can be read from the standard input.
 
 If the annealing is successfully carried out, the program will print to stdout
 a single line denoting the success of the anneal, followed by the final state in
@@ -32,17 +126,187 @@ The success messages can be
 
     SUCCESS     The .sudoku below is a solution to the given input.
     GLASS       The state was cooled into an invalid state, given below.
+    TIMEOUT     The time limit was reached before a solution was found; the
+                lowest-energy state visited is given below.
+    PLATEAU     The energy stopped going down, with --plateau, and the anneal
+                was stopped early, in the invalid state given below.
+
+With --verify, a solution is checked once more before SUCCESS is printed: it
+must be complete, agree with every clue, and break none of the rules. If it
+does not, what is wrong with it is reported to stderr, along with the board,
+and the program exits with code 1.
+
+With --constraints, every constraint of the file that the state breaks counts
+towards the energy, like a conflict, and with --non-consecutive, so does every
+pair of orthogonally adjacent cells that hold consecutive digits, so that the
+anneal looks for states that follow these rules too. A final state that breaks
+any of them is reported as GLASS. With a collection of puzzles, the constraints
+apply to every puzzle.
+
+With --descend, an anneal that ends in GLASS is followed by a descent at zero
+temperature: moves that lower the energy are made, in a random order, until
+there are none, and then up to the given number of moves that leave the energy
+as it is, to get out of a plateau, each followed by descending again. If this
+reaches a solution, SUCCESS is reported instead.
+
+With --plateau, the anneal is stopped as soon as no move that lowers the energy
+has been accepted for the given number of iterations in a row. While the
+temperature is high, the energy keeps going up and down, so this only happens
+once the state has frozen at a low temperature, where the rest of the schedule
+would most likely be spent without getting anywhere. The result is then
+reported as PLATEAU, and is otherwise treated like GLASS (e.g., by --descend,
+--glass-out and --show-best).
+
+With --glass-out, the state reached on GLASS is instead written to the given
+file, and only the status line is printed, so that the file can be given as the
+hint file of a follow-up run.
+
+Since moves that make the state worse are accepted along the way, the final
+state can be worse than states visited before it. With --show-best, GLASS is
+followed by a line
+
+    BEST <energy>
+
+and the lowest-energy state visited, with that energy, in .sudoku format, after
+the final state (or, with --glass-out, after the status line). On TIMEOUT, the
+state reported already is the lowest-energy one.
+
+The hint file, if provided, fills in some of the cells the input leaves empty,
+which are then kept fixed, like the clues, and only the cells the hint leaves
+empty are annealed, starting from a random state. It follows that the hint file
+must agree with the input file on the numerical clues, and must be feasible.
+
+If no schedule file is given, a default schedule for the board's size is used.
+These defaults are tuned for 4x4, 9x9 and 16x16 boards; the 9x9 default is the
+equivalent of
+
+    geometric 20.0 0.99 500 200
+
+With --adaptive, the temperature is not given by a schedule file, but adjusted
+during the anneal so that the fraction of accepted moves follows a fixed target
+curve (Lam's schedule). This only requires choosing the length of the run.
+If --acceptance is also given, the temperature is instead lowered
+geometrically, every 100 iterations, and the cooling rate is slowed down when
+fewer than <low> of the moves in those 100 iterations were accepted, or sped up
+when more than <high> were.
+
+With --auto-schedule, a geometric schedule is generated for the input. Its
+initial temperature is estimated from random moves, so that about 80% of the
+moves that make the state worse would be accepted; it then cools at a rate of
+0.99, with steps whose length scales with the size of the board, until even the
+smallest such moves would be accepted only about 0.1% of the time. With --rate,
+the temperature instead goes down between the same two temperatures after every
+iteration, by the given fraction of itself, so that a smaller rate makes for a
+longer anneal.
+
+A schedule file can be made to serve boards of any size with --rounds-scale.
+The rounds of each of its temperatures are then taken to be those for 81 cells,
+and are multiplied by the number of cells of the input over 81, with "cells", or
+by its number of free cells over 81, with "free" (rounding up). For example,
+with "cells", a 16x16 board gets 256/81 times as many rounds, and a 9x9 board
+the rounds of the file as written.
 
-The hint file, if provided, tells the annealer in what state to begin the
-annealing. It follows that the hint file must agree with the input file on the
-numerical clues, and must be feasible. Furthermore, hint inputs cannot contain
-empty spaces.
+With --moves box, each box is first filled with a permutation of the digits,
+and only cells in the same box are swapped, so the box constraints are never
+violated. This explores a much smaller space of states than the default global
+moves, where only the count of each digit is kept fixed. A hint file given with
+--moves box must not repeat a digit within a box.
+
+With --moves conflicted, the board is initialized as with the default global
+moves, but the first cell of each swap is picked with a probability proportional
+to the number of conflicts it is in, and only the second one uniformly, which
+spends most of the moves where the violations are. Free cells that are not in
+conflict are only moved as the second cell of a swap.
+
+With the default --acceptance-rule metropolis, a move that does not make the
+state worse is always accepted, and one that raises the energy by dE is
+accepted with probability exp(-dE / T), at temperature T. With glauber (the
+heat-bath rule), any move is accepted with probability 1 / (1 + exp(dE / T)),
+so that moves that make the state better are also sometimes rejected, and a
+move that leaves the energy as it is is accepted half of the time.
+
+The statistics printed with --stats are those of the reported chain, if
+--chains is given. Energies are the number of conflicts of each cell, summed
+over all cells.
+
+With --weights, a conflict counts towards the energy with the weight of the
+unit the two cells share, or with the largest weight, if they share both a row
+or column and a box. The optional fourth weight is that of each violation of a
+variant constraint, such as --non-consecutive. Making, say, boxes heavier than
+rows and columns changes which states the anneal tends to get stuck in. Since energies are scaled by the
+weights, so are the temperatures that suit them: --auto-schedule and --rate take
+the weights into account, but a schedule file may need adjusting.
+
+The trace written with --trace has a header line, followed by one record per
+iteration, of the form
+
+    iteration,temperature,energy,accepted
+
+where energy is the number of violations after the iteration, and accepted is 1
+if the proposed move was accepted, and 0 otherwise. Traces can get large, since
+a record is written for every iteration. --trace cannot be used with --chains
+or --best-of.
+
+The line kept up to date with --progress shows the current temperature, how
+much of the schedule has been run, the current and lowest energies so far, and
+the fraction of the moves accepted since the line was last updated, a few times
+a second. --progress cannot be used with --chains, --best-of, --trace or a
+collection of puzzles.
+
+With --checkpoint, the board, the state of the random number generator and of
+the schedule, and the statistics so far, are written to the given file every so
+often, so that an interrupted anneal can be continued with --resume. The resumed
+run must be given the same input and schedule options as the original run (and
+the same --seed, with --auto-schedule or --rate, and the same hint file, if
+any). Resuming and checkpointing can be combined, and the same file can be used
+for both.
+Checkpoints cannot be used with --chains or --best-of.
+
+Instead of a single puzzle, every .sudoku file in a directory, or every puzzle
+in an .sdm collection, can be annealed in turn. The .sdm format has one puzzle
+per line, written as its cells in reading order, without separators, and with
+"." or "0" for empty cells. The schedule is read once, and used for every
+puzzle; without one, each puzzle gets the default schedule for its size, or its
+own schedule with --auto-schedule or --rate. Each puzzle is annealed with its
+own seed, drawn from --seed, so that the results do not depend on --jobs. A CSV
+record is then written to stdout for each puzzle, in order, after a header line,
+
+    puzzle,status,seed,energy,iterations,seconds,board
+
+where status is SUCCESS, GLASS, TIMEOUT or PLATEAU as above, or ERROR if the
+puzzle could not be annealed, or, with --verify, if its solution is wrong (what
+went wrong is then reported to stderr, and the batch carries on), seed
+replays the anneal of that puzzle alone, and board is the final state in reading
+order, as in the .sdm format (with cells separated by spaces for boards larger
+than 9 by 9). With --show-best, two more fields, best_energy and best_board,
+give the lowest-energy state visited in the same way. A collection cannot be
+used with a hint file, --chains, --best-of, --trace, checkpoints or --glass-out.
+
+--chains and --best-of both run several anneals, in parallel and one after the
+other respectively, and cannot be used together. If a hint file is given, every
+anneal starts from the hint, and only the cells it leaves empty differ between
+anneals.
 
 The .schedule format describes a cooling schedule for the simulated annealing.
 It consists of plain, UTF-8 encoded text, with an arbitrary number of pairs of
 (floating point, integer) numbers, representing the temperature and number of
 iterations for that temperature.
 Lines beginning with a hash symbol (#) are ignored.
+A line of the form
+
+    geometric <initial temperature> <rate> <steps> <rounds per step>
+
+expands to <steps> temperatures, starting at the initial temperature and each
+being <rate> times the previous one, held for <rounds per step> iterations
+each. The rate must be larger than 0 and at most 1.
+Lines can be repeated by enclosing them in a repeat block,
+
+    repeat <count> {
+        ...
+    }
+
+where the opening and closing braces end and begin a line, respectively.
+Repeat blocks can be nested, e.g., to express heating and cooling cycles.
 Floating point numbers take the format (in loose BNF notation):
 
     float := mantissa exponent
@@ -52,7 +316,9 @@ Floating point numbers take the format (in loose BNF notation):
     decimal ~= \.\d+
 
 "#,
-    include_str!("../../FORMATTING.txt")
+    include_str!("../../FORMATTING.txt"),
+    "\n\n",
+    include_str!("../../CONSTRAINTS.txt")
 );
 
 fn main() {
@@ -60,7 +326,36 @@ fn main() {
 
     let mut schedule: Option<Result<Schedule, String>> = None;
     let mut input: Option<Result<Sudoku, String>> = None;
+    // Only one of the inputs can be read from the standard input
+    let mut stdin_used = false;
+    let mut collection: Option<PathBuf> = None;
     let mut init_hint: Option<Result<Sudoku, String>> = None;
+    let mut constraints_path: Option<PathBuf> = None;
+    let mut non_consecutive = false;
+    let mut adaptive: Option<usize> = None;
+    let mut verify = false;
+    let mut auto_schedule = false;
+    let mut rate: Option<f64> = None;
+    let mut acceptance: Option<(f64, f64)> = None;
+    let mut print_stats = false;
+    let mut show_best = false;
+    let mut show_progress = false;
+    let mut seed: Option<u64> = None;
+    let mut chains: usize = 1;
+    let mut jobs: usize = 1;
+    let mut descend: Option<usize> = None;
+    let mut best_of: usize = 1;
+    let mut trace: Option<PathBuf> = None;
+    let mut glass_out: Option<PathBuf> = None;
+    let mut time_limit: Option<Duration> = None;
+    let mut plateau: Option<usize> = None;
+    let mut checkpoint: Option<PathBuf> = None;
+    let mut checkpoint_every: usize = 1_000_000;
+    let mut resume: Option<PathBuf> = None;
+    let mut moves = Moves::Global;
+    let mut acceptance_rule = Acceptance::Metropolis;
+    let mut weights = Weights::default();
+    let mut rounds_scale: Option<RoundsScale> = None;
 
     while let Some(arg) = args.next() {
         match arg.as_str() {
@@ -70,40 +365,288 @@ fn main() {
                 println!("{}", LONG_HELP);
                 std::process::exit(0);
             }
-            "-" => {
-                if input.is_none() {
-                    input = Some(parsing::sudoku::parse(std::io::stdin()));
-                } else if schedule.is_none() {
-                    schedule = Some(schedule::parse(std::io::stdin()));
-                } else if init_hint.is_none() {
-                    init_hint = Some(parsing::sudoku::parse(std::io::stdin()))
-                } else {
-                    eprintln!("Too many arguments!");
+            "--non-consecutive" => {
+                non_consecutive = true;
+            }
+            "--constraints" => {
+                constraints_path = Some(PathBuf::from(named_path(&arg, args.next())));
+            }
+            "--verify" => {
+                verify = true;
+            }
+            "--stats" => print_stats = true,
+            "--show-best" => show_best = true,
+            "--progress" => show_progress = true,
+            "--acceptance" => {
+                let targets = args.next().and_then(|targets| {
+                    let (low, high) = targets.split_once(',')?;
+                    Some((low.parse::<f64>().ok()?, high.parse::<f64>().ok()?))
+                });
+                match targets {
+                    Some((low, high)) if 0. <= low && low <= high && high <= 1. => {
+                        acceptance = Some((low, high))
+                    }
+                    _ => {
+                        eprintln!(concat!(
+                            "Expected two acceptance ratios, low and high, ",
+                            "separated by a comma, after --acceptance."
+                        ));
+                        eprintln!("{}", USAGE);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            "--moves" => match args.next().as_deref() {
+                Some("global") => moves = Moves::Global,
+                Some("box") => moves = Moves::Box,
+                Some("conflicted") => moves = Moves::Conflicted,
+                _ => {
+                    eprintln!("Expected \"global\", \"box\" or \"conflicted\" after --moves.");
                     eprintln!("{}", USAGE);
                     std::process::exit(1);
                 }
+            },
+            "--weights" => {
+                let parsed = args.next().and_then(|weights| {
+                    let weights = weights
+                        .split(',')
+                        .map(|weight| weight.parse::<usize>().ok().filter(|&w| w > 0))
+                        .collect::<Option<Vec<_>>>()?;
+                    let (row, column, box_, variant) = match weights[..] {
+                        [row, column, box_] => (row, column, box_, 1),
+                        [row, column, box_, variant] => (row, column, box_, variant),
+                        _ => return None,
+                    };
+                    Some(Weights {
+                        row,
+                        column,
+                        box_,
+                        variant,
+                    })
+                });
+                match parsed {
+                    Some(parsed) => weights = parsed,
+                    None => {
+                        eprintln!(concat!(
+                            "Expected three or four positive weights, for rows, columns, boxes ",
+                            "and variant constraints, separated by commas, after --weights."
+                        ));
+                        eprintln!("{}", USAGE);
+                        std::process::exit(1);
+                    }
+                }
             }
-            path => {
-                let path = PathBuf::from(path);
-                let path_as_str = path.clone().to_string_lossy().to_string();
-                if !path.exists() {
-                    eprintln!("{} does not exist.", &path_as_str);
+            "--rounds-scale" => match args.next().as_deref() {
+                Some("cells") => rounds_scale = Some(RoundsScale::Cells),
+                Some("free") => rounds_scale = Some(RoundsScale::Free),
+                _ => {
+                    eprintln!("Expected \"cells\" or \"free\" after --rounds-scale.");
+                    eprintln!("{}", USAGE);
                     std::process::exit(1);
                 }
-
-                let reader = std::fs::File::open(path);
-                if let Err(e) = reader {
+            },
+            "--acceptance-rule" => match args.next().as_deref() {
+                Some("metropolis") => acceptance_rule = Acceptance::Metropolis,
+                Some("glauber") => acceptance_rule = Acceptance::Glauber,
+                _ => {
+                    eprintln!("Expected \"metropolis\" or \"glauber\" after --acceptance-rule.");
+                    eprintln!("{}", USAGE);
+                    std::process::exit(1);
+                }
+            },
+            "--checkpoint" => match args.next() {
+                Some(path) => checkpoint = Some(PathBuf::from(path)),
+                None => {
+                    eprintln!("Expected a file after --checkpoint.");
+                    eprintln!("{}", USAGE);
+                    std::process::exit(1);
+                }
+            },
+            "--checkpoint-every" => {
+                match args.next().map(|n| n.replace('_', "").parse::<usize>()) {
+                    Some(Ok(n)) if n > 0 => checkpoint_every = n,
+                    _ => {
+                        eprintln!(
+                            "Expected a positive number of iterations after --checkpoint-every."
+                        );
+                        eprintln!("{}", USAGE);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            "--resume" => match args.next() {
+                Some(path) => resume = Some(PathBuf::from(path)),
+                None => {
+                    eprintln!("Expected a checkpoint file after --resume.");
+                    eprintln!("{}", USAGE);
+                    std::process::exit(1);
+                }
+            },
+            "--glass-out" => match args.next() {
+                Some(path) => glass_out = Some(PathBuf::from(path)),
+                None => {
+                    eprintln!("Expected a file after --glass-out.");
+                    eprintln!("{}", USAGE);
+                    std::process::exit(1);
+                }
+            },
+            "--time-limit" => match args.next().map(|s| s.replace('_', "").parse::<f64>()) {
+                Some(Ok(seconds)) if seconds > 0. && seconds.is_finite() => {
+                    time_limit = Some(Duration::from_secs_f64(seconds))
+                }
+                _ => {
+                    eprintln!("Expected a positive number of seconds after --time-limit.");
+                    eprintln!("{}", USAGE);
+                    std::process::exit(1);
+                }
+            },
+            "--plateau" => match args.next().map(|n| n.replace('_', "").parse::<usize>()) {
+                Some(Ok(n)) if n > 0 => plateau = Some(n),
+                _ => {
+                    eprintln!("Expected a positive number of iterations after --plateau.");
+                    eprintln!("{}", USAGE);
+                    std::process::exit(1);
+                }
+            },
+            "--trace" => match args.next() {
+                Some(path) => trace = Some(PathBuf::from(path)),
+                None => {
+                    eprintln!("Expected a file after --trace.");
+                    eprintln!("{}", USAGE);
+                    std::process::exit(1);
+                }
+            },
+            "--chains" => match args.next().map(|n| n.replace('_', "").parse::<usize>()) {
+                Some(Ok(n)) if n > 0 => chains = n,
+                _ => {
+                    eprintln!("Expected a positive number of chains after --chains.");
+                    eprintln!("{}", USAGE);
+                    std::process::exit(1);
+                }
+            },
+            "--jobs" => match args.next().map(|n| n.replace('_', "").parse::<usize>()) {
+                Some(Ok(n)) if n > 0 => jobs = n,
+                _ => {
+                    eprintln!("Expected a positive number of jobs after --jobs.");
+                    eprintln!("{}", USAGE);
+                    std::process::exit(1);
+                }
+            },
+            "--best-of" => match args.next().map(|n| n.replace('_', "").parse::<usize>()) {
+                Some(Ok(n)) if n > 0 => best_of = n,
+                _ => {
+                    eprintln!("Expected a positive number of anneals after --best-of.");
+                    eprintln!("{}", USAGE);
+                    std::process::exit(1);
+                }
+            },
+            "--seed" => match args.next().map(|n| n.replace('_', "").parse::<u64>()) {
+                Some(Ok(n)) => seed = Some(n),
+                _ => {
+                    eprintln!("Expected a seed after --seed.");
+                    eprintln!("{}", USAGE);
+                    std::process::exit(1);
+                }
+            },
+            "--auto-schedule" => {
+                if schedule.is_some() {
+                    eprintln!("--auto-schedule cannot be used with a schedule file.");
+                    eprintln!("{}", USAGE);
+                    std::process::exit(1);
+                }
+                auto_schedule = true;
+            }
+            "--rate" => {
+                if schedule.is_some() {
+                    eprintln!("--rate cannot be used with a schedule file.");
+                    eprintln!("{}", USAGE);
+                    std::process::exit(1);
+                }
+                match args.next().map(|r| r.parse::<f64>()) {
+                    Some(Ok(r)) if 0. < r && r < 1. => rate = Some(r),
+                    _ => {
+                        eprintln!("Expected a rate between 0 and 1 after --rate.");
+                        eprintln!("{}", USAGE);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            "--descend" => match args.next().map(|n| n.replace('_', "").parse::<usize>()) {
+                Some(Ok(n)) => descend = Some(n),
+                _ => {
+                    eprintln!("Expected a number of sideways moves after --descend.");
+                    eprintln!("{}", USAGE);
+                    std::process::exit(1);
+                }
+            },
+            "--adaptive" => {
+                if schedule.is_some() {
+                    eprintln!("--adaptive cannot be used with a schedule file.");
+                    eprintln!("{}", USAGE);
+                    std::process::exit(1);
+                }
+                let iterations = args.next().map(|n| n.replace('_', "").parse::<usize>());
+                match iterations {
+                    Some(Ok(iterations)) => adaptive = Some(iterations),
+                    _ => {
+                        eprintln!("Expected a number of iterations after --adaptive.");
+                        eprintln!("{}", USAGE);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            "--input" => {
+                let path = named_path(&arg, args.next());
+                if input.is_some() || collection.is_some() {
+                    eprintln!("The input was given more than once.");
+                    eprintln!("{}", USAGE);
+                    std::process::exit(1);
+                }
+                if is_collection(&path) {
+                    collection = Some(PathBuf::from(path));
+                } else {
+                    input = Some(parsing::sudoku::parse(open_input(&path, &mut stdin_used)));
+                }
+            }
+            "--schedule" => {
+                let path = named_path(&arg, args.next());
+                if schedule.is_some() {
+                    eprintln!("The schedule was given more than once.");
+                    eprintln!("{}", USAGE);
+                    std::process::exit(1);
+                }
+                if adaptive.is_some() || auto_schedule || rate.is_some() {
                     eprintln!(
-                        "Could not open {} for reading.\nWith error {}",
-                        &path_as_str, e
+                        "--schedule cannot be used with --adaptive, --auto-schedule or --rate."
                     );
+                    eprintln!("{}", USAGE);
+                    std::process::exit(1);
+                }
+                schedule = Some(schedule::parse(open_input(&path, &mut stdin_used)));
+            }
+            "--init" => {
+                let path = named_path(&arg, args.next());
+                if init_hint.is_some() {
+                    eprintln!("The init file was given more than once.");
+                    eprintln!("{}", USAGE);
                     std::process::exit(1);
                 }
-                let reader = reader.unwrap();
+                init_hint = Some(parsing::sudoku::parse(open_input(&path, &mut stdin_used)));
+            }
+            path => {
+                if is_collection(path) && input.is_none() && collection.is_none() {
+                    collection = Some(PathBuf::from(path));
+                    continue;
+                }
 
-                if input.is_none() {
+                let reader = open_input(path, &mut stdin_used);
+                if input.is_none() && collection.is_none() {
                     input = Some(parsing::sudoku::parse(reader));
-                } else if schedule.is_none() {
+                } else if schedule.is_none()
+                    && adaptive.is_none()
+                    && !auto_schedule
+                    && rate.is_none()
+                {
                     schedule = Some(schedule::parse(reader));
                 } else if init_hint.is_none() {
                     init_hint = Some(parsing::sudoku::parse(reader))
@@ -117,19 +660,117 @@ fn main() {
     }
 
     let schedule = match schedule {
-        Some(Ok(schedule)) => schedule,
+        Some(Ok(schedule)) => Some(schedule),
         Some(Err(e)) => {
             eprintln!("Schedule format malformed.");
             eprintln!("{}", e);
             std::process::exit(1);
         }
-        None => {
-            eprintln!("No schedule file specified.");
-            eprintln!("{}", USAGE);
+        None => None,
+    };
+
+    if adaptive.is_some() && auto_schedule {
+        eprintln!("--adaptive cannot be used with --auto-schedule.");
+        eprintln!("{}", USAGE);
+        std::process::exit(1);
+    }
+
+    if rate.is_some() && (adaptive.is_some() || auto_schedule) {
+        eprintln!("--rate cannot be used with --adaptive or --auto-schedule.");
+        eprintln!("{}", USAGE);
+        std::process::exit(1);
+    }
+
+    if rounds_scale.is_some() && schedule.is_none() {
+        eprintln!("--rounds-scale can only be used with a schedule file.");
+        eprintln!("{}", USAGE);
+        std::process::exit(1);
+    }
+
+    if acceptance.is_some() && adaptive.is_none() {
+        eprintln!("--acceptance can only be used with --adaptive.");
+        eprintln!("{}", USAGE);
+        std::process::exit(1);
+    }
+
+    let choice = match (schedule, adaptive) {
+        (_, Some(iterations)) => CoolingChoice::Given(match acceptance {
+            Some((low, high)) => RateAdaptiveSchedule::new(iterations, low, high).into(),
+            None => AdaptiveSchedule::new(iterations).into(),
+        }),
+        (Some(schedule), None) => match rounds_scale {
+            Some(scale) => CoolingChoice::Scaled(schedule, scale),
+            None => CoolingChoice::Given(schedule.into()),
+        },
+        (None, None) if auto_schedule => CoolingChoice::Auto,
+        (None, None) => match rate {
+            Some(rate) => CoolingChoice::Rate(rate),
+            None => CoolingChoice::Preset,
+        },
+    };
+
+    let init_hint = match init_hint {
+        Some(Ok(hint)) => Some(hint),
+        Some(Err(e)) => {
+            println!("Init board malformed.");
+            println!("{}", e);
             std::process::exit(1);
         }
+        None => None,
     };
 
+    let seed = seed.unwrap_or_else(rng::random_seed);
+
+    let early_stop = match time_limit {
+        Some(time_limit) => EarlyStop::never().with_time_limit(time_limit),
+        None => EarlyStop::never(),
+    };
+    let early_stop = match plateau {
+        Some(plateau) => early_stop.with_plateau(plateau),
+        None => early_stop,
+    };
+
+    if let Some(collection) = collection {
+        let incompatible = init_hint.is_some()
+            || chains > 1
+            || best_of > 1
+            || trace.is_some()
+            || show_progress
+            || checkpoint.is_some()
+            || resume.is_some()
+            || glass_out.is_some();
+        if incompatible {
+            eprintln!(concat!(
+                "A collection of puzzles cannot be used with a hint file, --chains, ",
+                "--best-of, --trace, --progress, checkpoints or --glass-out."
+            ));
+            eprintln!("{}", USAGE);
+            std::process::exit(1);
+        }
+        let puzzles = match parsing::read_collection(&collection) {
+            Ok(puzzles) => puzzles,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        };
+        let side = puzzles.first().map_or(0, |(_, puzzle)| puzzle.side());
+        let constraints = read_constraints(constraints_path, side, non_consecutive);
+        let batch = Batch {
+            choice,
+            constraints,
+            moves,
+            acceptance: acceptance_rule,
+            weights,
+            early_stop,
+            verify,
+            descend,
+            show_best,
+        };
+        batch.run(puzzles, jobs, seed, BufWriter::new(std::io::stdout()));
+        std::process::exit(0);
+    }
+
     let mut input = match input {
         Some(Ok(input)) => input,
         Some(Err(e)) => {
@@ -144,44 +785,632 @@ fn main() {
         }
     };
 
-    let init_hint = match init_hint {
-        Some(Ok(hint)) => Some(hint),
-        Some(Err(e)) => {
-            println!("Init board malformed.");
-            println!("{}", e);
+    let constraints = read_constraints(constraints_path, input.side(), non_consecutive);
+
+    let cooling = match choice.cooling_for(&input, init_hint.clone(), moves, weights, seed) {
+        Ok(cooling) => cooling,
+        Err(e) => {
+            eprintln!("{}", e);
             std::process::exit(1);
         }
-        None => None,
     };
+    match (&choice, &cooling) {
+        (CoolingChoice::Auto | CoolingChoice::Rate(_), Cooling::Fixed(schedule)) => eprintln!(
+            "Generated a schedule from temperature {:.3e} to {:.3e}, over {} iterations.",
+            schedule.temperatures.first().unwrap_or(&0.),
+            schedule.temperatures.last().unwrap_or(&0.),
+            schedule.total_iterations()
+        ),
+        (CoolingChoice::Scaled(..), Cooling::Fixed(schedule)) => eprintln!(
+            "Scaled the rounds of the schedule to {} iterations.",
+            schedule.total_iterations()
+        ),
+        (CoolingChoice::Preset, _) => eprintln!(
+            "No schedule file specified; using the default schedule for side {}.",
+            input.side()
+        ),
+        _ => {}
+    }
+
+    let puzzle = input.clone();
+
+    if chains > 1 && best_of > 1 {
+        eprintln!("--chains cannot be used with --best-of.");
+        eprintln!("{}", USAGE);
+        std::process::exit(1);
+    }
+
+    if (checkpoint.is_some() || resume.is_some()) && (chains > 1 || best_of > 1) {
+        eprintln!("Checkpoints cannot be used with --chains or --best-of.");
+        eprintln!("{}", USAGE);
+        std::process::exit(1);
+    }
+
+    let resume = resume.map(|path| {
+        let checkpoint = std::fs::File::open(&path)
+            .map_err(|e| e.to_string())
+            .and_then(Checkpoint::parse);
+        match checkpoint {
+            Ok(checkpoint) => checkpoint,
+            Err(e) => {
+                eprintln!(
+                    "Could not read the checkpoint {}.\nWith error {}",
+                    path.to_string_lossy(),
+                    e
+                );
+                std::process::exit(1);
+            }
+        }
+    });
+
+    if trace.is_some() && (chains > 1 || best_of > 1) {
+        eprintln!("--trace cannot be used with --chains or --best-of.");
+        eprintln!("{}", USAGE);
+        std::process::exit(1);
+    }
+
+    if show_progress && (chains > 1 || best_of > 1 || trace.is_some()) {
+        eprintln!("--progress cannot be used with --chains, --best-of or --trace.");
+        eprintln!("{}", USAGE);
+        std::process::exit(1);
+    }
+
+    let mut trace = trace.map(|path| {
+        let trace = std::fs::File::create(&path)
+            .and_then(|file| CsvTrace::new(BufWriter::new(file)));
+        match trace {
+            Ok(trace) => (path, trace),
+            Err(e) => {
+                eprintln!(
+                    "Could not open {} for writing.\nWith error {}",
+                    path.to_string_lossy(),
+                    e
+                );
+                std::process::exit(1);
+            }
+        }
+    });
+
+    let mut live_progress =
+        show_progress.then(|| LiveProgress::new(std::io::stderr(), cooling.total_iterations()));
+
+    let mut result = if chains > 1 {
+        solver::anneal_chains(
+            &mut input,
+            cooling,
+            init_hint,
+            &constraints,
+            moves,
+            acceptance_rule,
+            weights,
+            chains,
+            seed,
+            &early_stop,
+            &Budget::unlimited(),
+        )
+    } else if best_of > 1 {
+        solver::anneal_best_of(
+            &mut input,
+            cooling,
+            init_hint,
+            &constraints,
+            moves,
+            acceptance_rule,
+            weights,
+            best_of,
+            &mut WyRand::with_seed(seed),
+            &early_stop,
+            &Budget::unlimited(),
+        )
+    } else {
+        let progress: &mut dyn ProgressSink<Progress> = match (&mut trace, &mut live_progress) {
+            (Some((_, trace)), _) => trace,
+            (None, Some(live_progress)) => live_progress,
+            (None, None) => &mut NoProgress,
+        };
+        if checkpoint.is_some() || resume.is_some() {
+            let mut save = |state: &Checkpoint<WyRand>| {
+                if let Some(path) = &checkpoint {
+                    save_checkpoint(path, state);
+                }
+            };
+            solver::anneal_checkpointed(
+                &mut input,
+                cooling,
+                init_hint,
+                &constraints,
+                moves,
+                acceptance_rule,
+                weights,
+                &mut WyRand::with_seed(seed),
+                resume,
+                checkpoint_every,
+                &mut save,
+                &early_stop,
+                &Budget::unlimited(),
+                progress,
+            )
+        } else {
+            solver::anneal(
+                &mut input,
+                cooling,
+                init_hint,
+                &constraints,
+                moves,
+                acceptance_rule,
+                weights,
+                &mut WyRand::with_seed(seed),
+                &early_stop,
+                &Budget::unlimited(),
+                progress,
+            )
+        }
+    };
+
+    if let Some(live_progress) = live_progress {
+        live_progress.finish();
+    }
+
+    if let Some((path, trace)) = trace {
+        if let Err(e) = trace.finish() {
+            eprintln!(
+                "Could not write the trace to {}.\nWith error {}",
+                path.to_string_lossy(),
+                e
+            );
+            std::process::exit(1);
+        }
+    }
+
+    let plateaued = matches!(result, Err(SolveError::Plateaued(_)));
+    if let (Some(sideways), Err(SolveError::Glassed(stats) | SolveError::Plateaued(stats))) =
+        (descend, &result)
+    {
+        result = solver::descend(
+            &mut input,
+            &puzzle,
+            &constraints,
+            moves,
+            weights,
+            sideways,
+            stats.clone(),
+            &mut WyRand::with_seed(seed),
+        )
+        .map_err(|e| match e {
+            SolveError::Glassed(stats) if plateaued => SolveError::Plateaued(stats),
+            e => e,
+        });
+    }
 
-    let result = solver::anneal(&mut input, schedule, init_hint);
+    if print_stats {
+        if let Some(stats) = result.as_ref().map_or_else(SolveError::stats, Some) {
+            eprintln!("{}", stats);
+        }
+    }
 
     match result {
-        Ok(()) => {
+        Ok(_stats) => {
+            if verify {
+                verify_or_exit(&puzzle, &input, &constraints);
+            }
             println!("SUCCESS");
             println!("{}", input);
             std::process::exit(0);
         }
-        Err(SolveError::Glassed) => {
-            println!("GLASS");
-            eprintln!(concat!(
-                "The board cooled down to an unfeasible state.\n",
-                "Perhaps you can start from this state and re-anneal?"
-            ));
+        Err(error @ (SolveError::Glassed(_) | SolveError::Plateaued(_))) => {
+            println!("{}", if plateaued { "PLATEAU" } else { "GLASS" });
+            eprintln!(
+                "{}\nPerhaps you can start from this state and re-anneal?",
+                error
+            );
+            eprintln!("This run can be replayed with --seed {}.", seed);
+            match glass_out {
+                Some(path) => {
+                    if let Err(e) = std::fs::write(&path, format!("{}", input)) {
+                        eprintln!(
+                            "Could not write the final state to {}.\nWith error {}",
+                            path.to_string_lossy(),
+                            e
+                        );
+                        std::process::exit(1);
+                    }
+                }
+                None => println!("{}", input),
+            }
+            if let Some(stats) = error.stats().filter(|_| show_best) {
+                if let Some(best_board) = &stats.best_board {
+                    println!("BEST {}", stats.best_energy);
+                    println!("{}", best_board);
+                }
+            }
+            std::process::exit(0);
+        }
+        Err(error @ SolveError::TimedOut(_)) => {
+            println!("TIMEOUT");
+            eprintln!("{}", error);
+            eprintln!("This run can be replayed with --seed {}.", seed);
             println!("{}", input);
             std::process::exit(0);
         }
-        Err(SolveError::EmptyHint) => {
-            eprintln!("The hint input had empty spaces. This is not allowed.");
+        Err(e) => {
+            eprintln!("{}", e);
             std::process::exit(1);
         }
-        Err(SolveError::IncompatibleHint) => {
-            eprintln!("The hint input is not compatible with the input's clues.");
+    }
+}
+
+/// How the cooling of an anneal is chosen.
+enum CoolingChoice {
+    /// Given by a schedule file, or an adaptive schedule.
+    Given(Cooling),
+    /// Given by a schedule file, with its rounds scaled to the input, with
+    /// --rounds-scale.
+    Scaled(Schedule, RoundsScale),
+    /// Generated for the input, with --auto-schedule.
+    Auto,
+    /// Generated for the input, cooling at the given rate, with --rate.
+    Rate(f64),
+    /// The default schedule for the size of the input.
+    Preset,
+}
+
+/// What the rounds of a schedule file are scaled by, with --rounds-scale.
+#[derive(Clone, Copy)]
+enum RoundsScale {
+    /// The number of cells of the board.
+    Cells,
+    /// The number of free cells of the input.
+    Free,
+}
+
+impl RoundsScale {
+    /// The factor to multiply the rounds by, for the input; schedule files are
+    /// taken to be written for 81 cells (or free cells).
+    fn factor(self, input: &Sudoku) -> f64 {
+        let cells = input.side() * input.side();
+        let count = match self {
+            RoundsScale::Cells => cells,
+            RoundsScale::Free => (0..cells)
+                .filter(|&raw| input.get_raw(raw).is_empty())
+                .count(),
+        };
+        count as f64 / 81.
+    }
+}
+
+impl CoolingChoice {
+    fn cooling_for(
+        &self,
+        input: &Sudoku,
+        init_hint: Option<Sudoku>,
+        moves: Moves,
+        weights: Weights,
+        seed: u64,
+    ) -> Result<Cooling, SolveError> {
+        match self {
+            CoolingChoice::Given(cooling) => Ok(cooling.clone()),
+            CoolingChoice::Scaled(schedule, scale) => {
+                Ok(schedule.with_scaled_rounds(scale.factor(input)).into())
+            }
+            CoolingChoice::Auto => {
+                let rng = &mut WyRand::with_seed(seed);
+                solver::auto_schedule(input, init_hint, moves, weights, rng).map(Cooling::from)
+            }
+            CoolingChoice::Rate(rate) => {
+                let rng = &mut WyRand::with_seed(seed);
+                solver::rate_schedule(input, init_hint, moves, weights, *rate, rng)
+                    .map(Cooling::from)
+            }
+            CoolingChoice::Preset => Ok(schedule::presets::for_side(input.side()).into()),
+        }
+    }
+}
+
+/// The options of the anneals of a collection of puzzles, which are the same
+/// for every puzzle.
+struct Batch {
+    choice: CoolingChoice,
+    constraints: Constraints,
+    moves: Moves,
+    acceptance: Acceptance,
+    weights: Weights,
+    early_stop: EarlyStop,
+    verify: bool,
+    descend: Option<usize>,
+    show_best: bool,
+}
+
+impl Batch {
+    /// Anneals each of the named puzzles, `jobs` at a time, and writes a CSV
+    /// record about each of them, in the order of the puzzles.
+    fn run<O: Write>(
+        &self,
+        puzzles: Vec<(String, Sudoku)>,
+        jobs: usize,
+        seed: u64,
+        mut out: BufWriter<O>,
+    ) {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::mpsc;
+
+        eprintln!("Annealing {} puzzle(s).", puzzles.len());
+
+        let mut seeds = WyRand::with_seed(seed);
+        let seeds = puzzles.iter().map(|_| seeds.u64()).collect::<Vec<_>>();
+        let next = AtomicUsize::new(0);
+        let (sender, receiver) = mpsc::channel();
+
+        write!(out, "puzzle,status,seed,energy,iterations,seconds,board").unwrap();
+        if self.show_best {
+            write!(out, ",best_energy,best_board").unwrap();
+        }
+        writeln!(out).unwrap();
+        std::thread::scope(|scope| {
+            for _ in 0..jobs.min(puzzles.len()) {
+                let sender = sender.clone();
+                let (puzzles, seeds, next) = (&puzzles, &seeds, &next);
+                scope.spawn(move || loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let mut board = match puzzles.get(index) {
+                        Some((_, puzzle)) => puzzle.clone(),
+                        None => break,
+                    };
+                    let result = self.anneal(&mut board, seeds[index]);
+                    if sender.send((index, result, board)).is_err() {
+                        break;
+                    }
+                });
+            }
+            drop(sender);
+
+            // Puzzles can finish out of order; each record is held back until
+            // those of the puzzles before it are written.
+            let mut finished = puzzles.iter().map(|_| None).collect::<Vec<_>>();
+            let mut written = 0;
+            for (index, result, board) in receiver {
+                finished[index] = Some((result, board));
+                while let Some((result, board)) = finished.get_mut(written).and_then(Option::take) {
+                    let (name, puzzle) = &puzzles[written];
+                    self.write_record(&mut out, name, puzzle, seeds[written], result, &board);
+                    written += 1;
+                }
+            }
+        });
+
+        out.flush().unwrap();
+    }
+
+    fn anneal(&self, board: &mut Sudoku, seed: u64) -> Result<SolveStats, SolveError> {
+        let puzzle = board.clone();
+        let cooling = self
+            .choice
+            .cooling_for(board, None, self.moves, self.weights, seed)?;
+        let result = solver::anneal(
+            board,
+            cooling,
+            None,
+            &self.constraints,
+            self.moves,
+            self.acceptance,
+            self.weights,
+            &mut WyRand::with_seed(seed),
+            &self.early_stop,
+            &Budget::unlimited(),
+            &mut NoProgress,
+        );
+        let plateaued = matches!(result, Err(SolveError::Plateaued(_)));
+        match (self.descend, result) {
+            (Some(sideways), Err(SolveError::Glassed(stats) | SolveError::Plateaued(stats))) => {
+                solver::descend(
+                    board,
+                    &puzzle,
+                    &self.constraints,
+                    self.moves,
+                    self.weights,
+                    sideways,
+                    stats,
+                    &mut WyRand::with_seed(seed),
+                )
+                .map_err(|e| match e {
+                    SolveError::Glassed(stats) if plateaued => SolveError::Plateaued(stats),
+                    e => e,
+                })
+            }
+            (_, result) => result,
+        }
+    }
+
+    fn write_record<O: Write>(
+        &self,
+        out: &mut BufWriter<O>,
+        name: &str,
+        puzzle: &Sudoku,
+        seed: u64,
+        result: Result<SolveStats, SolveError>,
+        board: &Sudoku,
+    ) {
+        let (status, stats) = match &result {
+            Ok(stats) => {
+                if self.verify && !verify(puzzle, board, &self.constraints) {
+                    eprintln!("{}: the solution failed verification.", name);
+                    ("ERROR", Some(stats))
+                } else {
+                    ("SUCCESS", Some(stats))
+                }
+            }
+            Err(SolveError::Glassed(stats)) => ("GLASS", Some(stats)),
+            Err(SolveError::TimedOut(stats)) => ("TIMEOUT", Some(stats)),
+            Err(SolveError::Plateaued(stats)) => ("PLATEAU", Some(stats)),
+            Err(e) => {
+                eprintln!("{}: {}", name, e);
+                ("ERROR", None)
+            }
+        };
+        let (fields, board) = match stats {
+            Some(stats) => (
+                format!(
+                    "{},{},{:.3}",
+                    stats.final_energy,
+                    stats.iterations,
+                    stats.elapsed.as_secs_f64()
+                ),
+                parsing::sdm::write(board),
+            ),
+            None => (",,".to_string(), String::new()),
+        };
+        write!(
+            out,
+            "{},{},{},{},{}",
+            csv_field(name),
+            status,
+            seed,
+            fields,
+            board
+        )
+        .unwrap();
+        if self.show_best {
+            match stats.and_then(|stats| Some((stats.best_energy, stats.best_board.as_ref()?))) {
+                Some((energy, best_board)) => {
+                    write!(out, ",{},{}", energy, parsing::sdm::write(best_board)).unwrap()
+                }
+                None => write!(out, ",,").unwrap(),
+            }
+        }
+        writeln!(out).unwrap();
+    }
+}
+
+/// The file given after a named input flag, such as --input.
+fn named_path(flag: &str, path: Option<String>) -> String {
+    match path {
+        Some(path) => path,
+        None => {
+            eprintln!("Expected a file after {}.", flag);
+            eprintln!("{}", USAGE);
             std::process::exit(1);
         }
-        Err(SolveError::Infeasible) => {
-            eprintln!("The input is infeasible.");
+    }
+}
+
+/// The constraints in the file at `path`, if any, for a board of `side` by
+/// `side` cells.
+fn read_constraints(path: Option<PathBuf>, side: usize, non_consecutive: bool) -> Constraints {
+    let mut constraints = match path {
+        Some(path) => {
+            let path_as_str = path.to_string_lossy().to_string();
+            let reader = match std::fs::File::open(path) {
+                Ok(reader) => reader,
+                Err(e) => {
+                    eprintln!(
+                        "Could not open {} for reading.\nWith error {}",
+                        &path_as_str, e
+                    );
+                    std::process::exit(1);
+                }
+            };
+            match parsing::constraints::parse(reader, side) {
+                Ok(constraints) => constraints,
+                Err(e) => {
+                    eprintln!("Constraints file malformed.");
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        None => Constraints::new(),
+    };
+    constraints.non_consecutive = non_consecutive;
+    constraints
+}
+
+/// Whether the path is of a collection of puzzles: a directory, or an .sdm
+/// file.
+fn is_collection(path: &str) -> bool {
+    let path = Path::new(path);
+    path.is_dir() || path.extension().is_some_and(|ext| ext == "sdm")
+}
+
+/// Opens an input file for reading, or the standard input for "-", which can
+/// only be done once.
+fn open_input(path: &str, stdin_used: &mut bool) -> Box<dyn std::io::Read> {
+    if path == "-" {
+        if *stdin_used {
+            eprintln!("Only one input can be read from the standard input.");
+            eprintln!("{}", USAGE);
             std::process::exit(1);
         }
+        *stdin_used = true;
+        return Box::new(std::io::stdin());
+    }
+
+    if !Path::new(path).exists() {
+        eprintln!("{} does not exist.", path);
+        std::process::exit(1);
+    }
+    match std::fs::File::open(path) {
+        Ok(file) => Box::new(file),
+        Err(e) => {
+            eprintln!("Could not open {} for reading.\nWith error {}", path, e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Quotes a CSV field, if needed.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Writes the checkpoint to `path`, going through a temporary file, so that
+/// the previous checkpoint survives if writing is interrupted.
+fn save_checkpoint(path: &Path, checkpoint: &Checkpoint<WyRand>) {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    let result = std::fs::File::create(&temporary)
+        .and_then(|file| {
+            let mut writer = BufWriter::new(file);
+            checkpoint.write(&mut writer)?;
+            writer.flush()
+        })
+        .and_then(|_| std::fs::rename(&temporary, path));
+    if let Err(e) = result {
+        eprintln!(
+            "Could not write the checkpoint to {}.\nWith error {}",
+            path.to_string_lossy(),
+            e
+        );
+        std::process::exit(1);
+    }
+}
+
+/// Checks the solution against the puzzle, and the variant constraints, and
+/// exits with code 1 if anything is wrong with it.
+fn verify_or_exit(puzzle: &Sudoku, solution: &Sudoku, constraints: &Constraints) {
+    if !verify(puzzle, solution, constraints) {
+        std::process::exit(1);
+    }
+}
+
+/// Checks the solution against the puzzle, and the variant constraints, and
+/// reports anything wrong with it to stderr. Returns whether it is right.
+fn verify(puzzle: &Sudoku, solution: &Sudoku, constraints: &Constraints) -> bool {
+    let mismatches = sudoku::verify_solution(puzzle, solution).err();
+    let broken = constraints.validate(solution).err();
+    if mismatches.is_none() && broken.is_none() {
+        return true;
+    }
+
+    eprintln!("The annealer reported a solution, but it is wrong!");
+    for mismatch in mismatches.unwrap_or_default() {
+        eprintln!("{}", mismatch);
+    }
+    for constraint in broken.unwrap_or_default() {
+        eprintln!("It breaks the constraint: {}", constraint);
     }
+    eprintln!("{}", solution);
+    false
 }
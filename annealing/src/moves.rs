@@ -0,0 +1,143 @@
+use crate::rng::Rng;
+use sudoku::Sudoku;
+
+/// The kind of move the annealer proposes, which also determines how the
+/// board is initialized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Moves {
+    /// Swap any two free cells. The board is initialized so that every digit
+    /// appears the right number of times.
+    #[default]
+    Global,
+    /// Swap two free cells in the same box. Each box is initialized with a
+    /// permutation of the digits, so the box constraints hold throughout, and
+    /// only rows and columns need to be annealed.
+    Box,
+    /// Like `Global`, but one of the two cells is picked with a probability
+    /// proportional to the number of conflicts it is in, so that the search
+    /// is focused on the cells that are wrong.
+    Conflicted,
+}
+
+/// Proposes swaps of free cells, according to a [`Moves`].
+pub(crate) enum Proposer {
+    Global(Vec<usize>),
+    /// The free cells of each box, leaving out boxes with fewer than two free
+    /// cells, where there is nothing to swap.
+    Box(Vec<Vec<usize>>),
+    Conflicted(Vec<usize>),
+}
+
+impl Proposer {
+    /// Groups the free cells (given as raw indices) by box.
+    pub(crate) fn by_box(free_indices: Vec<usize>, side: usize, box_side: usize) -> Self {
+        let mut boxes = vec![vec![]; side];
+        for raw in free_indices {
+            let (r, c) = (raw / side, raw % side);
+            boxes[(r / box_side) * box_side + c / box_side].push(raw);
+        }
+        boxes.retain(|cells| cells.len() >= 2);
+        Proposer::Box(boxes)
+    }
+
+    /// The groups of cells within which swaps are made.
+    fn groups(&self) -> &[Vec<usize>] {
+        match self {
+            Proposer::Global(free_indices) | Proposer::Conflicted(free_indices) => {
+                std::slice::from_ref(free_indices)
+            }
+            Proposer::Box(boxes) => boxes.as_slice(),
+        }
+    }
+
+    /// Every swap that could be proposed, as raw indices in increasing order.
+    pub(crate) fn all_swaps(&self) -> Vec<(usize, usize)> {
+        let mut swaps = vec![];
+        for cells in self.groups() {
+            for (i, &raw_a) in cells.iter().enumerate() {
+                for &raw_b in &cells[i + 1..] {
+                    swaps.push((raw_a.min(raw_b), raw_a.max(raw_b)));
+                }
+            }
+        }
+        swaps
+    }
+
+    /// Randomly permutes the values of the free cells, in a way that keeps
+    /// the invariants of the initialization, i.e., only within boxes for box
+    /// moves.
+    pub(crate) fn shuffle(&self, sudoku: &mut Sudoku, rng: &mut impl Rng) {
+        // Fisher-Yates
+        for cells in self.groups() {
+            for i in (1..cells.len()).rev() {
+                let j = rng.u64_less_than(i as u64 + 1) as usize;
+                sudoku.swap_raw(cells[i], cells[j]);
+            }
+        }
+    }
+
+    /// Picks two cells to swap, as raw indices in increasing order, or `None`
+    /// if there are no cells that can be swapped. `violation_count` holds the
+    /// number of conflicts each cell is in.
+    pub(crate) fn propose(
+        &self,
+        violation_count: &[usize],
+        rng: &mut impl Rng,
+    ) -> Option<(usize, usize)> {
+        let (mut raw_a, mut raw_b) = match self {
+            Proposer::Global(free_indices) => {
+                if free_indices.is_empty() {
+                    return None;
+                }
+                let len = free_indices.len() as u64;
+                (
+                    free_indices[rng.u64_less_than(len) as usize],
+                    free_indices[rng.u64_less_than(len) as usize],
+                )
+            }
+            Proposer::Box(boxes) => {
+                if boxes.is_empty() {
+                    return None;
+                }
+                let cells = &boxes[rng.u64_less_than(boxes.len() as u64) as usize];
+                // Pick two distinct cells; boxes are small, so swapping a cell
+                // with itself would waste a good fraction of the moves.
+                let len = cells.len() as u64;
+                let a = rng.u64_less_than(len) as usize;
+                let b = (a + 1 + rng.u64_less_than(len - 1) as usize) % cells.len();
+                (cells[a], cells[b])
+            }
+            Proposer::Conflicted(free_indices) => {
+                if free_indices.is_empty() {
+                    return None;
+                }
+                let len = free_indices.len() as u64;
+                let conflicts = free_indices
+                    .iter()
+                    .map(|&raw| violation_count[raw])
+                    .sum::<usize>();
+                let raw_a = if conflicts == 0 {
+                    // Only clues conflict, if anything; fall back to uniform
+                    free_indices[rng.u64_less_than(len) as usize]
+                } else {
+                    let mut pick = rng.u64_less_than(conflicts as u64) as usize;
+                    *free_indices
+                        .iter()
+                        .find(|&&raw| {
+                            if pick < violation_count[raw] {
+                                return true;
+                            }
+                            pick -= violation_count[raw];
+                            false
+                        })
+                        .expect("The pick is less than the total")
+                };
+                (raw_a, free_indices[rng.u64_less_than(len) as usize])
+            }
+        };
+        if raw_b < raw_a {
+            std::mem::swap(&mut raw_a, &mut raw_b);
+        }
+        Some((raw_a, raw_b))
+    }
+}
@@ -0,0 +1,205 @@
+use std::io::{self, Write};
+use std::time::Duration;
+use sudoku::ProgressSink;
+
+/// What an anneal reports to a [`ProgressSink`].
+pub enum Progress {
+    /// Reported after every proposed move.
+    Iteration(Iteration),
+    /// Reported at the end of every temperature step.
+    ///
+    /// To stop the anneal early, e.g., once the energy stops improving, hold
+    /// on to a flag also given to [`Budget::with_cancel_flag`], and set it
+    /// here.
+    ///
+    /// [`Budget::with_cancel_flag`]: crate::Budget::with_cancel_flag
+    TemperatureStep(TemperatureStep),
+}
+
+/// A snapshot of the state of an anneal, after a proposed move.
+pub struct Iteration {
+    /// Number of moves proposed so far.
+    pub iteration: usize,
+    pub temperature: f64,
+    /// Number of violations in the current state.
+    pub energy: usize,
+    /// Whether the move proposed in this iteration was accepted.
+    pub accepted: bool,
+    pub elapsed: Duration,
+}
+
+/// A summary of one temperature step of an anneal, reported when the step
+/// ends.
+///
+/// For fixed schedules, a step is a run of iterations at the same
+/// temperature; adaptive schedules, whose temperature changes more often,
+/// group iterations into steps of a fixed length instead.
+pub struct TemperatureStep {
+    /// The temperature at the start of the step.
+    pub temperature: f64,
+    /// Number of moves proposed during the step.
+    pub iterations: usize,
+    /// Lowest number of violations seen during the step.
+    pub best_energy: usize,
+    /// Mean number of violations over the iterations of the step.
+    pub mean_energy: f64,
+    /// Fraction of the proposed moves that were accepted.
+    pub acceptance: f64,
+}
+
+/// A [`ProgressSink`] that writes one CSV record per iteration, with columns
+/// `iteration,temperature,energy,accepted`.
+///
+/// Write errors are held on to, and reported by [`CsvTrace::finish`].
+pub struct CsvTrace<W: Write> {
+    writer: W,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> CsvTrace<W> {
+    /// Wraps `writer`, writing the CSV header to it.
+    ///
+    /// Since a record is written on every iteration, `writer` should usually
+    /// be buffered.
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        writeln!(writer, "iteration,temperature,energy,accepted")?;
+        Ok(CsvTrace {
+            writer,
+            error: None,
+        })
+    }
+
+    /// Flushes the trace, and returns the writer, or the first error that
+    /// occurred while writing.
+    pub fn finish(mut self) -> io::Result<W> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+impl<W: Write> ProgressSink<Progress> for CsvTrace<W> {
+    fn on_progress(&mut self, progress: &Progress) {
+        let progress = match progress {
+            Progress::Iteration(progress) if self.error.is_none() => progress,
+            _ => return,
+        };
+        let result = writeln!(
+            self.writer,
+            "{},{:e},{},{}",
+            progress.iteration,
+            progress.temperature,
+            progress.energy,
+            if progress.accepted { 1 } else { 0 }
+        );
+        if let Err(error) = result {
+            self.error = Some(error);
+        }
+    }
+}
+
+/// A [`ProgressSink`] that keeps a single status line up to date, for a person
+/// to watch, by redrawing it in place a few times a second.
+///
+/// The line shows the temperature, how much of the schedule has been run, the
+/// current and lowest energies so far, and the fraction of the moves proposed
+/// since the line was last drawn that were accepted. Write errors are ignored.
+pub struct LiveProgress<W: Write> {
+    writer: W,
+    total_iterations: usize,
+    best_energy: Option<usize>,
+    /// Iteration, temperature and energy of the latest report.
+    latest: Option<(usize, f64, usize)>,
+    last_drawn: Option<Duration>,
+    /// Moves proposed and accepted since the line was last drawn.
+    proposed: usize,
+    accepted: usize,
+    /// Length of the line last drawn, so that it can be blanked out.
+    width: usize,
+}
+
+impl<W: Write> LiveProgress<W> {
+    /// How often the line is redrawn.
+    const INTERVAL: Duration = Duration::from_millis(200);
+
+    /// Draws on `writer`, usually stderr, for an anneal of `total_iterations`
+    /// iterations.
+    pub fn new(writer: W, total_iterations: usize) -> Self {
+        LiveProgress {
+            writer,
+            total_iterations,
+            best_energy: None,
+            latest: None,
+            last_drawn: None,
+            proposed: 0,
+            accepted: 0,
+            width: 0,
+        }
+    }
+
+    /// Draws the line one last time, as of the end of the anneal, and ends it,
+    /// so that whatever is written next starts on a line of its own. Returns
+    /// the writer.
+    pub fn finish(mut self) -> W {
+        if self.proposed > 0 {
+            self.draw();
+        }
+        if self.width > 0 {
+            writeln!(self.writer).ok();
+            self.writer.flush().ok();
+        }
+        self.writer
+    }
+
+    fn draw(&mut self) {
+        let (iteration, temperature, energy) = match self.latest {
+            Some(latest) => latest,
+            None => return,
+        };
+        let done = 100. * iteration as f64 / self.total_iterations.max(1) as f64;
+        let acceptance = 100. * self.accepted as f64 / self.proposed.max(1) as f64;
+        let line = format!(
+            "T {:.3e} | {:5.1}% done | energy {} (best {}) | {:5.1}% accepted",
+            temperature,
+            done.min(100.),
+            energy,
+            self.best_energy.unwrap_or(energy),
+            acceptance
+        );
+        let padding = self.width.saturating_sub(line.len());
+        write!(self.writer, "\r{}{}", line, " ".repeat(padding)).ok();
+        self.writer.flush().ok();
+        self.width = line.len();
+    }
+}
+
+impl<W: Write> ProgressSink<Progress> for LiveProgress<W> {
+    fn on_progress(&mut self, progress: &Progress) {
+        let progress = match progress {
+            Progress::Iteration(progress) => progress,
+            Progress::TemperatureStep(_) => return,
+        };
+        self.proposed += 1;
+        if progress.accepted {
+            self.accepted += 1;
+        }
+        self.best_energy = Some(match self.best_energy {
+            Some(best) => best.min(progress.energy),
+            None => progress.energy,
+        });
+        self.latest = Some((progress.iteration, progress.temperature, progress.energy));
+
+        let due = match self.last_drawn {
+            Some(last_drawn) => progress.elapsed >= last_drawn + Self::INTERVAL,
+            None => true,
+        };
+        if due {
+            self.draw();
+            self.last_drawn = Some(progress.elapsed);
+            self.proposed = 0;
+            self.accepted = 0;
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A source of randomness for the annealer.
+///
+/// Only [`Rng::u64`] needs to be implemented; wrap your favourite generator in
+/// it to control seeding, or to run several anneals concurrently without any
+/// shared state.
+pub trait Rng {
+    /// A uniformly distributed 64-bit integer.
+    fn u64(&mut self) -> u64;
+
+    /// A uniformly distributed integer in `0..n`.
+    fn u64_less_than(&mut self, n: u64) -> u64 {
+        // Lemire's multiply-and-shift, without the rejection step; the bias is
+        // negligible for the board sizes we deal with.
+        (((self.u64() as u128) * (n as u128)) >> 64) as u64
+    }
+
+    /// A uniformly distributed float in `[0, 1)`.
+    fn f64(&mut self) -> f64 {
+        (self.u64() >> 11) as f64 / (1_u64 << 53) as f64
+    }
+}
+
+/// A small, fast, seedable pseudo-random number generator (wyrand); the
+/// default source of randomness of the annealing binary.
+#[derive(Clone)]
+pub struct WyRand {
+    state: u64,
+}
+
+impl WyRand {
+    pub fn with_seed(seed: u64) -> Self {
+        WyRand { state: seed }
+    }
+
+    /// The internal state of the generator. A generator seeded with it, with
+    /// [`WyRand::with_seed`], continues the same sequence of numbers.
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+}
+
+impl Rng for WyRand {
+    fn u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0xa076_1d64_78bd_642f);
+        let t = (self.state as u128) * ((self.state ^ 0xe703_7ed1_a0b4_28db) as u128);
+        ((t >> 64) as u64) ^ (t as u64)
+    }
+}
+
+/// A seed that differs from run to run, for when no seed is given.
+pub fn random_seed() -> u64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0);
+    WyRand::with_seed(nanos ^ std::process::id() as u64).u64()
+}
@@ -1,19 +1,343 @@
+use crate::acceptance::Acceptance;
+use crate::checkpoint::Checkpoint;
+use crate::cooling::{Cooling, CoolingRun, CoolingState};
+use crate::early_stop::EarlyStop;
+use crate::moves::{Moves, Proposer};
+use crate::progress::{Iteration, Progress, TemperatureStep};
+use crate::rng::{self, Rng, WyRand};
 use crate::schedule::Schedule;
+use crate::weights::Weights;
 use itertools::Itertools;
-use sudoku::{Sudoku, SudokuCell, SudokuCellValue};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use sudoku::constraints::{Constraint, Constraints};
+use sudoku::{
+    check, Budget, NoProgress, ProgressSink, Solver, Sudoku, SudokuCell, SudokuCellValue,
+};
 
+/// Statistics about a finished anneal.
+#[derive(Debug, Clone)]
+pub struct SolveStats {
+    pub elapsed: Duration,
+    /// Number of moves proposed.
+    pub iterations: usize,
+    /// Number of proposed moves that were accepted.
+    pub accepted: usize,
+    /// Number of temperature steps the anneal went through; see
+    /// [`TemperatureStep`].
+    pub temperature_steps: usize,
+    /// Lowest number of violations reached, and the iteration at which it was
+    /// first reached (0 for the initial state).
+    pub best_energy: usize,
+    pub best_energy_iteration: usize,
+    /// Number of violations in the final state.
+    pub final_energy: usize,
+    /// The lowest-energy state visited, which the final state is often worse
+    /// than. `None` for the statistics kept in a checkpoint, which has its
+    /// own copy.
+    pub best_board: Option<Sudoku>,
+}
+
+impl SolveStats {
+    /// Number of proposed moves that were rejected.
+    pub fn rejected(&self) -> usize {
+        self.iterations - self.accepted
+    }
+}
+
+impl std::fmt::Display for SolveStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "elapsed             {:.3}s", self.elapsed.as_secs_f64())?;
+        writeln!(f, "proposed moves      {}", self.iterations)?;
+        writeln!(f, "accepted moves      {}", self.accepted)?;
+        writeln!(f, "rejected moves      {}", self.rejected())?;
+        writeln!(f, "temperature steps   {}", self.temperature_steps)?;
+        writeln!(
+            f,
+            "best energy         {} (at iteration {})",
+            self.best_energy, self.best_energy_iteration
+        )?;
+        write!(f, "final energy        {}", self.final_energy)
+    }
+}
+
+#[derive(Debug)]
 pub enum SolveError {
-    Glassed,
-    EmptyHint,
+    /// The schedule ended in an invalid state, or one that breaks the
+    /// variant constraints; the board is left in that state.
+    Glassed(SolveStats),
     IncompatibleHint,
+    /// Box moves were asked for, but the hint repeats a digit within a box.
+    BoxInvalidHint,
+    /// The time limit of the early stop was reached; the board is left in the
+    /// lowest-energy state visited, and `final_energy` is its energy.
+    TimedOut(SolveStats),
+    /// The energy stopped going down for as long as the plateau limit of the
+    /// early stop allows, before the schedule ended; the board is left in its
+    /// final state.
+    Plateaued(SolveStats),
+    /// The checkpoint to resume from was taken with a different kind of
+    /// cooling, or on a board of a different size.
+    IncompatibleCheckpoint,
     Infeasible,
+    Cancelled,
+}
+
+impl std::fmt::Display for SolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SolveError::Glassed(_) => write!(f, "The board cooled down to an unfeasible state."),
+            SolveError::IncompatibleHint => {
+                write!(
+                    f,
+                    "The hint input is not the same size as the input, or does not agree with its clues."
+                )
+            }
+            SolveError::BoxInvalidHint => {
+                write!(
+                    f,
+                    "The hint input repeats a digit within a box, so it cannot be used with box moves."
+                )
+            }
+            SolveError::TimedOut(_) => {
+                write!(f, "The anneal was stopped when it reached its time limit.")
+            }
+            SolveError::Plateaued(_) => {
+                write!(
+                    f,
+                    "The anneal was stopped when the energy stopped going down."
+                )
+            }
+            SolveError::IncompatibleCheckpoint => {
+                write!(
+                    f,
+                    "The checkpoint was taken with a different kind of schedule, or on a board of a different size."
+                )
+            }
+            SolveError::Infeasible => write!(f, "The input is infeasible."),
+            SolveError::Cancelled => write!(f, "The anneal ran out of budget and was cancelled."),
+        }
+    }
 }
 
+impl std::error::Error for SolveError {}
+
+impl From<SolveError> for sudoku::SolveError {
+    fn from(error: SolveError) -> Self {
+        match error {
+            SolveError::Glassed(_) | SolveError::Plateaued(_) => sudoku::SolveError::Glassed,
+            SolveError::IncompatibleHint
+            | SolveError::BoxInvalidHint
+            | SolveError::IncompatibleCheckpoint => sudoku::SolveError::BadHint {
+                reason: error.to_string(),
+            },
+            SolveError::Infeasible => sudoku::SolveError::Infeasible,
+            SolveError::TimedOut(_) | SolveError::Cancelled => sudoku::SolveError::Cancelled,
+        }
+    }
+}
+
+impl SolveError {
+    /// The statistics of the anneal, for the errors that carry them.
+    pub fn stats(&self) -> Option<&SolveStats> {
+        match self {
+            SolveError::Glassed(stats)
+            | SolveError::TimedOut(stats)
+            | SolveError::Plateaued(stats) => Some(stats),
+            _ => None,
+        }
+    }
+}
+
+/// The annealer, as a [`Solver`], with the options of [`anneal`].
+#[derive(Clone)]
+pub struct Annealer {
+    pub cooling: Cooling,
+    /// Digits to fill in some of the empty cells with, which are then kept
+    /// fixed, like the clues.
+    pub init: Option<Sudoku>,
+    /// Variant constraints, whose violations count towards the energy.
+    pub constraints: Constraints,
+    pub moves: Moves,
+    pub acceptance: Acceptance,
+    /// How much conflicts in each kind of unit count towards the energy.
+    pub weights: Weights,
+    /// Seed for the random number generator; runs with the same seed are
+    /// identical. A random one is picked if there is none.
+    pub seed: Option<u64>,
+    pub early_stop: EarlyStop,
+    pub budget: Budget,
+}
+
+impl Solver for Annealer {
+    type Report = SolveStats;
+
+    fn solve(&self, sudoku: &mut Sudoku) -> Result<SolveStats, sudoku::SolveError> {
+        Ok(anneal(
+            sudoku,
+            self.cooling.clone(),
+            self.init.clone(),
+            &self.constraints,
+            self.moves,
+            self.acceptance,
+            self.weights,
+            &mut WyRand::with_seed(self.seed.unwrap_or_else(rng::random_seed)),
+            &self.early_stop,
+            &self.budget,
+            &mut NoProgress,
+        )?)
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn anneal(
     sudoku: &mut Sudoku,
-    schedule: Schedule,
+    cooling: impl Into<Cooling>,
+    init: Option<Sudoku>,
+    constraints: &Constraints,
+    moves: Moves,
+    acceptance: Acceptance,
+    weights: Weights,
+    rng: &mut impl Rng,
+    early_stop: &EarlyStop,
+    budget: &Budget,
+    progress: &mut dyn ProgressSink<Progress>,
+) -> Result<SolveStats, SolveError> {
+    run(
+        sudoku,
+        cooling.into().start(),
+        init,
+        constraints,
+        moves,
+        acceptance,
+        weights,
+        rng,
+        None,
+        None,
+        early_stop,
+        budget,
+        progress,
+        None,
+    )
+}
+
+/// Like [`anneal`], but hands a [`Checkpoint`] of the anneal to `save` every
+/// `every` iterations, and, if `resume_from` is given, picks the anneal back
+/// up from that checkpoint (in which case `rng` is ignored).
+///
+/// The board, hint and cooling must be the same as those of the anneal the
+/// checkpoint was taken from.
+#[allow(clippy::too_many_arguments)]
+pub fn anneal_checkpointed<R: Rng + Clone>(
+    sudoku: &mut Sudoku,
+    cooling: impl Into<Cooling>,
     init: Option<Sudoku>,
-) -> Result<(), SolveError> {
+    constraints: &Constraints,
+    moves: Moves,
+    acceptance: Acceptance,
+    weights: Weights,
+    rng: &mut R,
+    resume_from: Option<Checkpoint<R>>,
+    every: usize,
+    save: &mut dyn FnMut(&Checkpoint<R>),
+    early_stop: &EarlyStop,
+    budget: &Budget,
+    progress: &mut dyn ProgressSink<Progress>,
+) -> Result<SolveStats, SolveError> {
+    let mut save =
+        |board: &Sudoku, best_board: &Sudoku, rng: &R, stats: SolveStats, cooling: CoolingState| {
+            save(&Checkpoint {
+                board: board.clone(),
+                best_board: best_board.clone(),
+                rng: rng.clone(),
+                stats,
+                cooling,
+            })
+        };
+
+    match resume_from {
+        Some(checkpoint) => {
+            if checkpoint.board.side() != sudoku.side() {
+                return Err(SolveError::IncompatibleCheckpoint);
+            }
+            let cooling = cooling
+                .into()
+                .resume(&checkpoint.cooling)
+                .ok_or(SolveError::IncompatibleCheckpoint)?;
+            *rng = checkpoint.rng;
+            run(
+                sudoku,
+                cooling,
+                init,
+                constraints,
+                moves,
+                acceptance,
+                weights,
+                rng,
+                Some((checkpoint.stats, checkpoint.best_board, checkpoint.board)),
+                Some((every, &mut save)),
+                early_stop,
+                budget,
+                progress,
+                None,
+            )
+        }
+        None => run(
+            sudoku,
+            cooling.into().start(),
+            init,
+            constraints,
+            moves,
+            acceptance,
+            weights,
+            rng,
+            None,
+            Some((every, &mut save)),
+            early_stop,
+            budget,
+            progress,
+            None,
+        ),
+    }
+}
+
+/// Hook that receives the state of the anneal, from which a checkpoint can be
+/// made, and how often to call it.
+type CheckpointHook<'a, R> = (
+    usize,
+    &'a mut dyn FnMut(&Sudoku, &Sudoku, &R, SolveStats, CoolingState),
+);
+
+/// The annealing proper; `resumed` holds the statistics of the anneal so far,
+/// the best board seen, and the state it was in, if it is being resumed from
+/// a checkpoint. The
+/// anneal is cancelled as soon as `stop` is set.
+#[allow(clippy::too_many_arguments)]
+fn run<R: Rng>(
+    sudoku: &mut Sudoku,
+    mut cooling: CoolingRun,
+    init: Option<Sudoku>,
+    constraints: &Constraints,
+    moves: Moves,
+    acceptance: Acceptance,
+    weights: Weights,
+    rng: &mut R,
+    resumed: Option<(SolveStats, Sudoku, Sudoku)>,
+    mut checkpoints: Option<CheckpointHook<'_, R>>,
+    early_stop: &EarlyStop,
+    budget: &Budget,
+    progress: &mut dyn ProgressSink<Progress>,
+    stop: Option<&AtomicBool>,
+) -> Result<SolveStats, SolveError> {
+    let started = Instant::now();
+    let (resumed, resumed_best_board, resumed_state) = match resumed {
+        Some((stats, best_board, state)) => (Some(stats), Some(best_board), Some(state)),
+        None => (None, None, None),
+    };
+    let resumed = resumed.as_ref();
+    let elapsed_before = resumed.map_or(Duration::ZERO, |stats| stats.elapsed);
+
     // Start by filling in the board.
 
     // We don't need to respect the box, line, and column constraints, but we
@@ -25,153 +349,147 @@ pub fn anneal(
     let side = sudoku.side();
     let box_side = sudoku.box_side();
 
-    let free_indices = match init {
-        Some(init) => init_hint(sudoku, init, side)?,
-        None => init_no_hint(sudoku, side, side)?,
-    };
+    let proposer = initialize(sudoku, init, resumed_state, moves, rng)?;
+
+    let peers = peers(side, box_side, weights);
 
     // Keep a list of how many violations each cell is involved in.
     // This will be used to recalculate the score of a new board
     // This amounts to keeping a second sudoku board in memory.
-    let mut violation_count = vec![0_usize; side * side];
-
-    let violations = (0..side)
-        .cartesian_product(0..side)
-        .tuple_combinations()
-        .filter(|((r, c), (rr, cc))| {
-            if r == rr && c == cc {
-                return false; // This should never happen, due to the behavior of tuple_combinations()
-            }
-            if r == rr || c == cc {
-                return true;
-            }
-            (r / box_side) == (rr / box_side) && (c / box_side) == (cc / box_side)
-        })
-        .filter(|((r, c), (rr, cc))| sudoku.get(*r, *c).unwrap() == sudoku.get(*rr, *cc).unwrap());
-
-    for (a, b) in violations {
-        violation_count[a.0 * side + a.1] += 1;
-        violation_count[b.0 * side + b.1] += 1;
-    }
+    let mut violation_count = violation_counts(sudoku, &peers);
+    // Broken variant constraints count towards the energy too, so that the
+    // anneal also looks for states that follow them
+    let mut variant = VariantEnergy::new(sudoku, constraints, weights.variant);
 
     // Now start doing the actual annealing:
     // We "cache" the score of the current board since it won't change unless
     // a new microstate is accepted during the annealing step
-    let mut current_score: usize = violation_count.iter().sum();
+    let mut current_score: usize = violation_count.iter().sum::<usize>() + variant.energy();
+    // Entries of violation_count, and of the variant violations, changed by
+    // the last swap, with their previous values
+    let mut undo: Vec<(usize, usize)> = vec![];
+    let mut variant_undo: Vec<(usize, usize)> = vec![];
+    let mut iterations = resumed.map_or(0, |stats| stats.iterations);
+    let mut accepted = resumed.map_or(0, |stats| stats.accepted);
+    let mut best_energy = resumed.map_or(current_score, |stats| stats.best_energy);
+    let mut best_energy_iteration = resumed.map_or(0, |stats| stats.best_energy_iteration);
 
-    for &temperature in schedule.run() {
+    let mut step = StepTracker::default();
+    let mut temperature_steps = resumed.map_or(0, |stats| stats.temperature_steps);
+    // A copy of the lowest-energy state visited; it only needs to be updated
+    // when the energy reaches a new low, which is rare.
+    let mut best_board = resumed_best_board.unwrap_or_else(|| sudoku.clone());
+    let mut timed_out = false;
+    // The iteration at which a move that lowered the energy was last accepted
+    let mut last_improvement = iterations;
+    let mut plateaued = false;
+
+    while let Some(temperature) = cooling.next_temperature() {
         if current_score == 0 {
             // No violations, we lucked into the ground state!
             break;
         }
 
+        let step_ended = match cooling.step_length() {
+            Some(length) => step.iterations >= length,
+            None => step.iterations > 0 && temperature != step.temperature,
+        };
+        if step_ended {
+            progress.on_progress(&Progress::TemperatureStep(step.summary()));
+            temperature_steps += 1;
+            step = StepTracker::default();
+
+            if early_stop.is_past_time_limit(&started) {
+                timed_out = true;
+                break;
+            }
+        }
+        if step.iterations == 0 {
+            step.temperature = temperature;
+        }
+
+        if budget.is_exhausted(&started, iterations)
+            || stop.is_some_and(|stop| stop.load(Ordering::Relaxed))
+        {
+            return Err(SolveError::Cancelled);
+        }
+
         // Find a potential new microstate
         // The new microstate is given by swapping two elements (that are not
         // fixed)
-        let (raw_a, raw_b) = {
-            let mut raw_a = free_indices[alea::u64_less_than(free_indices.len() as u64) as usize];
-            let mut raw_b = free_indices[alea::u64_less_than(free_indices.len() as u64) as usize];
-            if raw_b < raw_a {
-                std::mem::swap(&mut raw_a, &mut raw_b);
-            }
-            (raw_a, raw_b)
+        let (raw_a, raw_b) = match proposer.propose(&violation_count, rng) {
+            Some(swap) => swap,
+            // Nothing can be moved, so nothing will change
+            None => break,
         };
 
-        sudoku.swap_raw(raw_a, raw_b);
+        iterations += 1;
 
         // Count the number of violations after the swap;
 
-        // TODO: is it trackable to keep this full clone() of violation_count,
-        //  instead of being more careful about it?
-        let old_violation_count = violation_count.clone();
-
         // We know that the swap means that only cells that are affected by
         // either of the swapped cells can change their violation status.  For
         // each of these other cells, remove--- if appropriate--- one violation
         // (from removing the old element), and add--- if appropriate--- one
-        // violation from the new element.
-        let mut recount_violations = |this: usize, other: usize| {
-            let (r, c) = (this / side, this % side);
-            let new_value = sudoku.get_raw(this).unwrap();
-            let old_value = sudoku.get_raw(other).unwrap();
-
-            for rr in 0..side {
-                if r == rr {
-                    continue;
-                }
-
-                let other_value = sudoku.get(rr, c).unwrap();
-                if other_value == old_value {
-                    violation_count[this] = violation_count[this].saturating_sub(1);
-                    violation_count[rr * side + c] =
-                        violation_count[rr * side + c].saturating_sub(1);
-                }
-                if other_value == new_value {
-                    violation_count[this] += 1;
-                    violation_count[rr * side + c] += 1;
-                }
-            }
-
-            for cc in 0..side {
-                if c == cc {
-                    continue;
-                }
-
-                let other_value = sudoku.get(r, cc).unwrap();
-                if other_value == old_value {
-                    violation_count[this] = violation_count[this].saturating_sub(1);
-                    violation_count[r * side + cc] =
-                        violation_count[r * side + cc].saturating_sub(1);
-                }
-                if other_value == new_value {
-                    violation_count[this] += 1;
-                    violation_count[r * side + cc] += 1;
-                }
-            }
-
-            for h in 0..box_side {
-                for v in 0..box_side {
-                    let rr = box_side * (r / box_side) + v;
-                    let cc = box_side * (c / box_side) + h;
-
-                    if rr == r || cc == c {
-                        // we've already checked same row & same col
+        // violation from the new element, each counting with the weight of the
+        // pair. The swapped cells are skipped as
+        // each other's peers, since they conflict neither before nor after
+        // the swap. Every entry changed is logged, so that the changes can be
+        // undone if the swap is rejected.
+        undo.clear();
+        variant_undo.clear();
+        let mut new_score = current_score;
+        let value_a = sudoku.get_raw(raw_a).value();
+        let value_b = sudoku.get_raw(raw_b).value();
+        if value_a != value_b {
+            for (this, other, old_value, new_value) in [
+                (raw_a, raw_b, value_a, value_b),
+                (raw_b, raw_a, value_b, value_a),
+            ] {
+                undo.push((this, violation_count[this]));
+                for &(peer, weight) in &peers[this] {
+                    if peer == other {
                         continue;
                     }
-                    let other_value = sudoku.get(rr, cc).unwrap();
-                    if other_value == old_value {
-                        violation_count[this] = violation_count[this].saturating_sub(1);
-                        violation_count[rr * side + cc] =
-                            violation_count[rr * side + cc].saturating_sub(1);
-                    }
-                    if other_value == new_value {
-                        violation_count[this] += 1;
-                        violation_count[rr * side + cc] += 1;
+                    let peer_value = sudoku.get_raw(peer).value();
+                    if peer_value == old_value {
+                        undo.push((peer, violation_count[peer]));
+                        violation_count[peer] -= weight;
+                        violation_count[this] -= weight;
+                        new_score -= 2 * weight;
+                    } else if peer_value == new_value {
+                        undo.push((peer, violation_count[peer]));
+                        violation_count[peer] += weight;
+                        violation_count[this] += weight;
+                        new_score += 2 * weight;
                     }
                 }
             }
-        };
-
-        recount_violations(raw_a, raw_b);
-        recount_violations(raw_b, raw_a);
-
-        drop(recount_violations);
+        }
 
-        let new_score: usize = violation_count.iter().sum();
+        sudoku.swap_raw(raw_a, raw_b);
+        if value_a != value_b {
+            let before = variant.energy();
+            variant.swapped(sudoku, raw_a, raw_b, &mut variant_undo);
+            new_score = new_score + variant.energy() - before;
+        }
 
         // Test if we should approve this score
-        let boltzmann = || {
-            alea::f64()
-                <= (f64::from(
-                    i32::try_from(current_score as isize - new_score as isize)
-                        .expect("Over or underflow"),
-                ) / temperature)
-                    .exp()
-                    .min(1.)
-        };
-        if new_score < current_score || boltzmann() {
+        let was_accepted = acceptance.accepts(current_score, new_score, temperature, rng);
+        if was_accepted {
             // Commit to the switch
+            if new_score < current_score {
+                last_improvement = iterations;
+            }
             current_score = new_score;
+            accepted += 1;
+            cooling.record(true);
+
+            if current_score < best_energy {
+                best_energy = current_score;
+                best_energy_iteration = iterations;
+                best_board.clone_from(sudoku);
+            }
 
             //println!("{:?}", current_score);
             //println!("{}", sudoku);
@@ -179,49 +497,717 @@ pub fn anneal(
         } else {
             // Undo the switch
             sudoku.swap_raw(raw_a, raw_b);
-            violation_count = old_violation_count;
+            for &(raw, count) in undo.iter().rev() {
+                violation_count[raw] = count;
+            }
+            variant.undo(&variant_undo);
+            cooling.record(false);
+        }
+
+        step.record(current_score, was_accepted);
+
+        progress.on_progress(&Progress::Iteration(Iteration {
+            iteration: iterations,
+            temperature,
+            energy: current_score,
+            accepted: was_accepted,
+            elapsed: elapsed_before + started.elapsed(),
+        }));
+
+        if let Some((every, save)) = &mut checkpoints {
+            if *every > 0 && iterations.is_multiple_of(*every) {
+                let stats = SolveStats {
+                    elapsed: elapsed_before + started.elapsed(),
+                    iterations,
+                    accepted,
+                    temperature_steps,
+                    best_energy,
+                    best_energy_iteration,
+                    final_energy: current_score,
+                    best_board: None,
+                };
+                save(sudoku, &best_board, rng, stats, cooling.state());
+            }
         }
+
+        if early_stop.is_on_plateau(iterations - last_improvement) {
+            plateaued = true;
+            break;
+        }
+    }
+
+    if step.iterations > 0 {
+        progress.on_progress(&Progress::TemperatureStep(step.summary()));
+        temperature_steps += 1;
+    }
+
+    if timed_out {
+        sudoku.clone_from(&best_board);
+        return Err(SolveError::TimedOut(SolveStats {
+            elapsed: elapsed_before + started.elapsed(),
+            iterations,
+            accepted,
+            temperature_steps,
+            best_energy,
+            best_energy_iteration,
+            final_energy: best_energy,
+            best_board: Some(best_board),
+        }));
+    }
+
+    let stats = SolveStats {
+        elapsed: elapsed_before + started.elapsed(),
+        iterations,
+        accepted,
+        temperature_steps,
+        best_energy,
+        best_energy_iteration,
+        final_energy: current_score,
+        best_board: Some(best_board),
+    };
+
+    if plateaued {
+        return Err(SolveError::Plateaued(stats));
     }
 
     // We've finished the schedule. Check if we're indeed at a solution or just
     // "glassed"
-    let pairs_to_check = (0..side)
-        .cartesian_product(0..side)
-        .tuple_combinations()
-        .filter(|((r, c), (rr, cc))| {
-            if r == rr && c == cc {
-                return false; // This should never happen, due to the behavior of tuple_combinations()
+    if check::has_conflicts(sudoku) || constraints.validate(sudoku).is_err() {
+        return Err(SolveError::Glassed(stats));
+    }
+
+    // Cool!
+    Ok(stats)
+}
+
+/// Finishes off a glassed anneal by descending greedily, at zero temperature:
+/// swaps that lower the energy are made, in a random order, until there are
+/// none left, at which point up to `sideways` swaps that leave the energy as
+/// it is can be made to get out of a plateau.
+///
+/// `puzzle` is the input of the anneal, whose clues are left in place, and
+/// `stats` those of the anneal, which are updated with the outcome. As with
+/// [`anneal`], the board is a solution if `Ok` is returned, and is otherwise
+/// the state the descent got stuck in, with `Glassed`.
+#[allow(clippy::too_many_arguments)]
+pub fn descend(
+    sudoku: &mut Sudoku,
+    puzzle: &Sudoku,
+    constraints: &Constraints,
+    moves: Moves,
+    weights: Weights,
+    sideways: usize,
+    mut stats: SolveStats,
+    rng: &mut impl Rng,
+) -> Result<SolveStats, SolveError> {
+    let side = sudoku.side();
+    let box_side = sudoku.box_side();
+    let peers = peers(side, box_side, weights);
+
+    let free_indices = (0..side * side)
+        .filter(|&raw| puzzle.get_raw(raw).value().is_none())
+        .collect::<Vec<_>>();
+    let mut swaps = match moves {
+        Moves::Global | Moves::Conflicted => Proposer::Global(free_indices),
+        Moves::Box => Proposer::by_box(free_indices, side, box_side),
+    }
+    .all_swaps();
+
+    // The change in energy if the two cells were swapped
+    let delta = |sudoku: &Sudoku, raw_a: usize, raw_b: usize| -> isize {
+        let value_a = sudoku.get_raw(raw_a).value();
+        let value_b = sudoku.get_raw(raw_b).value();
+        if value_a == value_b {
+            return 0;
+        }
+        let mut delta = 0;
+        for (this, other, old_value, new_value) in [
+            (raw_a, raw_b, value_a, value_b),
+            (raw_b, raw_a, value_b, value_a),
+        ] {
+            for &(peer, weight) in &peers[this] {
+                if peer == other {
+                    continue;
+                }
+                let peer_value = sudoku.get_raw(peer).value();
+                if peer_value == old_value {
+                    delta -= 2 * weight as isize;
+                } else if peer_value == new_value {
+                    delta += 2 * weight as isize;
+                }
+            }
+        }
+        delta
+    };
+
+    let mut variant = VariantEnergy::new(sudoku, constraints, weights.variant);
+    let mut variant_undo = vec![];
+
+    let mut energy = board_energy(sudoku, &peers) + variant.energy();
+    let mut sideways_left = sideways;
+    let mut improved = false;
+    while energy > 0 {
+        // Fisher-Yates, so that the first improvement found is a random one
+        for i in (1..swaps.len()).rev() {
+            let j = rng.u64_less_than(i as u64 + 1) as usize;
+            swaps.swap(i, j);
+        }
+
+        let mut sideways_swap = None;
+        let mut improving_swap = None;
+        for &(raw_a, raw_b) in &swaps {
+            stats.iterations += 1;
+            if sudoku.get_raw(raw_a).value() == sudoku.get_raw(raw_b).value() {
+                continue;
+            }
+            let variant_delta = variant.swap_delta(sudoku, raw_a, raw_b, &mut variant_undo);
+            match delta(sudoku, raw_a, raw_b) + variant_delta {
+                d if d < 0 => {
+                    improving_swap = Some((raw_a, raw_b, d));
+                    break;
+                }
+                0 if sideways_swap.is_none() => sideways_swap = Some((raw_a, raw_b, 0)),
+                _ => {}
             }
-            if r == rr || c == cc {
-                return true;
+        }
+
+        let (raw_a, raw_b, d) = match (improving_swap, sideways_swap) {
+            (Some(swap), _) => swap,
+            (None, Some(swap)) if sideways_left > 0 => {
+                sideways_left -= 1;
+                swap
             }
-            (r / box_side) == (rr / box_side) && (c / box_side) == (cc / box_side)
-        });
-    for ((r, c), (rr, cc)) in pairs_to_check {
-        if sudoku.get(r, c).unwrap() == sudoku.get(rr, cc).unwrap() {
-            return Err(SolveError::Glassed);
+            _ => break,
+        };
+        sudoku.swap_raw(raw_a, raw_b);
+        variant_undo.clear();
+        variant.swapped(sudoku, raw_a, raw_b, &mut variant_undo);
+        energy = (energy as isize + d) as usize;
+        stats.accepted += 1;
+        if energy < stats.best_energy {
+            stats.best_energy = energy;
+            stats.best_energy_iteration = stats.iterations;
+            improved = true;
         }
     }
+    stats.final_energy = energy;
+    if improved {
+        // The energy never goes up, so the final state is the best one
+        stats.best_board = Some(sudoku.clone());
+    }
 
-    // Cool!
-    Ok(())
+    if check::has_conflicts(sudoku) || constraints.validate(sudoku).is_err() {
+        return Err(SolveError::Glassed(stats));
+    }
+    Ok(stats)
+}
+
+/// The violations of the variant constraints, as counted towards the energy:
+/// each counts twice its weight, like a conflict between two cells.
+struct VariantEnergy<'a> {
+    constraints: Vec<&'a dyn Constraint>,
+    /// The indices of the constraints that involve each cell, by raw index.
+    involved: Vec<Vec<usize>>,
+    /// The number of violations of each constraint, and their total.
+    violations: Vec<usize>,
+    total: usize,
+    weight: usize,
+}
+
+impl<'a> VariantEnergy<'a> {
+    fn new(sudoku: &Sudoku, constraints: &'a Constraints, weight: usize) -> Self {
+        let side = sudoku.side();
+        let constraints = constraints.iter().collect::<Vec<_>>();
+        let involved = (0..side * side)
+            .map(|raw| {
+                (0..constraints.len())
+                    .filter(|&i| constraints[i].involves(raw / side, raw % side))
+                    .collect()
+            })
+            .collect();
+        let violations = constraints
+            .iter()
+            .map(|constraint| constraint.violations(sudoku))
+            .collect::<Vec<_>>();
+        let total = violations.iter().sum();
+        VariantEnergy {
+            constraints,
+            involved,
+            violations,
+            total,
+            weight,
+        }
+    }
+
+    fn energy(&self) -> usize {
+        2 * self.weight * self.total
+    }
+
+    /// Recounts the violations of the constraints that involve either of the
+    /// two cells, which were just swapped, logging the previous counts in
+    /// `undo`.
+    fn swapped(
+        &mut self,
+        sudoku: &Sudoku,
+        raw_a: usize,
+        raw_b: usize,
+        undo: &mut Vec<(usize, usize)>,
+    ) {
+        let (involved_a, involved_b) = (&self.involved[raw_a], &self.involved[raw_b]);
+        let only_b = involved_b
+            .iter()
+            .filter(|i| involved_a.binary_search(i).is_err());
+        for &i in involved_a.iter().chain(only_b) {
+            undo.push((i, self.violations[i]));
+            self.total -= self.violations[i];
+            self.violations[i] = self.constraints[i].violations(sudoku);
+            self.total += self.violations[i];
+        }
+    }
+
+    /// The change in energy if the two cells were swapped, found by swapping
+    /// them and back; `undo` is only used as scratch space.
+    fn swap_delta(
+        &mut self,
+        sudoku: &mut Sudoku,
+        raw_a: usize,
+        raw_b: usize,
+        undo: &mut Vec<(usize, usize)>,
+    ) -> isize {
+        let before = self.energy();
+        sudoku.swap_raw(raw_a, raw_b);
+        undo.clear();
+        self.swapped(sudoku, raw_a, raw_b, undo);
+        let after = self.energy();
+        sudoku.swap_raw(raw_a, raw_b);
+        self.undo(undo);
+        after as isize - before as isize
+    }
+
+    /// Puts back the counts logged by [`VariantEnergy::swapped`].
+    fn undo(&mut self, undo: &[(usize, usize)]) {
+        for &(i, count) in undo.iter().rev() {
+            self.total = self.total - self.violations[i] + count;
+            self.violations[i] = count;
+        }
+    }
+}
+
+/// The number of conflicts each cell is in, each counting with its weight, by
+/// raw index.
+fn violation_counts(sudoku: &Sudoku, peers: &[Vec<(usize, usize)>]) -> Vec<usize> {
+    let side = sudoku.side();
+    let mut violation_count = vec![0_usize; side * side];
+    for conflict in check::conflicts(sudoku) {
+        let (a, b) = conflict.cells;
+        let weight = peers[a]
+            .iter()
+            .find_map(|&(peer, weight)| (peer == b).then_some(weight))
+            .expect("Conflicting cells are peers");
+        violation_count[a] += weight;
+        violation_count[b] += weight;
+    }
+    violation_count
+}
+
+/// The cells that share a row, column or box with each cell, as raw indices,
+/// along with the weight of a conflict between the two.
+fn peers(side: usize, box_side: usize, weights: Weights) -> Vec<Vec<(usize, usize)>> {
+    (0..side * side)
+        .map(|raw| {
+            let (r, c) = (raw / side, raw % side);
+            (0..side * side)
+                .filter(|&other| other != raw)
+                .filter_map(|other| {
+                    let (rr, cc) = (other / side, other % side);
+                    let same_box =
+                        (r / box_side) == (rr / box_side) && (c / box_side) == (cc / box_side);
+                    let weight = weights.of_pair(r == rr, c == cc, same_box)?;
+                    Some((other, weight))
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>()
+}
+
+/// Fills in the free cells of the board, from the hint where it has values,
+/// and randomly elsewhere, or from `state` when resuming, and returns what
+/// moves can be made from there. Like the clues, the hinted cells are never
+/// moved.
+fn initialize(
+    sudoku: &mut Sudoku,
+    init: Option<Sudoku>,
+    state: Option<Sudoku>,
+    moves: Moves,
+    rng: &mut impl Rng,
+) -> Result<Proposer, SolveError> {
+    let side = sudoku.side();
+    let box_side = sudoku.box_side();
+
+    let hinted = match init {
+        Some(init) => init_hint(sudoku, init, side)?,
+        None => vec![],
+    };
+    if !hinted.is_empty() && moves == Moves::Box && !boxes_are_valid(sudoku, side, box_side) {
+        return Err(SolveError::BoxInvalidHint);
+    }
+
+    let proposer = |free_indices| match moves {
+        Moves::Global => Proposer::Global(free_indices),
+        Moves::Box => Proposer::by_box(free_indices, side, box_side),
+        Moves::Conflicted => Proposer::Conflicted(free_indices),
+    };
+
+    // The hinted cells are now filled, so only the rest is filled in here
+    let mut filled = match state {
+        Some(state) => {
+            let free = (0..side * side)
+                .filter(|&raw| sudoku.get_raw(raw).is_empty())
+                .collect::<Vec<_>>();
+            for &raw in &free {
+                sudoku.set_raw(raw, state.get_raw(raw));
+            }
+            free
+        }
+        None => {
+            let filled = match moves {
+                Moves::Global | Moves::Conflicted => init_no_hint(sudoku, side, side)?,
+                Moves::Box => init_boxes(sudoku, side, box_side)?,
+            };
+            proposer(filled.clone()).shuffle(sudoku, rng);
+            filled
+        }
+    };
+    filled.sort_unstable();
+
+    Ok(proposer(filled))
+}
+
+/// Generates a geometric schedule for the given board, so that no schedule
+/// needs to be written by hand.
+///
+/// The initial temperature is estimated by sampling random moves from a
+/// random initial state, and choosing it so that about 80% of the moves that
+/// increase the energy would be accepted. The schedule then cools at a fixed
+/// rate, with steps whose length scales with the number of cells, until even
+/// the smallest of the sampled increases would be accepted only about 0.1% of
+/// the time.
+pub fn auto_schedule(
+    sudoku: &Sudoku,
+    init: Option<Sudoku>,
+    moves: Moves,
+    weights: Weights,
+    rng: &mut impl Rng,
+) -> Result<Schedule, SolveError> {
+    let cells = (sudoku.side() * sudoku.side()) as f64;
+    let rounds_per_step = ((200. * cells / 81.) as usize).max(1);
+    estimated_schedule(sudoku, init, moves, weights, 0.99, rounds_per_step, rng)
+}
+
+/// Like [`auto_schedule`], but lowers the temperature by a fraction `rate` of
+/// itself after every iteration, rather than in steps, between the same
+/// estimated temperatures.
+pub fn rate_schedule(
+    sudoku: &Sudoku,
+    init: Option<Sudoku>,
+    moves: Moves,
+    weights: Weights,
+    rate: f64,
+    rng: &mut impl Rng,
+) -> Result<Schedule, SolveError> {
+    estimated_schedule(sudoku, init, moves, weights, 1. - rate, 1, rng)
+}
+
+/// A geometric schedule with the given rate and length of the steps, between
+/// temperatures estimated from random moves; see [`auto_schedule`].
+fn estimated_schedule(
+    sudoku: &Sudoku,
+    init: Option<Sudoku>,
+    moves: Moves,
+    weights: Weights,
+    rate: f64,
+    rounds_per_step: usize,
+    rng: &mut impl Rng,
+) -> Result<Schedule, SolveError> {
+    const SAMPLES: usize = 200;
+    const INITIAL_ACCEPTANCE: f64 = 0.8;
+    const FINAL_ACCEPTANCE: f64 = 1e-3;
+
+    let mut board = sudoku.clone();
+    let proposer = initialize(&mut board, init, None, moves, rng)?;
+    let peers = peers(board.side(), board.box_side(), weights);
+
+    let mut increases = vec![];
+    let mut energy = board_energy(&board, &peers);
+    for _ in 0..SAMPLES {
+        let (raw_a, raw_b) = match proposer.propose(&violation_counts(&board, &peers), rng) {
+            Some(swap) => swap,
+            None => break,
+        };
+        board.swap_raw(raw_a, raw_b);
+        let new_energy = board_energy(&board, &peers);
+        if new_energy > energy {
+            increases.push((new_energy - energy) as f64);
+        }
+        // Random walk, to sample more than the neighbourhood of one state
+        energy = new_energy;
+    }
+
+    if increases.is_empty() {
+        // No move makes things worse; a short, cold run will do.
+        return Ok(Schedule::builder().plateau(1e-3, rounds_per_step).build());
+    }
+
+    let mean = increases.iter().sum::<f64>() / increases.len() as f64;
+    let smallest = increases.iter().cloned().fold(f64::INFINITY, f64::min);
+    let initial_temperature = -mean / INITIAL_ACCEPTANCE.ln();
+    let final_temperature = -smallest / FINAL_ACCEPTANCE.ln();
+    let steps = if final_temperature < initial_temperature {
+        ((final_temperature / initial_temperature).ln() / rate.ln()).ceil() as usize
+    } else {
+        1
+    };
+
+    Ok(Schedule::builder()
+        .geometric(initial_temperature, rate, steps, rounds_per_step)
+        .build())
+}
+
+/// The energy of a filled board, as counted by the annealer: the number of
+/// conflicts of each cell, weighted as in `peers`, summed over all cells.
+fn board_energy(sudoku: &Sudoku, peers: &[Vec<(usize, usize)>]) -> usize {
+    violation_counts(sudoku, peers).iter().sum()
+}
+
+/// Accumulates the statistics of the current temperature step.
+#[derive(Default)]
+struct StepTracker {
+    temperature: f64,
+    iterations: usize,
+    accepted: usize,
+    best_energy: Option<usize>,
+    total_energy: usize,
+}
+
+impl StepTracker {
+    fn record(&mut self, energy: usize, accepted: bool) {
+        self.iterations += 1;
+        if accepted {
+            self.accepted += 1;
+        }
+        self.best_energy = Some(self.best_energy.map_or(energy, |best| best.min(energy)));
+        self.total_energy += energy;
+    }
+
+    fn summary(&self) -> TemperatureStep {
+        TemperatureStep {
+            temperature: self.temperature,
+            iterations: self.iterations,
+            best_energy: self.best_energy.unwrap_or(0),
+            mean_energy: self.total_energy as f64 / self.iterations as f64,
+            acceptance: self.accepted as f64 / self.iterations as f64,
+        }
+    }
+}
+
+/// Runs `chains` independent anneals in parallel, one per thread, each seeded
+/// differently from `seed`.
+///
+/// As soon as one chain finds a solution, the others are cancelled, and the
+/// board is set to that solution. If none does, the board is set to the
+/// lowest-energy final state, and `Glassed` is returned.
+#[allow(clippy::too_many_arguments)]
+pub fn anneal_chains(
+    sudoku: &mut Sudoku,
+    cooling: impl Into<Cooling>,
+    init: Option<Sudoku>,
+    constraints: &Constraints,
+    moves: Moves,
+    acceptance: Acceptance,
+    weights: Weights,
+    chains: usize,
+    seed: u64,
+    early_stop: &EarlyStop,
+    budget: &Budget,
+) -> Result<SolveStats, SolveError> {
+    let cooling = cooling.into();
+    let solved = AtomicBool::new(false);
+    let mut seeds = WyRand::with_seed(seed);
+
+    let (sender, receiver) = mpsc::channel();
+    std::thread::scope(|scope| {
+        for _ in 0..chains {
+            let sender = sender.clone();
+            let mut board = sudoku.clone();
+            let cooling = cooling.clone();
+            let init = init.clone();
+            let mut rng = WyRand::with_seed(seeds.u64());
+            let solved = &solved;
+            scope.spawn(move || {
+                let result = run(
+                    &mut board,
+                    cooling.start(),
+                    init,
+                    constraints,
+                    moves,
+                    acceptance,
+                    weights,
+                    &mut rng,
+                    None,
+                    None,
+                    early_stop,
+                    budget,
+                    &mut NoProgress,
+                    Some(solved),
+                );
+                if result.is_ok() {
+                    solved.store(true, Ordering::Relaxed);
+                }
+                // The receiver outlives the scope, so this cannot fail
+                sender.send((result, board)).ok();
+            });
+        }
+    });
+    drop(sender);
+
+    let mut best: Option<(Result<SolveStats, SolveError>, Sudoku)> = None;
+    for (result, board) in receiver {
+        if is_better(best.as_ref().map(|(result, _)| result), &result) {
+            best = Some((result, board));
+        }
+    }
+
+    match best {
+        Some((result, board)) => {
+            if matches!(
+                result,
+                Ok(_)
+                    | Err(SolveError::Glassed(_))
+                    | Err(SolveError::TimedOut(_))
+                    | Err(SolveError::Plateaued(_))
+            ) {
+                *sudoku = board;
+            }
+            result
+        }
+        // No chains were run
+        None => Err(SolveError::Cancelled),
+    }
+}
+
+/// Runs up to `restarts` anneals one after the other, each from a different
+/// random initial state, stopping at the first solution.
+///
+/// If no anneal finds a solution, the board is set to the lowest-energy final
+/// state, and `Glassed` is returned.
+#[allow(clippy::too_many_arguments)]
+pub fn anneal_best_of(
+    sudoku: &mut Sudoku,
+    cooling: impl Into<Cooling>,
+    init: Option<Sudoku>,
+    constraints: &Constraints,
+    moves: Moves,
+    acceptance: Acceptance,
+    weights: Weights,
+    restarts: usize,
+    rng: &mut impl Rng,
+    early_stop: &EarlyStop,
+    budget: &Budget,
+) -> Result<SolveStats, SolveError> {
+    let cooling = cooling.into();
+
+    let mut best: Option<(Result<SolveStats, SolveError>, Sudoku)> = None;
+    for _ in 0..restarts {
+        let mut board = sudoku.clone();
+        let result = anneal(
+            &mut board,
+            cooling.clone(),
+            init.clone(),
+            constraints,
+            moves,
+            acceptance,
+            weights,
+            rng,
+            early_stop,
+            budget,
+            &mut NoProgress,
+        );
+        let solved = result.is_ok();
+        if is_better(best.as_ref().map(|(result, _)| result), &result) {
+            best = Some((result, board));
+        }
+        if solved {
+            break;
+        }
+        if !matches!(
+            best,
+            Some((Err(SolveError::Glassed(_) | SolveError::Plateaued(_)), _))
+        ) {
+            // Any other error would only be repeated, and a timed out anneal
+            // leaves no time for another
+            break;
+        }
+    }
+
+    match best {
+        Some((result, board)) => {
+            if matches!(
+                result,
+                Ok(_)
+                    | Err(SolveError::Glassed(_))
+                    | Err(SolveError::TimedOut(_))
+                    | Err(SolveError::Plateaued(_))
+            ) {
+                *sudoku = board;
+            }
+            result
+        }
+        // No restarts were run
+        None => Err(SolveError::Cancelled),
+    }
+}
+
+/// Whether `result` is preferable to `best`: solutions beat glassed or timed
+/// out anneals, which beat other errors, and lower final energies beat higher
+/// ones.
+fn is_better(
+    best: Option<&Result<SolveStats, SolveError>>,
+    result: &Result<SolveStats, SolveError>,
+) -> bool {
+    let unsolved = |result: &Result<SolveStats, SolveError>| match result {
+        Err(SolveError::Glassed(stats))
+        | Err(SolveError::TimedOut(stats))
+        | Err(SolveError::Plateaued(stats)) => Some(stats.final_energy),
+        _ => None,
+    };
+    match (best, result) {
+        (None, _) => true,
+        (Some(Ok(_)), _) => false,
+        (Some(_), Ok(_)) => true,
+        (Some(best), result) => match (unsolved(best), unsolved(result)) {
+            (Some(old), Some(new)) => new < old,
+            (Some(_), None) => false,
+            (None, _) => true,
+        },
+    }
 }
 
+/// Sets the free cells that have a value in the hint to that value, and
+/// returns them.
 fn init_hint(sudoku: &mut Sudoku, hint: Sudoku, side: usize) -> Result<Vec<usize>, SolveError> {
+    if hint.side() != side {
+        return Err(SolveError::IncompatibleHint);
+    }
     (0..(side * side))
         .filter_map(|raw| {
-            let hint_here = hint.get_raw(raw).value().ok_or(SolveError::EmptyHint);
-            let hint_here = match hint_here {
-                Ok(value) => value,
-                Err(err) => return Some(Err(err)),
-            };
+            let hint_here = hint.get_raw(raw).value()?;
             if let Some(value) = sudoku.get_raw(raw).value() {
                 if hint_here != value {
                     return Some(Err(SolveError::IncompatibleHint));
                 }
                 None
             } else {
-                sudoku.set_raw(raw, SudokuCell::Digit(hint_here));
+                sudoku.set_raw(raw, SudokuCell::digit(hint_here));
                 Some(Ok(raw))
             }
         })
@@ -260,8 +1246,58 @@ fn init_no_hint(
         .flatten();
 
     for (raw, value) in free_indices.iter().zip(initial_values) {
-        sudoku.set_raw(*raw, SudokuCell::Digit(value));
+        sudoku.set_raw(*raw, SudokuCell::digit(value));
+    }
+
+    Ok(free_indices)
+}
+
+/// Fills the free cells of each box with the digits missing from that box, so
+/// that every box is a permutation of the digits, and returns the free cells.
+fn init_boxes(sudoku: &mut Sudoku, side: usize, box_side: usize) -> Result<Vec<usize>, SolveError> {
+    let mut free_indices = vec![];
+    for b in 0..side {
+        let cells = box_cells(b, side, box_side).collect::<Vec<_>>();
+
+        let mut present = vec![false; side];
+        for &raw in &cells {
+            if let Some(value) = sudoku.get_raw(raw).value() {
+                if present[value - 1] {
+                    return Err(SolveError::Infeasible);
+                }
+                present[value - 1] = true;
+            }
+        }
+
+        let missing = (1..=side).filter(|d| !present[d - 1]);
+        let free = cells
+            .into_iter()
+            .filter(|&raw| sudoku.get_raw(raw).is_empty())
+            .collect::<Vec<_>>();
+        for (raw, value) in free.into_iter().zip(missing) {
+            sudoku.set_raw(raw, SudokuCell::digit(value));
+            free_indices.push(raw);
+        }
     }
+    free_indices.sort_unstable();
 
     Ok(free_indices)
 }
+
+/// Whether no box of the (filled) board repeats a digit.
+fn boxes_are_valid(sudoku: &Sudoku, side: usize, box_side: usize) -> bool {
+    (0..side).all(|b| {
+        box_cells(b, side, box_side)
+            .filter_map(|raw| sudoku.get_raw(raw).value())
+            .all_unique()
+    })
+}
+
+/// The raw indices of the cells of box `b`, with boxes numbered in reading
+/// order.
+fn box_cells(b: usize, side: usize, box_side: usize) -> impl Iterator<Item = usize> {
+    let (r0, c0) = ((b / box_side) * box_side, (b % box_side) * box_side);
+    (0..box_side)
+        .cartesian_product(0..box_side)
+        .map(move |(v, h)| (r0 + v) * side + c0 + h)
+}
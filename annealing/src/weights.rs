@@ -0,0 +1,46 @@
+/// How much a conflict between two cells counts towards the energy, by the
+/// kind of unit the two cells share.
+///
+/// A conflict between two cells that share more than one unit (a row and a
+/// box, say) counts with the largest of their weights, so that, with the
+/// default weights of 1, the energy is twice the number of conflicts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Weights {
+    pub row: usize,
+    pub column: usize,
+    pub box_: usize,
+    /// How much each violation of a variant constraint counts, like a
+    /// conflict; see [`Constraint::violations`](sudoku::constraints::Constraint::violations).
+    pub variant: usize,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Weights {
+            row: 1,
+            column: 1,
+            box_: 1,
+            variant: 1,
+        }
+    }
+}
+
+impl Weights {
+    /// The weight of a conflict between two cells, given which units they
+    /// share, or `None` if they share none.
+    pub(crate) fn of_pair(
+        &self,
+        same_row: bool,
+        same_column: bool,
+        same_box: bool,
+    ) -> Option<usize> {
+        [
+            (same_row, self.row),
+            (same_column, self.column),
+            (same_box, self.box_),
+        ]
+        .into_iter()
+        .filter_map(|(shared, weight)| shared.then_some(weight))
+        .max()
+    }
+}
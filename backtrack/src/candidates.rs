@@ -0,0 +1,222 @@
+//! The candidate digits of the cells of a board, kept up to date as the
+//! search fills it in.
+
+use sudoku::constraints::Constraints;
+use sudoku::{Sudoku, SudokuCell, SudokuCellValue};
+
+/// Something done to the board or the candidates, that can be undone.
+enum Change {
+    /// A digit was removed from the candidates of a cell.
+    Eliminated { cell: usize, digit: usize },
+    /// A cell was filled in by propagation.
+    Placed { cell: usize },
+}
+
+pub(crate) struct Candidates {
+    /// For each cell, the other cells in its row, column and box.
+    peers: Vec<Vec<usize>>,
+    /// The cells of every row, column and box.
+    units: Vec<Vec<usize>>,
+    /// For each cell, `words` words in which bit `d` (counting across the
+    /// words) is set if the digit `d` may go there.
+    masks: Vec<u64>,
+    /// Number of words in the mask of each cell, enough for bits 0 through
+    /// `side`.
+    words: usize,
+    /// The changes made so far, in order, so that they can be undone.
+    trail: Vec<Change>,
+    /// Number of naked singles filled in so far, including those undone
+    /// since.
+    pub naked_singles: usize,
+    /// Number of hidden singles filled in so far, including those undone
+    /// since.
+    pub hidden_singles: usize,
+}
+
+impl Candidates {
+    /// The candidates of the empty cells of the board, given their row,
+    /// column and box, and the variant constraints.
+    pub fn new(sudoku: &Sudoku, constraints: &Constraints) -> Self {
+        let side = sudoku.side();
+        let box_side = sudoku.box_side();
+        let words = (side + 1).div_ceil(64);
+
+        let peers = (0..side * side)
+            .map(|cell| {
+                let (r, c) = (cell / side, cell % side);
+                let (top, left) = (box_side * (r / box_side), box_side * (c / box_side));
+                let row = (0..side).map(|cc| r * side + cc);
+                let column = (0..side).map(|rr| rr * side + c);
+                let boxed = (0..side).map(|i| (top + i / box_side) * side + left + i % box_side);
+                let mut peers = row
+                    .chain(column)
+                    .chain(boxed)
+                    .filter(|&peer| peer != cell)
+                    .collect::<Vec<_>>();
+                peers.sort_unstable();
+                peers.dedup();
+                peers
+            })
+            .collect::<Vec<_>>();
+
+        let mut candidates = Candidates {
+            peers,
+            units: sudoku.units().map(|unit| unit.cells).collect(),
+            masks: vec![0; side * side * words],
+            words,
+            trail: vec![],
+            naked_singles: 0,
+            hidden_singles: 0,
+        };
+        for cell in (0..side * side).filter(|&cell| sudoku.get_raw(cell).is_empty()) {
+            let (r, c) = (cell / side, cell % side);
+            let mut taken = vec![false; side + 1];
+            for value in candidates.peers[cell]
+                .iter()
+                .filter_map(|&peer| sudoku.get_raw(peer).value())
+            {
+                taken[value] = true;
+            }
+            for d in (1..=side).filter(|&d| !taken[d] && constraints.allows(r, c, d, side)) {
+                candidates.insert(cell, d);
+            }
+        }
+        candidates
+    }
+
+    pub fn count(&self, cell: usize) -> usize {
+        self.mask(cell)
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    /// Whether `digit` may go in `cell`.
+    fn contains(&self, cell: usize, digit: usize) -> bool {
+        self.mask(cell)[digit / 64] & 1 << (digit % 64) != 0
+    }
+
+    fn insert(&mut self, cell: usize, digit: usize) {
+        self.masks[cell * self.words + digit / 64] |= 1 << (digit % 64);
+    }
+
+    fn remove(&mut self, cell: usize, digit: usize) {
+        self.masks[cell * self.words + digit / 64] &= !(1 << (digit % 64));
+    }
+
+    fn mask(&self, cell: usize) -> &[u64] {
+        &self.masks[cell * self.words..(cell + 1) * self.words]
+    }
+
+    pub fn peers(&self, cell: usize) -> &[usize] {
+        &self.peers[cell]
+    }
+
+    pub fn digits(&self, cell: usize) -> Vec<usize> {
+        (1..self.words * 64)
+            .filter(|&d| self.contains(cell, d))
+            .collect()
+    }
+
+    /// Removes `digit`, which was just placed in `cell`, from the candidates
+    /// of the empty peers of `cell` ("forward checking"). Returns `false` if
+    /// that leaves one of them without candidates, in which case the digit
+    /// cannot stay.
+    pub fn eliminate_from_peers(&mut self, sudoku: &Sudoku, cell: usize, digit: usize) -> bool {
+        for i in 0..self.peers[cell].len() {
+            let peer = self.peers[cell][i];
+            if !sudoku.get_raw(peer).is_empty() || !self.contains(peer, digit) {
+                continue;
+            }
+            self.remove(peer, digit);
+            self.trail.push(Change::Eliminated { cell: peer, digit });
+            if self.count(peer) == 0 {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Repeatedly fills in the empty cells that have a single candidate left
+    /// ("naked singles"), and the digits that have a single place left in a
+    /// row, column or box ("hidden singles"), until there are none. Returns
+    /// `false` if this runs into a contradiction, i.e., a cell without
+    /// candidates, a digit without a place, or a broken variant constraint.
+    pub fn propagate(&mut self, sudoku: &mut Sudoku, constraints: &Constraints) -> bool {
+        let side = sudoku.side();
+        loop {
+            let mut placed_any = false;
+
+            for cell in 0..side * side {
+                if !sudoku.get_raw(cell).is_empty() || self.count(cell) != 1 {
+                    continue;
+                }
+                let digit = self.digits(cell)[0];
+                self.naked_singles += 1;
+                if !self.place(sudoku, constraints, cell, digit) {
+                    return false;
+                }
+                placed_any = true;
+            }
+
+            for unit in 0..self.units.len() {
+                for digit in 1..=side {
+                    let cells = &self.units[unit];
+                    if cells
+                        .iter()
+                        .any(|&cell| sudoku.get_raw(cell).value() == Some(digit))
+                    {
+                        continue;
+                    }
+                    let mut places = cells.iter().copied().filter(|&cell| {
+                        sudoku.get_raw(cell).is_empty() && self.contains(cell, digit)
+                    });
+                    let cell = match (places.next(), places.next()) {
+                        (None, _) => return false, // The digit has nowhere to go
+                        (Some(cell), None) => cell,
+                        _ => continue,
+                    };
+                    self.hidden_singles += 1;
+                    if !self.place(sudoku, constraints, cell, digit) {
+                        return false;
+                    }
+                    placed_any = true;
+                }
+            }
+
+            if !placed_any {
+                return true;
+            }
+        }
+    }
+
+    /// Fills in `cell` with `digit`, as part of propagation. Returns `false`
+    /// if this runs into a contradiction.
+    fn place(
+        &mut self,
+        sudoku: &mut Sudoku,
+        constraints: &Constraints,
+        cell: usize,
+        digit: usize,
+    ) -> bool {
+        sudoku.set_raw(cell, SudokuCell::digit(digit));
+        self.trail.push(Change::Placed { cell });
+        self.eliminate_from_peers(sudoku, cell, digit) && !constraints.is_broken_at(sudoku, cell)
+    }
+
+    /// A point to [`Candidates::undo`] back to.
+    pub fn checkpoint(&self) -> usize {
+        self.trail.len()
+    }
+
+    /// Undoes every change since `checkpoint`, putting back the removed
+    /// candidates and emptying the cells filled in by propagation.
+    pub fn undo(&mut self, sudoku: &mut Sudoku, checkpoint: usize) {
+        for change in self.trail.split_off(checkpoint) {
+            match change {
+                Change::Eliminated { cell, digit } => self.insert(cell, digit),
+                Change::Placed { cell } => sudoku.set_raw(cell, SudokuCell::Empty),
+            }
+        }
+    }
+}
@@ -0,0 +1,20 @@
+//! Backtracking solver for sudoku, as a library.
+//!
+//! The `backtrack` binary is a thin command line wrapper around [`backtrack`].
+
+mod candidates;
+pub mod order;
+pub mod progress;
+pub mod propagation;
+pub mod solver;
+pub mod trace;
+
+pub use order::Order;
+pub use progress::Progress;
+pub use propagation::propagate;
+pub use solver::{
+    backtrack, backtrack_parallel, check_clues, count_solutions, solutions, solve, Backtrack,
+    Solutions, SolveError, SolveStats,
+};
+pub use sudoku::{Budget, NoProgress, ProgressSink};
+pub use trace::{trace, Event, Trace};
@@ -1,36 +1,136 @@
 use std::{
     io::{BufWriter, Write},
     path::PathBuf,
+    time::Duration,
 };
 
-use solver::SolveError;
-use sudoku::parsing;
-
-mod solver;
+use backtrack::solver::{self, SolveError};
+use backtrack::{Backtrack, Budget, Order};
+use sudoku::constraints::Constraints;
+use sudoku::{check, parsing, Sudoku, SudokuCell, SudokuCellValue};
 
 const HELP: &'static str = concat!(
     r#"backtrack solver for sudoku
 
 Usage:
-    sudoku [--benchmark=<file>] <input file>
+    sudoku [--benchmark=<file> [--format <csv|json>]] [--constraints <file>]
+           [--non-consecutive] [--order <order>] [--parallel] [--stats] [--seed <n>]
+           [--max-nodes <n>] [--timeout <seconds>] [--verify] <input file>
+           [<init file>]
+    sudoku (--max-solutions <n> | --unique) [--constraints <file>]
+           [--non-consecutive] [--verify] <input file>
+    sudoku --benchmark=<file> [--format <csv|json>] [--constraints <file>]
+           [--non-consecutive] [--order <order>] [--parallel] [--seed <n>]
+           [--max-nodes <n>] [--timeout <seconds>] (<directory> | <.sdm file>)
     sudoku --help
 
 Options:
-    --help      Print this text.
+    --help                  Print this text.
+    --benchmark=<file>      Time several solves of the input, and append
+                            statistics about the times to <file>.
+    --format <csv|json>     Format of the benchmark statistics; CSV by
+                            default.
+    --max-solutions <n>     Print up to n solutions, rather than the first
+                            one found.
+    --constraints <file>    Read variant constraints from <file>, which the
+                            solution must follow too.
+    --non-consecutive       Forbid consecutive digits in orthogonally
+                            adjacent cells.
+    --order <order>         How to choose the next cell to guess at; one of
+                            static, mrv, degree or random. mrv by default.
+    --parallel              Split the search across threads, one for each
+                            candidate of the first cell that needs guessing.
+    --stats                 Print statistics about the search to stderr.
+    --seed <n>              Seed the random order in which digits are tried,
+                            so that the same solution is found every time
+                            (but for --parallel, where threads race).
+    --max-nodes <n>         Give up after trying n digits.
+    --timeout <seconds>     Give up after the given time.
+    --unique                Check whether the input has exactly one solution.
+    --verify                Check each solution against the input before
+                            printing it.
 
 An input file of "-" denotes the input data should be read from the standard
 input.
 
-The input file is expected to be in .soduku format.
+The input file and init file are expected to be in .soduku format, and the
+constraints file in .constraints format.
+
+If some of the clues already break the rules, e.g., the same digit appears
+twice in a row, the program reports the offending cells and exits with code 1,
+without searching.
+
+The init file, if provided, holds a partial solution to continue from. Its
+filled in cells are taken as given, along with the clues; it follows that the
+init file must agree with the input file on the clues, and must not break the
+rules by itself.
+
+The orders of --order choose, among the empty cells,
+
+    static              the first one, in reading order;
+    mrv                 the one with the fewest candidates left;
+    degree              the one with the most empty cells in its row, column
+                        and box;
+    random              any one, at random.
+
+With --verify, each solution is checked once more before it is printed: it must
+be complete, agree with every clue, and break none of the rules. If it does
+not, what is wrong with it is reported to stderr, along with the board, and the
+program exits with code 1.
+
+With --unique, the program prints to stdout a single line with the outcome of
+the check, and exits with the matching code:
+
+    UNIQUE              The input has exactly one solution, given below.
+                        Exit code 0.
+    MULTIPLE (n>=2)     The input has more than one solution. Exit code 2.
+    NONE                The input has no solution. Exit code 1.
+
+Benchmarks can also be run over every .sudoku file in a directory, or every
+puzzle in an .sdm collection. The .sdm format has one puzzle per line, written
+as its cells in reading order, without separators, and with "." or "0" for
+empty cells.
+
+The benchmark statistics are the number of runs, the number of failed runs, and
+the minimum, median, 95th percentile, maximum, mean and standard deviation of
+the times of the successful runs, in microseconds. They are given for each
+puzzle, and for all of the runs together. In CSV, they are given as a header
+line and a line of values for each puzzle, followed by one for all puzzles,
+named "all"; in JSON, as an object on a single line, with a "puzzles" list and
+an "all" member.
+
+If the search gives up because of --max-nodes or --timeout, the program prints
+EXHAUSTED to stdout, and exits with code 3. With --parallel, these limits apply
+to each thread separately. In a benchmark, the runs that give up count as
+failed runs.
+
+If the input is already complete, and breaks no rule, the program prints
+ALREADY SOLVED to stdout, followed by the board, and exits with code 0.
 "#,
-    include_str!("../../FORMATTING.txt")
+    include_str!("../../FORMATTING.txt"),
+    "\n\n",
+    include_str!("../../CONSTRAINTS.txt")
 );
 
 fn main() {
     let mut args = std::env::args().skip(1); // Skip the filename
 
     let mut input = None;
+    let mut input_name = String::new();
+    let mut collection: Option<PathBuf> = None;
+    let mut init_hint = None;
     let mut benchmark: Option<BufWriter<Box<dyn Write>>> = None;
+    let mut constraints_path: Option<PathBuf> = None;
+    let mut non_consecutive = false;
+    let mut parallel = false;
+    let mut max_solutions: Option<usize> = None;
+    let mut unique = false;
+    let mut verify = false;
+    let mut stats = false;
+    let mut budget = Budget::unlimited();
+    let mut seed: Option<u64> = None;
+    let mut format = Format::Csv;
+    let mut order = Order::default();
 
     while let Some(arg) = args.next() {
         match arg.as_str() {
@@ -39,8 +139,84 @@ fn main() {
                 std::process::exit(0);
             }
             "-" => {
-                input = Some(parsing::sudoku::parse(std::io::stdin()));
+                if input.is_none() && collection.is_none() {
+                    input = Some(parsing::sudoku::parse(std::io::stdin()));
+                    input_name = "-".to_string();
+                } else if init_hint.is_none() {
+                    init_hint = Some(parsing::sudoku::parse(std::io::stdin()));
+                } else {
+                    eprintln!("Too many arguments!");
+                    eprintln!("{}", HELP);
+                    std::process::exit(1);
+                }
+            }
+            "--non-consecutive" => {
+                non_consecutive = true;
+            }
+            "--parallel" => {
+                parallel = true;
+            }
+            "--unique" => {
+                unique = true;
             }
+            "--verify" => {
+                verify = true;
+            }
+            "--stats" => {
+                stats = true;
+            }
+            "--max-solutions" => match args.next().map(|n| n.parse::<usize>()) {
+                Some(Ok(n)) => max_solutions = Some(n),
+                _ => {
+                    println!("{}", HELP);
+                    std::process::exit(1);
+                }
+            },
+            "--format" => match args.next().as_deref() {
+                Some("csv") => format = Format::Csv,
+                Some("json") => format = Format::Json,
+                _ => {
+                    println!("{}", HELP);
+                    std::process::exit(1);
+                }
+            },
+            "--order" => match args.next().as_deref().and_then(Order::from_name) {
+                Some(o) => order = o,
+                None => {
+                    println!("{}", HELP);
+                    std::process::exit(1);
+                }
+            },
+            "--seed" => match args.next().map(|n| n.parse::<u64>()) {
+                Some(Ok(n)) => seed = Some(n),
+                _ => {
+                    println!("{}", HELP);
+                    std::process::exit(1);
+                }
+            },
+            "--max-nodes" => match args.next().map(|n| n.parse::<usize>()) {
+                Some(Ok(n)) => budget = budget.with_max_steps(n),
+                _ => {
+                    println!("{}", HELP);
+                    std::process::exit(1);
+                }
+            },
+            "--timeout" => match args.next().map(|seconds| seconds.parse::<f64>()) {
+                Some(Ok(seconds)) if seconds >= 0. && seconds.is_finite() => {
+                    budget = budget.with_max_time(Duration::from_secs_f64(seconds))
+                }
+                _ => {
+                    println!("{}", HELP);
+                    std::process::exit(1);
+                }
+            },
+            "--constraints" => match args.next() {
+                Some(path) => constraints_path = Some(PathBuf::from(path)),
+                None => {
+                    println!("{}", HELP);
+                    std::process::exit(1);
+                }
+            },
             other => {
                 if other.starts_with("--benchmark") {
                     // Parse a benchmark file path
@@ -83,6 +259,13 @@ fn main() {
                         std::process::exit(1);
                     }
 
+                    let is_collection =
+                        path.is_dir() || path.extension().is_some_and(|ext| ext == "sdm");
+                    if is_collection && input.is_none() && collection.is_none() {
+                        collection = Some(path);
+                        continue;
+                    }
+
                     let reader = std::fs::File::open(path);
                     if let Err(e) = reader {
                         eprintln!(
@@ -93,18 +276,63 @@ fn main() {
                     }
                     let reader = reader.unwrap();
 
-                    input = Some(parsing::sudoku::parse(reader));
+                    if input.is_none() && collection.is_none() {
+                        input = Some(parsing::sudoku::parse(reader));
+                        input_name = path_as_str;
+                    } else if init_hint.is_none() {
+                        init_hint = Some(parsing::sudoku::parse(reader));
+                    } else {
+                        eprintln!("Too many arguments!");
+                        eprintln!("{}", HELP);
+                        std::process::exit(1);
+                    }
                 }
             }
         }
     }
 
+    if let Some(path) = collection {
+        let writer = match benchmark {
+            Some(writer) => writer,
+            None => {
+                eprintln!("Only benchmarks can be run over a directory or an .sdm collection.");
+                std::process::exit(1);
+            }
+        };
+        if init_hint.is_some() {
+            eprintln!("An init file cannot be used with a directory or an .sdm collection.");
+            std::process::exit(1);
+        }
+        let puzzles = match parsing::read_collection(&path) {
+            Ok(puzzles) if !puzzles.is_empty() => puzzles,
+            Ok(_) => {
+                eprintln!("{} holds no puzzles.", path.to_string_lossy());
+                std::process::exit(1);
+            }
+            Err(e) => {
+                println!("Input board malformed.");
+                println!("{}", e);
+                std::process::exit(1);
+            }
+        };
+        let constraints = read_constraints(constraints_path, puzzles[0].1.side(), non_consecutive);
+        let solver = Backtrack {
+            constraints,
+            order,
+            budget,
+            seed,
+            parallel,
+        };
+        run_benchmark(puzzles, solver, format, writer);
+        return;
+    }
+
     if input.is_none() {
         eprintln!("{}", HELP);
         std::process::exit(1);
     };
 
-    let input = match input.unwrap() {
+    let mut input = match input.unwrap() {
         Ok(input) => input,
         Err(e) => {
             println!("Input board malformed.");
@@ -113,18 +341,172 @@ fn main() {
         }
     };
 
-    match benchmark {
-        Some(writer) => run_benchmark(input, writer),
-        None => run(input),
+    if benchmark.is_none() {
+        if let Err(e) = solver::check_clues(&input) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+
+    match init_hint {
+        Some(Ok(hint)) => {
+            if let Err(e) = apply_hint(&mut input, &hint) {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        Some(Err(e)) => {
+            println!("Init board malformed.");
+            println!("{}", e);
+            std::process::exit(1);
+        }
+        None => {}
+    }
+
+    let constraints = read_constraints(constraints_path, input.side(), non_consecutive);
+
+    if let Some(writer) = benchmark {
+        let solver = Backtrack {
+            constraints,
+            order,
+            budget,
+            seed,
+            parallel,
+        };
+        run_benchmark(vec![(input_name, input)], solver, format, writer);
+    } else if unique {
+        run_unique(input, constraints, verify);
+    } else if let Some(max_solutions) = max_solutions {
+        run_solutions(input, constraints, max_solutions, verify);
+    } else {
+        let solver = Backtrack {
+            constraints,
+            order,
+            budget,
+            seed,
+            parallel,
+        };
+        run(input, solver, stats, verify);
+    }
+}
+
+/// The constraints in the file at `path`, if any, for a board of `side` by
+/// `side` cells.
+fn read_constraints(path: Option<PathBuf>, side: usize, non_consecutive: bool) -> Constraints {
+    let mut constraints = match path {
+        Some(path) => {
+            let path_as_str = path.to_string_lossy().to_string();
+            let reader = match std::fs::File::open(path) {
+                Ok(reader) => reader,
+                Err(e) => {
+                    eprintln!(
+                        "could not open {} for reading.\nwith error {}",
+                        &path_as_str, e
+                    );
+                    std::process::exit(1);
+                }
+            };
+            match parsing::constraints::parse(reader, side) {
+                Ok(constraints) => constraints,
+                Err(e) => {
+                    println!("Constraints file malformed.");
+                    println!("{}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        None => Constraints::new(),
     };
+    constraints.non_consecutive = non_consecutive;
+    constraints
 }
 
-fn run(mut input: sudoku::Sudoku) {
-    let result = solver::backtrack(&mut input);
+/// Fills in the cells that are filled in the hint, as if they were clues.
+fn apply_hint(input: &mut sudoku::Sudoku, hint: &sudoku::Sudoku) -> Result<(), String> {
+    if hint.side() != input.side() {
+        return Err("The hint input is not the same size as the input.".to_string());
+    }
+    for raw in 0..input.side() * input.side() {
+        let hint_here = match hint.get_raw(raw).value() {
+            Some(value) => value,
+            None => continue,
+        };
+        match input.get_raw(raw).value() {
+            Some(value) if value != hint_here => {
+                return Err("The hint input is not compatible with the input's clues.".to_string())
+            }
+            Some(_) => {}
+            None => input.set_raw(raw, SudokuCell::digit(hint_here)),
+        }
+    }
+    if check::has_conflicts(input) {
+        return Err("The hint input breaks the rules, given the input's clues.".to_string());
+    }
+    Ok(())
+}
+
+fn run_unique(input: sudoku::Sudoku, constraints: Constraints, verify: bool) {
+    // Two solutions are enough to tell it is not unique.
+    let mut solutions = solver::solutions(&input, &constraints).take(2);
+    match (solutions.next(), solutions.next()) {
+        (Some(solution), None) => {
+            if verify {
+                verify_or_exit(&input, &solution, &constraints);
+            }
+            println!("UNIQUE");
+            println!("{}", solution);
+            std::process::exit(0);
+        }
+        (Some(_), Some(_)) => {
+            println!("MULTIPLE (n>=2)");
+            std::process::exit(2);
+        }
+        (None, _) => {
+            println!("NONE");
+            std::process::exit(1);
+        }
+    }
+}
+
+fn run_solutions(
+    input: sudoku::Sudoku,
+    constraints: Constraints,
+    max_solutions: usize,
+    verify: bool,
+) {
+    let mut found = 0;
+    for solution in solver::solutions(&input, &constraints).take(max_solutions) {
+        if verify {
+            verify_or_exit(&input, &solution, &constraints);
+        }
+        if found > 0 {
+            println!();
+        }
+        println!("{}", solution);
+        found += 1;
+    }
+
+    eprintln!("Found {} solution(s).", found);
+    std::process::exit(if found > 0 { 0 } else { 1 });
+}
 
-    match result {
-        Ok(()) => {
-            eprintln!("Success.");
+fn run(mut input: sudoku::Sudoku, solver: Backtrack, print_stats: bool, verify: bool) {
+    let puzzle = input.clone();
+    let side = input.side();
+    let already_solved = (0..side * side).all(|raw| !input.get_raw(raw).is_empty());
+    match solver.run(&mut input) {
+        Ok(stats) => {
+            if verify {
+                verify_or_exit(&puzzle, &input, &solver.constraints);
+            }
+            if already_solved {
+                println!("ALREADY SOLVED");
+            } else {
+                eprintln!("Success.");
+            }
+            if print_stats {
+                eprintln!("{}", stats);
+            }
             println!("{}", input);
             std::process::exit(0);
         }
@@ -135,35 +517,116 @@ fn run(mut input: sudoku::Sudoku) {
             );
             std::process::exit(1);
         }
+        Err(e @ SolveError::Exhausted) => {
+            println!("EXHAUSTED");
+            eprintln!("{}", e);
+            std::process::exit(3);
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Checks the solution against the puzzle, and the variant constraints, and
+/// exits with code 1 if anything is wrong with it.
+fn verify_or_exit(puzzle: &Sudoku, solution: &Sudoku, constraints: &Constraints) {
+    let mismatches = sudoku::verify_solution(puzzle, solution).err();
+    let broken = constraints.validate(solution).err();
+    if mismatches.is_none() && broken.is_none() {
+        return;
+    }
+
+    eprintln!("The solver reported a solution, but it is wrong!");
+    for mismatch in mismatches.unwrap_or_default() {
+        eprintln!("{}", mismatch);
+    }
+    for constraint in broken.unwrap_or_default() {
+        eprintln!("It breaks the constraint: {}", constraint);
+    }
+    eprintln!("{}", solution);
+    std::process::exit(1);
+}
+
+/// Output format of the benchmark statistics.
+#[derive(Clone, Copy)]
+enum Format {
+    Csv,
+    Json,
+}
+
+/// Benchmarks `solver` on each of the named puzzles, and writes statistics
+/// about each of them and about all of them together.
+fn run_benchmark<O: Write>(
+    puzzles: Vec<(String, sudoku::Sudoku)>,
+    solver: Backtrack,
+    format: Format,
+    mut out: BufWriter<O>,
+) {
+    eprintln!("Benchmarking {} puzzle(s).", puzzles.len());
+
+    let mut summaries = vec![];
+    let mut all_results = vec![];
+    for (name, input) in puzzles {
+        let results = time_runs(&input, &solver);
+        summaries.push((name, Summary::of(&results)));
+        all_results.extend(results);
+    }
+    let total = Summary::of(&all_results);
+
+    match format {
+        Format::Csv => {
+            writeln!(out, "puzzle,runs,failed,{}", Summary::KEYS.join(",")).unwrap();
+            for (name, summary) in &summaries {
+                writeln!(out, "{},{}", csv_field(name), summary.csv()).unwrap();
+            }
+            writeln!(out, "all,{}", total.csv()).unwrap();
+        }
+        Format::Json => {
+            let puzzles = summaries
+                .iter()
+                .map(|(name, summary)| {
+                    format!("{{\"puzzle\":{},{}}}", json_string(name), summary.json())
+                })
+                .collect::<Vec<_>>()
+                .join(",");
+            writeln!(
+                out,
+                "{{\"puzzles\":[{}],\"all\":{{{}}}}}",
+                puzzles,
+                total.json()
+            )
+            .unwrap();
+        }
     }
+
+    out.flush().unwrap();
 }
 
-fn run_benchmark<O: Write>(input: sudoku::Sudoku, mut out: BufWriter<O>) {
-    // Run the function 100 times, append the average to the file.
+/// The times of several solves of the input by `solver`, in microseconds, or
+/// `None` for the failed ones.
+fn time_runs(input: &sudoku::Sudoku, solver: &Backtrack) -> Vec<Option<u128>> {
     use std::sync::mpsc;
     use std::thread;
     use std::time;
 
     let (time_tx, time_rx) = mpsc::channel::<Option<u128>>();
     let thread_iterations = 1;
-    let thread_count = thread::available_parallelism().unwrap().get() / 2;
-
-    eprintln!(
-        "Benchmarking {} iterations.",
-        thread_iterations * thread_count
-    );
+    let thread_count = (thread::available_parallelism().unwrap().get() / 2).max(1);
 
     for _thread in 0..thread_count {
         let time_tx = time_tx.clone();
         let input = input.clone();
+        let solver = solver.clone();
         thread::spawn(move || {
             for _ in 0..thread_iterations {
                 let mut input = input.clone();
                 let now = time::Instant::now();
-                let result = solver::backtrack(&mut input);
-                let elapsed = now.elapsed().as_millis();
+                let result = solver.run(&mut input);
+                let elapsed = now.elapsed().as_micros();
                 match result {
-                    Ok(()) => time_tx.send(Some(elapsed)),
+                    Ok(_) => time_tx.send(Some(elapsed)),
                     Err(_) => time_tx.send(None),
                 }
                 .ok();
@@ -172,16 +635,106 @@ fn run_benchmark<O: Write>(input: sudoku::Sudoku, mut out: BufWriter<O>) {
     }
     drop(time_tx);
 
-    while let Ok(time) = time_rx.recv() {
-        match time {
-            Some(time) => {
-                out.write(format!("{}\n", time).as_bytes()).unwrap();
-            }
-            None => {
-                out.write("-1\n".as_bytes()).unwrap();
-            }
+    time_rx.iter().collect()
+}
+
+/// Quotes a CSV field, if needed.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// A JSON string literal.
+fn json_string(string: &str) -> String {
+    let mut literal = String::from('"');
+    for c in string.chars() {
+        match c {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            c if c.is_control() => literal.push_str(&format!("\\u{:04x}", c as u32)),
+            c => literal.push(c),
         }
     }
+    literal.push('"');
+    literal
+}
 
-    out.flush().unwrap();
+/// Statistics about the times of several runs, in microseconds.
+struct Summary {
+    runs: usize,
+    failed: usize,
+    /// The minimum, median, 95th percentile, maximum, mean and standard
+    /// deviation of the times of the successful runs, if there were any.
+    times: Option<[f64; 6]>,
+}
+
+impl Summary {
+    /// The names of the statistics of the times, in order.
+    const KEYS: [&str; 6] = [
+        "min_us",
+        "median_us",
+        "p95_us",
+        "max_us",
+        "mean_us",
+        "std_dev_us",
+    ];
+
+    /// The statistics of the given runs, with `None` for a failed run.
+    fn of(results: &[Option<u128>]) -> Self {
+        let mut times = results
+            .iter()
+            .filter_map(|&time| time.map(|time| time as f64))
+            .collect::<Vec<_>>();
+        times.sort_by(f64::total_cmp);
+
+        let n = times.len();
+        let summary = if n == 0 {
+            None
+        } else {
+            let median = if n % 2 == 0 {
+                (times[n / 2 - 1] + times[n / 2]) / 2.
+            } else {
+                times[n / 2]
+            };
+            // Nearest rank
+            let p95 = times[((0.95 * n as f64).ceil() as usize).max(1) - 1];
+            let mean = times.iter().sum::<f64>() / n as f64;
+            let variance = times.iter().map(|time| (time - mean).powi(2)).sum::<f64>() / n as f64;
+            Some([times[0], median, p95, times[n - 1], mean, variance.sqrt()])
+        };
+
+        Summary {
+            runs: results.len(),
+            failed: results.len() - n,
+            times: summary,
+        }
+    }
+
+    fn csv(&self) -> String {
+        let times = match self.times {
+            Some(times) => times.map(|time| format!("{:.1}", time)).join(","),
+            None => ",,,,,".to_string(),
+        };
+        format!("{},{},{}", self.runs, self.failed, times)
+    }
+
+    /// The statistics as the members of a JSON object.
+    fn json(&self) -> String {
+        let times = Self::KEYS
+            .iter()
+            .enumerate()
+            .map(|(i, key)| match self.times {
+                Some(times) => format!("\"{}\":{:.1}", key, times[i]),
+                None => format!("\"{}\":null", key),
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        format!(
+            "\"runs\":{},\"failed\":{},{}",
+            self.runs, self.failed, times
+        )
+    }
 }
@@ -0,0 +1,35 @@
+//! The order in which the search chooses the empty cell to guess at next.
+
+/// How the search chooses the next empty cell to guess at.
+///
+/// The cell is chosen anew after every guess, among the cells that are still
+/// empty. The digits of the chosen cell are tried in a random order, whatever
+/// the order of the cells.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Order {
+    /// The first empty cell, in reading order.
+    Static,
+    /// The cell with the fewest candidates left ("minimum remaining values"),
+    /// so that dead ends are found right away.
+    #[default]
+    Mrv,
+    /// The cell with the most empty peers, i.e., the one that constrains the
+    /// most other cells.
+    Degree,
+    /// Any empty cell, at random.
+    Random,
+}
+
+impl Order {
+    /// The order with the given name, as in `static`, `mrv`, `degree` or
+    /// `random`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "static" => Some(Order::Static),
+            "mrv" => Some(Order::Mrv),
+            "degree" => Some(Order::Degree),
+            "random" => Some(Order::Random),
+            _ => None,
+        }
+    }
+}
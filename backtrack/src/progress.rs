@@ -0,0 +1,11 @@
+use std::time::Duration;
+
+/// A snapshot of the state of the search, handed to a
+/// [`ProgressSink`](sudoku::ProgressSink) after every digit tried.
+pub struct Progress {
+    /// Number of digits tried so far.
+    pub nodes: usize,
+    /// Number of cells currently filled in by the search.
+    pub depth: usize,
+    pub elapsed: Duration,
+}
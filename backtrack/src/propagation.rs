@@ -0,0 +1,27 @@
+//! Filling in the cells of a board that are forced by the rules, without any
+//! guessing. This is what [`backtrack`](crate::backtrack) does between
+//! guesses, but it is also useful by itself, e.g., to give hints.
+
+use sudoku::constraints::Constraints;
+use sudoku::Sudoku;
+
+use crate::solver::{prepare, SolveError};
+
+/// Fills in every naked single (a cell with a single candidate left) and
+/// hidden single (a digit with a single place left in a row, column or box),
+/// repeatedly, until there are none. Returns the number of cells filled in.
+///
+/// If this runs into a contradiction, the board is infeasible, and is left as
+/// it was.
+pub fn propagate(sudoku: &mut Sudoku, constraints: &Constraints) -> Result<usize, SolveError> {
+    let empty_before = empty_cells(sudoku);
+    prepare(sudoku, constraints)?;
+    Ok(empty_before - empty_cells(sudoku))
+}
+
+fn empty_cells(sudoku: &Sudoku) -> usize {
+    let side = sudoku.side();
+    (0..side * side)
+        .filter(|&cell| sudoku.get_raw(cell).is_empty())
+        .count()
+}
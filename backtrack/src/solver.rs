@@ -1,179 +1,653 @@
-use itertools::Itertools;
-use rand::{prelude::SliceRandom, thread_rng};
-use std::collections::BTreeSet;
-use sudoku::{Sudoku, SudokuCell, SudokuCellValue};
+use rand::rngs::StdRng;
+use rand::{prelude::SliceRandom, SeedableRng};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use sudoku::check::{self, Conflict};
+use sudoku::constraints::Constraints;
+use sudoku::{Budget, NoProgress, ProgressSink, Solver, Sudoku, SudokuCell};
 
+use crate::candidates::Candidates;
+use crate::order::Order;
+use crate::progress::Progress;
+use crate::trace::Event;
+
+/// Statistics about a finished search.
+#[derive(Debug, Clone)]
+pub struct SolveStats {
+    pub elapsed: Duration,
+    /// Number of digits tried.
+    pub nodes: usize,
+    /// Number of times the search ran out of digits for a cell, and went back
+    /// to the previous one.
+    pub backtracks: usize,
+    /// Largest number of guesses the board held at once.
+    pub max_depth: usize,
+    /// Number of cells filled in because they had a single candidate left.
+    pub naked_singles: usize,
+    /// Number of cells filled in because they were the only place left for a
+    /// digit in a row, column or box.
+    pub hidden_singles: usize,
+}
+
+impl SolveStats {
+    /// The statistics of a board that was solved before any guessing.
+    fn unguessed(started: Instant, candidates: &Candidates) -> Self {
+        SolveStats {
+            elapsed: started.elapsed(),
+            nodes: 0,
+            backtracks: 0,
+            max_depth: 0,
+            naked_singles: candidates.naked_singles,
+            hidden_singles: candidates.hidden_singles,
+        }
+    }
+}
+
+impl std::fmt::Display for SolveStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "elapsed         {:?}", self.elapsed)?;
+        writeln!(f, "nodes           {}", self.nodes)?;
+        writeln!(f, "backtracks      {}", self.backtracks)?;
+        writeln!(f, "max depth       {}", self.max_depth)?;
+        writeln!(f, "naked singles   {}", self.naked_singles)?;
+        write!(f, "hidden singles  {}", self.hidden_singles)
+    }
+}
+
+#[derive(Debug)]
 pub enum SolveError {
     Infeasible,
+    /// Some of the clues already hold the same digit in a row, column or box.
+    ConflictingClues {
+        side: usize,
+        conflicts: Vec<Conflict>,
+    },
+    /// The search tried as many digits, or ran for as long, as its budget
+    /// allows.
+    Exhausted,
+    /// The search was cancelled through the cancel flag of its budget.
+    Cancelled,
 }
 
-pub fn backtrack(sudoku: &mut Sudoku) -> Result<(), SolveError> {
-    // Start by making a list of compatible digits
-    let side = sudoku.side();
-    let box_side = sudoku.box_side();
-    let digit_range = box_side * box_side;
-    let mut incompatible = vec![BTreeSet::<usize>::new(); side * side];
-
-    // Iterate over pairs of elements.
-    // We should only consider a pair if both elements lie on the same row,
-    // or the same column, or are in the same box. We disregard pairs of that
-    // are the same element twice.
-    // TODO: This could probably be optimized.
-    let pairs_to_check = (0..side)
-        .cartesian_product(0..side)
-        .tuple_combinations()
-        .filter(|((r, c), (rr, cc))| {
-            if r == rr && c == cc {
-                return false; // This should never happen, due to the behavior of tuple_combinations()
-            }
-            if r == rr || c == cc {
-                return true;
+impl std::fmt::Display for SolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SolveError::Infeasible => write!(f, "The input board is infeasible."),
+            SolveError::ConflictingClues { side, conflicts } => {
+                write!(f, "The clues of the input board conflict with each other:")?;
+                for conflict in conflicts {
+                    let (a, b) = conflict.cells;
+                    write!(
+                        f,
+                        "\n    r{}c{} and r{}c{} both hold {}",
+                        a / side + 1,
+                        a % side + 1,
+                        b / side + 1,
+                        b % side + 1,
+                        conflict.value
+                    )?;
+                }
+                Ok(())
             }
-            (r / box_side) == (rr / box_side) && (c / box_side) == (cc / box_side)
-        });
+            SolveError::Exhausted => write!(f, "The search ran out of budget."),
+            SolveError::Cancelled => write!(f, "The search was cancelled."),
+        }
+    }
+}
 
-    let mut subject_to = |this: (usize, usize), that: (usize, usize)| {
-        let index = this.0 * side + this.1;
-        let this_cell = sudoku.get(this.0, this.1);
+impl std::error::Error for SolveError {}
 
-        if this_cell.is_empty() {
-            if let Some(value) = sudoku.get(that.0, that.1).value() {
-                incompatible[index].insert(value);
+impl From<SolveError> for sudoku::SolveError {
+    fn from(error: SolveError) -> Self {
+        match error {
+            SolveError::Infeasible | SolveError::ConflictingClues { .. } => {
+                sudoku::SolveError::Infeasible
             }
-        } else {
-            incompatible[index].extend(1..=digit_range);
+            SolveError::Exhausted => sudoku::SolveError::IterationsExhausted,
+            SolveError::Cancelled => sudoku::SolveError::Cancelled,
         }
-    };
+    }
+}
 
-    for (left, right) in pairs_to_check {
-        subject_to(left, right);
-        subject_to(right, left);
-    }
-
-    drop(subject_to);
-
-    // Now let us sort the cells by ascending cardinality OF COMPATIBILITY
-    // Since we've kept track of the incompatible digits, this means sorting
-    // the elements of `incompatible` by DESCENDING cardinality.
-    // We also need to sort the indices in the same way, to know what corresponds
-    // to what
-    // Since we're iterating over the elements of `incompatible`, let's also turn them
-    // into the elements that ARE compatible, into a vec sorted by ascending order.
-    // NOTE also: we shuffle to compatible digits around, to try to defeat adversarial
-    // pathological cases.
-    let (indices, compatible): (Vec<usize>, Vec<Vec<usize>>) = incompatible
-        .into_iter()
-        .map(|set| {
-            (1..=digit_range)
-                .filter(|d| !set.contains(d))
-                .collect::<Vec<usize>>()
-        })
-        .enumerate() // Important to enumerate before filtering out!
-        .filter(|(_, x)| x.len() > 0)
-        .map(|(i, mut x)| {
-            x.shuffle(&mut thread_rng());
-            (i, x)
-        })
-        .sorted_unstable_by_key(|(_i, x)| x.len() as isize)
-        .unzip();
-
-    // Start doing the backtracking
-    let mut depth = 0; // The index of the string character being tested.
-    let mut pointer = vec![0_usize; indices.len()]; // The character being tested, for each depth.
-    loop {
-        // Have we exhausted the possibilities at this depth?
-        if pointer[depth] == compatible[depth].len() {
-            if depth == 0 {
-                // Root node ran out of options
-                return Err(SolveError::Infeasible);
-            } else {
-                sudoku.set_raw(indices[depth], SudokuCell::Empty);
-                pointer[depth] = 0;
-
-                pointer[depth - 1] += 1;
-                depth -= 1;
-                continue;
-            }
-        }
+/// The backtracking solver, as a [`Solver`], with the options of
+/// [`backtrack`].
+#[derive(Clone, Default)]
+pub struct Backtrack {
+    /// Variant constraints the solution must also follow.
+    pub constraints: Constraints,
+    /// How the next cell to guess at is chosen.
+    pub order: Order,
+    pub budget: Budget,
+    /// Seed for the order in which digits are tried; see [`backtrack`].
+    pub seed: Option<u64>,
+    /// Whether to split the search across threads, as [`backtrack_parallel`]
+    /// does.
+    pub parallel: bool,
+}
 
-        let next_guess = compatible[depth][pointer[depth]];
-        //println!("Trying depth {}, character {}", depth, pointer[depth]);
-        sudoku.set_raw(indices[depth], SudokuCell::Digit(next_guess));
-
-        //println!("{}", sudoku);
-        //std::io::stdin().read_line(&mut String::new()).ok();
-
-        // If constraint is violated, try the next compatible digit
-        // We only need to check whether the new addition violates a constraint,
-        //  because we knew that we were in a sane state the previous iteration.
-        if violates_constraints(&sudoku, indices[depth], next_guess) {
-            // We don't need to undo the previous set_raw because it'll be overridden
-            // in the next pass, either by a new value, or with Empty when we backtrack
-            // to the above depth.
-            pointer[depth] += 1;
+impl Backtrack {
+    /// Like [`Solver::solve`], but telling apart the ways the search can
+    /// fail.
+    pub fn run(&self, sudoku: &mut Sudoku) -> Result<SolveStats, SolveError> {
+        if self.parallel {
+            backtrack_parallel(
+                sudoku,
+                &self.constraints,
+                self.order,
+                self.seed,
+                &self.budget,
+            )
         } else {
-            // Otherwise, this stays feasible
+            backtrack(
+                sudoku,
+                &self.constraints,
+                self.order,
+                self.seed,
+                &self.budget,
+                &mut NoProgress,
+            )
+        }
+    }
+}
 
-            // Have we reached a fully feasible state?
-            if depth == compatible.len() - 1 {
-                break; // Success; we've reached a leaf.
-            } else {
-                depth += 1;
+impl Solver for Backtrack {
+    type Report = SolveStats;
+
+    fn solve(&self, sudoku: &mut Sudoku) -> Result<SolveStats, sudoku::SolveError> {
+        Ok(self.run(sudoku)?)
+    }
+}
+
+/// Solves the board in place.
+///
+/// The digits of each cell are tried in a random order, so that the solution
+/// found is not biased towards small digits. Given a seed, the order, and so
+/// the solution, is the same from run to run.
+pub fn backtrack(
+    sudoku: &mut Sudoku,
+    constraints: &Constraints,
+    order: Order,
+    seed: Option<u64>,
+    budget: &Budget,
+    progress: &mut dyn ProgressSink<Progress>,
+) -> Result<SolveStats, SolveError> {
+    search(sudoku, constraints, order, seed, budget, progress, None)
+}
+
+/// Solves a copy of the board, which is left as it was, whether or not the
+/// search succeeds.
+pub fn solve(sudoku: &Sudoku, constraints: &Constraints) -> Result<Sudoku, SolveError> {
+    let mut solution = sudoku.clone();
+    backtrack(
+        &mut solution,
+        constraints,
+        Order::default(),
+        None,
+        &Budget::unlimited(),
+        &mut NoProgress,
+    )?;
+    Ok(solution)
+}
+
+/// Like [`backtrack`], but with the top of the search split across threads.
+///
+/// After filling in the cells that need no guessing, the first cell that does
+/// is found, and each of its candidates is searched from in a thread of its
+/// own. The first thread to find a solution stops the others.
+///
+/// The budget applies to each thread separately, and the statistics are those
+/// of the thread that found the solution. As the threads race, the solution
+/// found may differ from run to run even given a seed.
+pub fn backtrack_parallel(
+    sudoku: &mut Sudoku,
+    constraints: &Constraints,
+    order: Order,
+    seed: Option<u64>,
+    budget: &Budget,
+) -> Result<SolveStats, SolveError> {
+    let started = Instant::now();
+
+    let candidates = prepare(sudoku, constraints)?;
+    if is_complete(sudoku) {
+        return Ok(SolveStats::unguessed(started, &candidates));
+    }
+    let branch = next_branch(sudoku, &candidates, order, &mut rng(seed))
+        .expect("The board has empty cells.");
+
+    let found = AtomicBool::new(false);
+    let results = thread::scope(|scope| {
+        let threads = branch
+            .digits
+            .iter()
+            .map(|&digit| {
+                let mut fork = sudoku.clone();
+                fork.set_raw(branch.cell, SudokuCell::digit(digit));
+                let found = &found;
+                scope.spawn(move || {
+                    let result = search(
+                        &mut fork,
+                        constraints,
+                        order,
+                        seed,
+                        budget,
+                        &mut NoProgress,
+                        Some(found),
+                    );
+                    if result.is_ok() {
+                        found.store(true, Ordering::Relaxed);
+                    }
+                    (fork, result)
+                })
+            })
+            .collect::<Vec<_>>();
+        threads
+            .into_iter()
+            .map(|thread| thread.join().expect("A search thread panicked."))
+            .collect::<Vec<_>>()
+    });
+
+    // Threads stopped by a sibling's solution also report being cancelled,
+    // so a solution takes precedence.
+    let mut gave_up = None;
+    for (fork, result) in results {
+        match result {
+            Ok(stats) => {
+                *sudoku = fork;
+                return Ok(SolveStats {
+                    elapsed: started.elapsed(),
+                    ..stats
+                });
             }
+            Err(SolveError::Infeasible) => {}
+            Err(error) => gave_up = Some(error),
         }
     }
+    Err(gave_up.unwrap_or(SolveError::Infeasible))
+}
 
-    Ok(())
+/// Fails with [`SolveError::ConflictingClues`] if any two clues break the
+/// usual rules, which would otherwise only show after a search of the whole
+/// board.
+pub fn check_clues(sudoku: &Sudoku) -> Result<(), SolveError> {
+    let conflicts = check::conflicts(sudoku);
+    if conflicts.is_empty() {
+        return Ok(());
+    }
+    Err(SolveError::ConflictingClues {
+        side: sudoku.side(),
+        conflicts,
+    })
+}
+
+/// Checks the clues, and fills in the cells that need no guessing, returning
+/// the candidates left for the others. On failure, the board is left as it
+/// was.
+pub(crate) fn prepare(
+    sudoku: &mut Sudoku,
+    constraints: &Constraints,
+) -> Result<Candidates, SolveError> {
+    // The search only checks the constraints involving the cells it fills in,
+    // so the clues had better not break any to begin with.
+    check_clues(sudoku)?;
+    if constraints.validate(sudoku).is_err() {
+        return Err(SolveError::Infeasible);
+    }
+
+    // Cells with a single candidate, and digits with a single place in a
+    // unit, need no guessing at all, and filling them in often leaves more of
+    // them. Most published puzzles are solved by this alone.
+    let mut candidates = Candidates::new(sudoku, constraints);
+    if !candidates.propagate(sudoku, constraints) {
+        candidates.undo(sudoku, 0);
+        return Err(SolveError::Infeasible);
+    }
+    Ok(candidates)
 }
 
-fn violates_constraints(sudoku: &Sudoku, last_changed: usize, new_value: usize) -> bool {
+/// Whether every cell of the board is filled in.
+fn is_complete(sudoku: &Sudoku) -> bool {
     let side = sudoku.side();
-    let box_side = sudoku.box_side();
-    let (r, c) = (last_changed / side, last_changed % side);
+    (0..side * side).all(|cell| !sudoku.get_raw(cell).is_empty())
+}
 
-    // Check row
-    for cc in 0..side {
-        if cc == c {
-            continue;
-        }
-        let element = sudoku.get(r, cc);
-        if let Some(value) = element.value() {
-            if value == new_value {
-                return true;
+/// The search of [`backtrack`], which also gives up as soon as `stop` is set.
+fn search(
+    sudoku: &mut Sudoku,
+    constraints: &Constraints,
+    order: Order,
+    seed: Option<u64>,
+    budget: &Budget,
+    progress: &mut dyn ProgressSink<Progress>,
+    stop: Option<&AtomicBool>,
+) -> Result<SolveStats, SolveError> {
+    let started = Instant::now();
+
+    let mut propagated = sudoku.clone();
+    let candidates = prepare(&mut propagated, constraints)?;
+    if is_complete(&propagated) {
+        // Nothing needs guessing, so there is no search to set up.
+        *sudoku = propagated;
+        return Ok(SolveStats::unguessed(started, &candidates));
+    }
+
+    let mut search =
+        Search::with_candidates(propagated, constraints, candidates, order, seed, started);
+    let found = search.next_solution(budget, progress, stop);
+    sudoku.clone_from(&search.sudoku);
+    match found? {
+        true => Ok(search.stats()),
+        false => Err(SolveError::Infeasible),
+    }
+}
+
+/// The number of solutions of the board, counting up to `limit`.
+///
+/// Unlike [`backtrack`], this carries on searching after the first solution,
+/// so e.g. a limit of 2 tells whether the solution is unique. An infeasible
+/// board has no solutions.
+pub fn count_solutions(sudoku: &Sudoku, constraints: &Constraints, limit: usize) -> usize {
+    solutions(sudoku, constraints).take(limit).count()
+}
+
+/// Every solution of the board, in no particular order. Use
+/// [`Iterator::take`] to only look for so many of them.
+pub fn solutions<'c>(sudoku: &Sudoku, constraints: &'c Constraints) -> Solutions<'c> {
+    Solutions {
+        search: Search::new(sudoku.clone(), constraints, Order::default(), None).ok(),
+    }
+}
+
+/// The iterator returned by [`solutions`].
+pub struct Solutions<'c> {
+    /// The search, until it is over.
+    search: Option<Search<'c>>,
+}
+
+impl Iterator for Solutions<'_> {
+    type Item = Sudoku;
+
+    fn next(&mut self) -> Option<Sudoku> {
+        let search = self.search.as_mut()?;
+        match search.next_solution(&Budget::unlimited(), &mut NoProgress, None) {
+            Ok(true) => Some(search.sudoku.clone()),
+            _ => {
+                self.search = None;
+                None
             }
         }
     }
+}
+
+/// A backtracking search over a board, which can be carried on after a
+/// solution to look for the next one.
+pub(crate) struct Search<'c> {
+    /// The board, as far as the search got.
+    sudoku: Sudoku,
+    constraints: &'c Constraints,
+    candidates: Candidates,
+    /// The cells filled in by guessing so far.
+    stack: Vec<Branch>,
+    /// Whether there is nothing left to search.
+    finished: bool,
+    /// What came of the last digit tried, to be reported by the next step.
+    pending: Option<Event>,
+    order: Order,
+    /// Shuffles the digits of each cell, and chooses the cells with
+    /// [`Order::Random`].
+    rng: StdRng,
+    started: Instant,
+    nodes: usize,
+    backtracks: usize,
+    max_depth: usize,
+}
 
-    // Check column
-    for rr in 0..side {
-        if rr == r {
-            continue;
+impl<'c> Search<'c> {
+    /// Sets up the search, filling in the cells that need no guessing. If that
+    /// already shows the board to be infeasible, there is nothing to search.
+    pub(crate) fn new(
+        mut sudoku: Sudoku,
+        constraints: &'c Constraints,
+        order: Order,
+        seed: Option<u64>,
+    ) -> Result<Self, SolveError> {
+        let started = Instant::now();
+        let candidates = prepare(&mut sudoku, constraints)?;
+        Ok(Search::with_candidates(
+            sudoku,
+            constraints,
+            candidates,
+            order,
+            seed,
+            started,
+        ))
+    }
+
+    /// Sets up the search over a board already prepared by [`prepare`].
+    fn with_candidates(
+        sudoku: Sudoku,
+        constraints: &'c Constraints,
+        candidates: Candidates,
+        order: Order,
+        seed: Option<u64>,
+        started: Instant,
+    ) -> Self {
+        // The search is a stack of the cells filled in so far, each with the
+        // digits left to try there. Rather than fixing the order of the cells
+        // up front, the next cell is chosen anew after every assignment; by
+        // default, as the one with the fewest candidates left ("minimum
+        // remaining values"). This way dead ends, i.e. cells with no
+        // candidates, are found right away, and forced cells are filled in
+        // before any guessing.
+        let mut rng = rng(seed);
+        let stack = match next_branch(&sudoku, &candidates, order, &mut rng) {
+            Some(branch) => vec![branch],
+            None => vec![], // Nothing left to fill in
+        };
+        let max_depth = stack.len();
+        Search {
+            sudoku,
+            constraints,
+            candidates,
+            stack,
+            finished: false,
+            pending: None,
+            order,
+            rng,
+            started,
+            nodes: 0,
+            backtracks: 0,
+            max_depth,
+        }
+    }
+
+    fn stats(&self) -> SolveStats {
+        SolveStats {
+            elapsed: self.started.elapsed(),
+            nodes: self.nodes,
+            backtracks: self.backtracks,
+            max_depth: self.max_depth,
+            naked_singles: self.candidates.naked_singles,
+            hidden_singles: self.candidates.hidden_singles,
         }
-        if let Some(value) = sudoku.get(rr, c).value() {
-            if value == new_value {
-                return true;
+    }
+
+    /// Searches on until the next solution, which is left on the board.
+    /// Returns `false` if there are no more solutions.
+    fn next_solution(
+        &mut self,
+        budget: &Budget,
+        progress: &mut dyn ProgressSink<Progress>,
+        stop: Option<&AtomicBool>,
+    ) -> Result<bool, SolveError> {
+        loop {
+            if budget.is_cancelled() || stop.is_some_and(|stop| stop.load(Ordering::Relaxed)) {
+                return Err(SolveError::Cancelled);
+            }
+            if budget.is_exhausted(&self.started, self.nodes) {
+                return Err(SolveError::Exhausted);
+            }
+
+            match self.step() {
+                None => return Ok(false),
+                Some(Event::Solved) => return Ok(true),
+                Some(Event::TryDigit { .. }) => progress.on_progress(&Progress {
+                    nodes: self.nodes,
+                    depth: self.stack.len(),
+                    elapsed: self.started.elapsed(),
+                }),
+                Some(_) => {}
             }
         }
     }
 
-    // Check box
-    for h in 0..box_side {
-        for v in 0..box_side {
-            let rr = box_side * (r / box_side) + v;
-            let cc = box_side * (c / box_side) + h;
+    /// Takes the next step of the search, or returns `None` if the search is
+    /// over.
+    pub(crate) fn step(&mut self) -> Option<Event> {
+        if let Some(event) = self.pending.take() {
+            return Some(event);
+        }
+
+        let branch = match self.stack.last_mut() {
+            Some(branch) => branch,
+            None => {
+                // Either nothing needed guessing, and the board as it was set
+                // up is the only solution, or the search is over.
+                if self.finished {
+                    return None;
+                }
+                self.finished = true;
+                return Some(Event::Solved);
+            }
+        };
+        let side = self.sudoku.side();
+        let (row, column) = (branch.cell / side, branch.cell % side);
 
-            if rr == r || cc == c {
-                // we've already checked same row & same col
-                continue;
+        // Undo what the previous digit tried here led to.
+        self.candidates.undo(&mut self.sudoku, branch.checkpoint);
+
+        // Have we exhausted the possibilities for this cell?
+        let digit = match branch.digits.pop() {
+            Some(digit) => digit,
+            None => {
+                self.sudoku.set_raw(branch.cell, SudokuCell::Empty);
+                self.stack.pop();
+                self.backtracks += 1;
+                if self.stack.is_empty() {
+                    // The first cell guessed ran out of options.
+                    self.finished = true;
+                }
+                return Some(Event::Backtrack { row, column });
             }
+        };
 
-            if let Some(value) = sudoku.get(rr, cc).value() {
-                if value == new_value {
-                    return true;
+        self.nodes += 1;
+        self.sudoku.set_raw(branch.cell, SudokuCell::digit(digit));
+
+        // The candidates are kept up to date with the board, so the digit
+        // cannot break the usual rules; but it may leave a peer without
+        // candidates, or break a variant constraint, either right away or
+        // once the singles it leaves are filled in. If so, the next step
+        // tries the next digit.
+        let cell = branch.cell;
+        self.pending = if self
+            .candidates
+            .eliminate_from_peers(&self.sudoku, cell, digit)
+            && !self.constraints.is_broken_at(&self.sudoku, cell)
+            && self
+                .candidates
+                .propagate(&mut self.sudoku, self.constraints)
+        {
+            match next_branch(&self.sudoku, &self.candidates, self.order, &mut self.rng) {
+                Some(branch) => {
+                    self.stack.push(branch);
+                    self.max_depth = self.max_depth.max(self.stack.len());
+                    None
                 }
+                None => Some(Event::Solved), // Every cell is filled in.
             }
-        }
+        } else {
+            Some(Event::Contradiction { row, column, digit })
+        };
+
+        Some(Event::TryDigit { row, column, digit })
+    }
+
+    /// The board, as far as the search got.
+    pub(crate) fn sudoku(&self) -> &Sudoku {
+        &self.sudoku
     }
+}
+
+/// An empty cell, and the digits that are left to try in it.
+struct Branch {
+    cell: usize,
+    digits: Vec<usize>,
+    /// The candidates as they were before trying any digit here.
+    checkpoint: usize,
+}
 
-    return false;
+/// The empty cell to guess at next, as chosen by `order`, or `None` if the
+/// board is full.
+///
+/// The candidates are shuffled, to try to defeat adversarial pathological
+/// cases.
+fn next_branch(
+    sudoku: &Sudoku,
+    candidates: &Candidates,
+    order: Order,
+    rng: &mut StdRng,
+) -> Option<Branch> {
+    let side = sudoku.side();
+    let mut empty = (0..side * side).filter(|&cell| sudoku.get_raw(cell).is_empty());
+    let cell = match order {
+        Order::Static => empty.next()?,
+        Order::Mrv => empty.min_by_key(|&cell| candidates.count(cell))?,
+        Order::Degree => empty.min_by_key(|&cell| {
+            let empty_peers = candidates
+                .peers(cell)
+                .iter()
+                .filter(|&&peer| sudoku.get_raw(peer).is_empty())
+                .count();
+            std::cmp::Reverse(empty_peers)
+        })?,
+        Order::Random => *empty.collect::<Vec<_>>().choose(rng)?,
+    };
+    let mut digits = candidates.digits(cell);
+    digits.shuffle(rng);
+    Some(Branch {
+        cell,
+        digits,
+        checkpoint: candidates.checkpoint(),
+    })
+}
+
+/// A random number generator seeded with `seed`, or from the system's entropy
+/// if there is none.
+fn rng(seed: Option<u64>) -> StdRng {
+    match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    }
+}
+
+#[cfg(all(test, feature = "proptest"))]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use sudoku::arbitrary::{invalid, partial};
+    use sudoku::verify_solution;
+
+    proptest! {
+        #[test]
+        fn solutions_pass_verification(puzzle in (2_usize..=3).prop_flat_map(partial)) {
+            let solution = solve(&puzzle, &Constraints::new()).unwrap();
+            prop_assert_eq!(verify_solution(&puzzle, &solution), Ok(()));
+        }
+
+        #[test]
+        fn invalid_boards_have_no_solution(puzzle in (2_usize..=3).prop_flat_map(invalid)) {
+            prop_assert!(solve(&puzzle, &Constraints::new()).is_err());
+        }
+    }
 }
@@ -0,0 +1,73 @@
+//! The backtracking search, step by step, e.g. to animate it.
+
+use sudoku::constraints::Constraints;
+use sudoku::Sudoku;
+
+use crate::order::Order;
+use crate::solver::Search;
+
+/// A step of the search. Cells are given by their row and column, counting
+/// from 0.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    /// A digit was filled in a cell, as a guess. It is followed by a
+    /// [`Event::Contradiction`] if the digit cannot stay, and by
+    /// [`Event::Solved`] if the board is now full.
+    TryDigit {
+        row: usize,
+        column: usize,
+        digit: usize,
+    },
+    /// The digit just tried leads to a contradiction, and will be replaced.
+    Contradiction {
+        row: usize,
+        column: usize,
+        digit: usize,
+    },
+    /// Every digit was tried in a cell, which was emptied again, and the
+    /// search goes back to the previous guess.
+    Backtrack { row: usize, column: usize },
+    /// The board is a solution. The search can carry on, to look for more.
+    Solved,
+}
+
+/// The steps of the search of a board, as [`Event`]s, as it looks for every
+/// solution. Stop at the first [`Event::Solved`] to follow the search of
+/// [`backtrack`](crate::backtrack).
+///
+/// Besides the guesses, the search fills in the cells that need no guessing,
+/// which is not reported as steps; [`Trace::sudoku`] gives the board as it is
+/// after each step.
+pub struct Trace<'c> {
+    /// The search, unless the board was infeasible from the start.
+    search: Option<Search<'c>>,
+    /// The board as it was given.
+    input: Sudoku,
+}
+
+/// Traces the search of the board.
+pub fn trace<'c>(sudoku: &Sudoku, constraints: &'c Constraints) -> Trace<'c> {
+    Trace {
+        search: Search::new(sudoku.clone(), constraints, Order::default(), None).ok(),
+        input: sudoku.clone(),
+    }
+}
+
+impl Trace<'_> {
+    /// The board, as far as the search got; or the board as it was given, if
+    /// it was infeasible from the start.
+    pub fn sudoku(&self) -> &Sudoku {
+        match &self.search {
+            Some(search) => search.sudoku(),
+            None => &self.input,
+        }
+    }
+}
+
+impl Iterator for Trace<'_> {
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Event> {
+        self.search.as_mut()?.step()
+    }
+}
@@ -0,0 +1,9 @@
+//! Dancing links (Knuth's Algorithm X) solver for sudoku, as a library.
+//!
+//! The `dlx` binary is a thin command line wrapper around [`solve`] and
+//! [`count_solutions`].
+
+pub mod solver;
+
+pub use solver::{count_solutions, solve, Dlx};
+pub use sudoku::SolveError;
@@ -0,0 +1,148 @@
+use std::path::PathBuf;
+use sudoku::*;
+
+const HEADER: &str = r#"dancing links solver for sudoku
+"#;
+
+const USAGE: &str = r#"
+Usage:
+    dlx [--verify] <input file>
+    dlx --count <limit> <input file>
+    dlx --help
+
+Options:
+    --count <limit>     Count the solutions of the input, up to <limit>,
+                        instead of solving it.
+    --verify            Check the solution against the input before printing
+                        it.
+    --help              Print help information.
+"#;
+
+const LONG_HELP: &str = concat!(
+    r#"
+An input file of "-" denotes the input data should be read from the standard
+input. The input file is expected to be in .sudoku format.
+
+The input is solved with Knuth's Algorithm X, using dancing links, over the
+exact cover formulation of the sudoku.
+
+If a solution is found, it is printed to stdout in .sudoku format, and the
+program exits with code 0. If the input has no solution, or other errors
+occur, these are reported to stderr, and the program exits with code 1.
+
+With --verify, the solution is checked once more before it is printed: it must
+be complete, agree with every clue, and break none of the rules. If it does
+not, what is wrong with it is reported to stderr, along with the board, and the
+program exits with code 1.
+
+With --count, the number of solutions is printed to stdout instead. The search
+stops after <limit> solutions, so that under-constrained inputs finish in
+reasonable time; a count equal to the limit means "at least this many".
+"#,
+    include_str!("../../FORMATTING.txt")
+);
+
+fn main() {
+    let mut args = std::env::args().skip(1); // Skip the filename
+
+    let mut input: Option<Result<Sudoku, String>> = None;
+    let mut count_limit: Option<usize> = None;
+    let mut verify = false;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--help" => {
+                println!("{}", HEADER);
+                println!("{}", USAGE);
+                println!("{}", LONG_HELP);
+                std::process::exit(0);
+            }
+            "--verify" => verify = true,
+            "--count" => {
+                let limit = args.next().map(|n| n.replace('_', "").parse::<usize>());
+                match limit {
+                    Some(Ok(limit)) => count_limit = Some(limit),
+                    _ => {
+                        eprintln!("Expected a limit after --count.");
+                        eprintln!("{}", USAGE);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            _ if input.is_some() => {
+                eprintln!("Too many arguments!");
+                eprintln!("{}", USAGE);
+                std::process::exit(1);
+            }
+            "-" => input = Some(parsing::sudoku::parse(std::io::stdin())),
+            path => {
+                let path = PathBuf::from(path);
+                let path_as_str = path.clone().to_string_lossy().to_string();
+                if !path.exists() {
+                    eprintln!("{} does not exist.", &path_as_str);
+                    std::process::exit(1);
+                }
+
+                let reader = std::fs::File::open(path);
+                if let Err(e) = reader {
+                    eprintln!(
+                        "Could not open {} for reading.\nWith error {}",
+                        &path_as_str, e
+                    );
+                    std::process::exit(1);
+                }
+                input = Some(parsing::sudoku::parse(reader.unwrap()));
+            }
+        }
+    }
+
+    let mut input = match input {
+        Some(Ok(input)) => input,
+        Some(Err(e)) => {
+            eprintln!("Input board malformed.");
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        None => {
+            eprintln!("No sudoku file specified.");
+            eprintln!("{}", USAGE);
+            std::process::exit(1);
+        }
+    };
+
+    if let Some(limit) = count_limit {
+        println!("{}", dlx::count_solutions(&input, limit));
+        std::process::exit(0);
+    }
+
+    let puzzle = input.clone();
+    match dlx::solve(&mut input) {
+        Ok(()) => {
+            if verify {
+                verify_or_exit(&puzzle, &input);
+            }
+            println!("{}", input);
+            std::process::exit(0);
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Checks the solution against the puzzle, and exits with code 1 if anything
+/// is wrong with it.
+fn verify_or_exit(puzzle: &Sudoku, solution: &Sudoku) {
+    let mismatches = match sudoku::verify_solution(puzzle, solution) {
+        Ok(()) => return,
+        Err(mismatches) => mismatches,
+    };
+
+    eprintln!("The solver reported a solution, but it is wrong!");
+    for mismatch in mismatches {
+        eprintln!("{}", mismatch);
+    }
+    eprintln!("{}", solution);
+    std::process::exit(1);
+}
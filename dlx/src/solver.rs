@@ -0,0 +1,242 @@
+use sudoku::{SolveError, Solver, Sudoku, SudokuCell, SudokuCellValue};
+
+/// The dancing links solver, as a [`Solver`]. It has no options.
+#[derive(Clone, Copy, Default)]
+pub struct Dlx;
+
+impl Solver for Dlx {
+    type Report = ();
+
+    fn solve(&self, sudoku: &mut Sudoku) -> Result<(), SolveError> {
+        solve(sudoku)
+    }
+}
+
+/// Solves the board in place. If there is no solution, the board is left
+/// untouched.
+pub fn solve(sudoku: &mut Sudoku) -> Result<(), SolveError> {
+    let side = sudoku.side();
+    let mut links = Links::for_sudoku(sudoku);
+
+    let mut solution = None;
+    links.search(&mut vec![], &mut |rows| {
+        solution = Some(rows.to_vec());
+        true
+    });
+
+    match solution {
+        Some(rows) => {
+            for row in rows {
+                let (raw, d) = (row / side, row % side);
+                sudoku.set_raw(raw, SudokuCell::digit(d + 1));
+            }
+            Ok(())
+        }
+        None => Err(SolveError::Infeasible),
+    }
+}
+
+/// Counts the solutions of the board, stopping once `limit` solutions have
+/// been found.
+pub fn count_solutions(sudoku: &Sudoku, limit: usize) -> usize {
+    let mut links = Links::for_sudoku(sudoku);
+
+    let mut count = 0;
+    if limit > 0 {
+        links.search(&mut vec![], &mut |_| {
+            count += 1;
+            count >= limit
+        });
+    }
+    count
+}
+
+/// The exact cover matrix, as a toroidal doubly linked list (Knuth's dancing
+/// links), stored in flat vectors.
+///
+/// Node 0 is the root, and nodes 1 through the number of columns are the
+/// column headers; the remaining nodes are the 1s of the matrix.
+struct Links {
+    left: Vec<usize>,
+    right: Vec<usize>,
+    up: Vec<usize>,
+    down: Vec<usize>,
+    /// The column header of each node.
+    column: Vec<usize>,
+    /// The matrix row of each node (meaningless for headers).
+    row: Vec<usize>,
+    /// The number of 1s in each column, indexed by header.
+    size: Vec<usize>,
+}
+
+impl Links {
+    fn new(columns: usize) -> Self {
+        let headers = columns + 1;
+        Links {
+            left: (0..headers).map(|i| (i + headers - 1) % headers).collect(),
+            right: (0..headers).map(|i| (i + 1) % headers).collect(),
+            up: (0..headers).collect(),
+            down: (0..headers).collect(),
+            column: (0..headers).collect(),
+            row: vec![usize::MAX; headers],
+            size: vec![0; headers],
+        }
+    }
+
+    /// Builds the standard exact cover encoding of a sudoku. There is one
+    /// matrix row per candidate (cell, digit), which covers four columns:
+    ///  - the cell is filled,
+    ///  - the digit appears in the row,
+    ///  - the digit appears in the column,
+    ///  - the digit appears in the box.
+    ///
+    /// Matrix rows are indexed by `raw * side + digit - 1`, and clue cells only
+    /// get a row for their clue.
+    fn for_sudoku(sudoku: &Sudoku) -> Self {
+        let side = sudoku.side();
+        let box_side = sudoku.box_side();
+        let cells = side * side;
+
+        let mut links = Links::new(4 * cells);
+        for r in 0..side {
+            for c in 0..side {
+                let b = (r / box_side) * box_side + c / box_side;
+                let clue = sudoku.get(r, c).value();
+                for d in 0..side {
+                    if clue.is_some_and(|clue| clue - 1 != d) {
+                        continue;
+                    }
+                    links.add_row(
+                        (r * side + c) * side + d,
+                        &[
+                            r * side + c,
+                            cells + r * side + d,
+                            2 * cells + c * side + d,
+                            3 * cells + b * side + d,
+                        ],
+                    );
+                }
+            }
+        }
+        links
+    }
+
+    fn add_row(&mut self, row: usize, columns: &[usize]) {
+        let first = self.left.len();
+        for (k, &column) in columns.iter().enumerate() {
+            let header = column + 1;
+            let node = first + k;
+
+            // Vertically, insert at the bottom of the column
+            self.up.push(self.up[header]);
+            self.down.push(header);
+            let above = self.up[header];
+            self.down[above] = node;
+            self.up[header] = node;
+            self.column.push(header);
+            self.row.push(row);
+            self.size[header] += 1;
+
+            // Horizontally, insert at the end of the row
+            if k == 0 {
+                self.left.push(node);
+                self.right.push(node);
+            } else {
+                let last = self.left[first];
+                self.left.push(last);
+                self.right.push(first);
+                self.right[last] = node;
+                self.left[first] = node;
+            }
+        }
+    }
+
+    fn cover(&mut self, header: usize) {
+        let (l, r) = (self.left[header], self.right[header]);
+        self.right[l] = r;
+        self.left[r] = l;
+
+        let mut i = self.down[header];
+        while i != header {
+            let mut j = self.right[i];
+            while j != i {
+                let (u, d) = (self.up[j], self.down[j]);
+                self.down[u] = d;
+                self.up[d] = u;
+                self.size[self.column[j]] -= 1;
+                j = self.right[j];
+            }
+            i = self.down[i];
+        }
+    }
+
+    fn uncover(&mut self, header: usize) {
+        let mut i = self.up[header];
+        while i != header {
+            let mut j = self.left[i];
+            while j != i {
+                let (u, d) = (self.up[j], self.down[j]);
+                self.down[u] = j;
+                self.up[d] = j;
+                self.size[self.column[j]] += 1;
+                j = self.left[j];
+            }
+            i = self.up[i];
+        }
+
+        let (l, r) = (self.left[header], self.right[header]);
+        self.right[l] = header;
+        self.left[r] = header;
+    }
+
+    /// Algorithm X. Calls `on_solution` with the matrix rows of every exact
+    /// cover found, until it returns `true`. Returns whether the search was
+    /// stopped that way.
+    fn search(
+        &mut self,
+        partial: &mut Vec<usize>,
+        on_solution: &mut dyn FnMut(&[usize]) -> bool,
+    ) -> bool {
+        if self.right[0] == 0 {
+            return on_solution(partial);
+        }
+
+        // Branch on the column with the fewest candidates
+        let mut header = self.right[0];
+        let mut j = self.right[header];
+        while j != 0 {
+            if self.size[j] < self.size[header] {
+                header = j;
+            }
+            j = self.right[j];
+        }
+        if self.size[header] == 0 {
+            return false;
+        }
+
+        self.cover(header);
+        let mut i = self.down[header];
+        let mut stop = false;
+        while i != header && !stop {
+            partial.push(self.row[i]);
+            let mut j = self.right[i];
+            while j != i {
+                self.cover(self.column[j]);
+                j = self.right[j];
+            }
+
+            stop = self.search(partial, on_solution);
+
+            let mut j = self.left[i];
+            while j != i {
+                self.uncover(self.column[j]);
+                j = self.left[j];
+            }
+            partial.pop();
+            i = self.down[i];
+        }
+        self.uncover(header);
+
+        stop
+    }
+}
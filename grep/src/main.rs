@@ -1,18 +1,42 @@
 use colored::Colorize;
-use itertools::Itertools;
-use std::{collections::BTreeSet, path::PathBuf};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
 use sudoku::*;
 
+mod template;
+
+use template::Template;
+
 const HEADER: &'static str = r#"visual grepper for .sudoku
 "#;
 
 const USAGE: &'static str = r#"
 Usage:
-    skgrep [<.sudoku file>]
+    skgrep [--json] [--sdm] [--digit <n>] [--rulers] [--no-color | --ascii]
+           [--solution <file>] [--stats]
+           [--only-valid | --only-invalid] [--only-complete] [--template <file>]
+           [<.sudoku file> | <.sdm file>]...
     skgrep --help
 
 Options:
     --help              Print help information.
+    --json              Print the board and its violations as JSON, instead of
+                        in color.
+    --digit <n>         Highlight every <n> on the board, and dim the rest.
+    --rulers            Number the rows and columns, and draw lines between the
+                        boxes.
+    --no-color          Mark the cells instead of coloring them, as [5] for a
+                        violation, and (5) for the digit of --digit.
+    --ascii             The same as --no-color.
+    --solution <file>   Mark the cells that differ from the board in <file>.
+    --stats             Count the clues, and the cells filled in with each digit
+                        and in each row, column and box, below each board.
+    --sdm               Read the input as an .sdm collection, one puzzle per
+                        line. Files ending in .sdm are always read this way.
+    --only-valid        Only echo the puzzles without violations.
+    --only-invalid      Only echo the puzzles with violations.
+    --only-complete     Only echo the puzzles with every cell filled in.
+    --template <file>   Only echo the puzzles that match the template in <file>.
 "#;
 
 const LONG_HELP: &'static str = concat!(
@@ -21,6 +45,64 @@ An input file of "-" denotes the input data should be read from the standard
 input. No input file is taken to mean the data should be read from the standard
 input.
 
+Several input files can be given, each of which is then printed under a header
+with its name, followed by a summary of all the puzzles read. Patterns with "*"
+or "?" in the file name, such as "puzzles/*.sudoku", are expanded by skgrep
+when the shell leaves them as they are.
+
+With --digit, the cells holding the given digit stand out from the rest of the
+board, to see at a glance where it is still missing. This only changes the
+colored output.
+
+With --rulers, the rows and columns are numbered from 1, as in the locations
+reported with --json, and lines are drawn between the boxes.
+
+The output is only colored on a terminal, and unless the NO_COLOR environment
+variable is set, or --no-color is given. Otherwise, the cells in violation are
+marked as [5] instead of red, and those of --digit as (5) instead of bold.
+
+With --solution, the filled in cells that differ from the solution are yellow,
+or marked as <5>, unless they are also in violation. These are mistakes even
+when they break no rule, and a board with any of them is never green. The
+solution must be the size of every board read; its empty cells are taken to be
+correct.
+
+With --stats, each board is followed by how many of its cells are filled in and
+empty, how many times each digit appears, and how many cells are filled in in
+each row, column and box, in order. This only changes the drawn boards.
+
+The input may hold several puzzles, one after the other, optionally separated by
+empty lines, or be an .sdm collection, with one puzzle per line, written as its
+cells in reading order, with "." or "0" for empty cells. Each puzzle is then
+printed in turn.
+
+With any of the --only options, skgrep acts as a filter instead: the puzzles
+that pass are echoed as they were given, in the same format, and the others are
+left out. The exit code is 1 if no puzzle passes.
+
+A template, as taken by --template, is written like a board, but with "_" for
+the cells that can hold anything, and "." for those that must be empty; a digit
+must be there as it is. For example, the puzzles of a collection that start with
+a 1 in the corner, and nothing else in the first row, are found with a template
+whose first row is "1 . . . . . . . .", and whose other rows are all "_". Rows
+without spaces are read a character per cell, as in "1........".
+
+With --json, a single line is printed for each puzzle, of the form
+
+    {"side":4,"board":[[1,2,null,4],...],"violations":[...],
+     "complete":false,"valid":true}
+
+where the board is given row by row, with null for empty cells. Each violation
+is a pair of cells holding the same digit in the same unit, as in
+
+    {"cells":[[1,1],[1,3]],"digit":2,"unit":"row"}
+
+with rows and columns numbered from 1, and the unit one of "row", "column" or
+"box". A pair in the same row and box is a row violation. "complete" tells
+whether every cell is filled in, and "valid" whether there are no violations.
+With --solution, the line also has a "wrong" list of the cells that differ from
+the solution, as in [[2,3],[4,1]].
+
 "#,
     include_str!("../../FORMATTING.txt")
 );
@@ -28,100 +110,557 @@ input.
 fn main() {
     let mut args = std::env::args().skip(1); // Skip the filename
 
-    let input = match args.next() {
-        None => {
-            parsing::sudoku::parse(std::io::stdin())
-        }
-        Some(string) => match string.as_str() {
+    let mut json = false;
+    let mut sdm = false;
+    let mut only_valid = false;
+    let mut only_invalid = false;
+    let mut only_complete = false;
+    let mut stats = false;
+    let mut appearance = Appearance::default();
+    let mut solution_path: Option<PathBuf> = None;
+    let mut template_path: Option<PathBuf> = None;
+    let mut paths: Vec<String> = vec![];
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
             "--help" => {
                 println!("{}", HEADER);
                 println!("{}", USAGE);
                 println!("{}", LONG_HELP);
                 std::process::exit(0);
             }
-            "-" => parsing::sudoku::parse(std::io::stdin()),
-            path => {
-                let path = PathBuf::from(path);
-                let path_as_str = path.clone().to_string_lossy().to_string();
-                if !path.exists() {
-                    eprintln!("{} does not exist.", &path_as_str);
+            "--json" => json = true,
+            "--sdm" => sdm = true,
+            "--rulers" => appearance.rulers = true,
+            "--stats" => stats = true,
+            "--no-color" | "--ascii" => colored::control::set_override(false),
+            "--digit" => match args.next().map(|n| n.parse::<usize>()) {
+                Some(Ok(n)) if n > 0 => appearance.digit = Some(n),
+                _ => {
+                    eprintln!("Expected a digit after --digit.");
+                    eprintln!("{}", USAGE);
                     std::process::exit(1);
                 }
-
-                let reader = std::fs::File::open(path);
-                if let Err(e) = reader {
-                    eprintln!(
-                        "Could not open {} for reading.\nWith error {}",
-                        &path_as_str, e
-                    );
+            },
+            "--solution" => match args.next() {
+                Some(path) => solution_path = Some(PathBuf::from(path)),
+                None => {
+                    eprintln!("Expected a file after --solution.");
+                    eprintln!("{}", USAGE);
+                    std::process::exit(1);
+                }
+            },
+            "--template" => match args.next() {
+                Some(path) => template_path = Some(PathBuf::from(path)),
+                None => {
+                    eprintln!("Expected a file after --template.");
+                    eprintln!("{}", USAGE);
                     std::process::exit(1);
                 }
-                let reader = reader.unwrap();
+            },
+            "--only-valid" => only_valid = true,
+            "--only-invalid" => only_invalid = true,
+            "--only-complete" => only_complete = true,
+            _ => paths.extend(expand(&arg)),
+        }
+    }
+    if only_valid && only_invalid {
+        eprintln!("Only one of --only-valid and --only-invalid can be given.");
+        eprintln!("{}", USAGE);
+        std::process::exit(1);
+    }
+    let filtering = only_valid || only_invalid || only_complete || template_path.is_some();
 
-                parsing::sudoku::parse(reader)
+    let solution = solution_path.map(|path| {
+        let path_as_str = path.to_string_lossy().to_string();
+        let reader = match std::fs::File::open(&path) {
+            Ok(reader) => reader,
+            Err(e) => {
+                eprintln!(
+                    "Could not open {} for reading.\nWith error {}",
+                    &path_as_str, e
+                );
+                std::process::exit(1);
             }
-        },
-    };
+        };
+        match parsing::sudoku::parse(reader) {
+            Ok(solution) => solution,
+            Err(e) => {
+                eprintln!("Solution board malformed.");
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    });
 
-    let input = match input {
-        Ok(input) => input,
-        Err(e) => {
-            eprintln!("Input board malformed.");
-            eprintln!("{}", e);
-            std::process::exit(1);
+    let template = template_path.map(|path| {
+        let path_as_str = path.to_string_lossy().to_string();
+        let reader = match std::fs::File::open(&path) {
+            Ok(reader) => reader,
+            Err(e) => {
+                eprintln!(
+                    "Could not open {} for reading.\nWith error {}",
+                    &path_as_str, e
+                );
+                std::process::exit(1);
+            }
+        };
+        match Template::parse(reader) {
+            Ok(template) => template,
+            Err(e) => {
+                eprintln!("Template malformed.");
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
         }
-    };
+    });
 
-    let side = input.side();
-    let box_side = input.box_side();
+    if paths.is_empty() {
+        paths.push("-".to_string());
+    }
+    let several = paths.len() > 1;
+    // Headers and the summary only go with the boards drawn for people;
+    // filtered and JSON output is meant for other programs.
+    let headers = several && !json && !filtering;
 
-    // Look for violations
-    let mut invalid = BTreeSet::<usize>::new();
-    let pairs_to_check = (0..side)
-        .cartesian_product(0..side)
-        .tuple_combinations()
-        .filter(|((r, c), (rr, cc))| {
-            if r == rr && c == cc {
-                return false; // This should never happen, due to the behavior of tuple_combinations()
+    let mut passed = 0;
+    let mut summary = Summary::default();
+    for path in paths.iter() {
+        let (puzzles, sdm) = match read_input(path, sdm) {
+            (Ok(puzzles), sdm) => (puzzles, sdm),
+            (Err(e), _) => {
+                if several {
+                    eprintln!("Input board malformed, in {}.", path);
+                } else {
+                    eprintln!("Input board malformed.");
+                }
+                eprintln!("{}", e);
+                std::process::exit(1);
             }
-            if r == rr || c == cc {
-                return true;
+        };
+
+        if headers {
+            if passed > 0 {
+                println!();
             }
-            (r / box_side) == (rr / box_side) && (c / box_side) == (cc / box_side)
-        });
-
-    let mut filled_count = 0;
-    for ((r, c), (rr, cc)) in pairs_to_check {
-        if let Some(this) = input.get(r, c).value() {
-            if let Some(that) = input.get(rr, cc).value() {
-                filled_count += 1;
-                if this == that {
-                    invalid.insert(r * side + c);
-                    invalid.insert(rr * side + cc);
+            println!("==> {} <==", path);
+        }
+
+        let mut passed_here = 0;
+        for puzzle in puzzles.iter() {
+            let side = puzzle.side();
+            let conflicts = check::conflicts(puzzle);
+            let filled = (0..side * side).all(|raw| !puzzle.get_raw(raw).is_empty());
+            summary.add(&conflicts, filled);
+            let wrong = solution.as_ref().map(|solution| {
+                if solution.side() != side {
+                    eprintln!("The solution is not the size of the board.");
+                    std::process::exit(1);
                 }
+                wrong_cells(puzzle, solution)
+            });
+
+            if (only_valid && !conflicts.is_empty())
+                || (only_invalid && conflicts.is_empty())
+                || (only_complete && !filled)
+                || template
+                    .as_ref()
+                    .is_some_and(|template| !template.matches(puzzle))
+            {
+                continue;
             }
+
+            if json {
+                print_json(puzzle, &conflicts, filled, wrong.as_deref());
+            } else if filtering && sdm {
+                println!("{}", parsing::sdm::write(puzzle));
+            } else {
+                let separate = if headers { passed_here > 0 } else { passed > 0 };
+                if separate {
+                    println!();
+                }
+                if filtering {
+                    println!("{}", puzzle);
+                } else {
+                    print_colored(
+                        puzzle,
+                        &conflicts,
+                        filled,
+                        wrong.as_deref().unwrap_or_default(),
+                        &appearance,
+                    );
+                    if stats {
+                        print_stats(puzzle);
+                    }
+                }
+            }
+            passed += 1;
+            passed_here += 1;
+        }
+    }
+
+    if headers {
+        println!();
+        println!("{}", summary.describe(paths.len()));
+    }
+
+    if filtering && passed == 0 {
+        std::process::exit(1);
+    }
+}
+
+/// Counts of the puzzles read, over every input file.
+#[derive(Default)]
+struct Summary {
+    puzzles: usize,
+    valid: usize,
+    complete: usize,
+}
+
+impl Summary {
+    fn add(&mut self, conflicts: &[check::Conflict], filled: bool) {
+        self.puzzles += 1;
+        if conflicts.is_empty() {
+            self.valid += 1;
         }
+        if filled {
+            self.complete += 1;
+        }
+    }
+
+    fn describe(&self, files: usize) -> String {
+        format!(
+            "{} puzzles in {} files: {} valid, {} with violations, {} complete",
+            self.puzzles,
+            files,
+            self.valid,
+            self.puzzles - self.valid,
+            self.complete
+        )
+    }
+}
+
+/// Reads the puzzles in the file at `path`, or in the standard input for
+/// `-`, along with whether they were read as an .sdm collection, which is
+/// the case for files ending in .sdm, or if `sdm` is set. Exits if the file
+/// cannot be opened.
+fn read_input(path: &str, sdm: bool) -> (Result<Vec<Sudoku>, String>, bool) {
+    if path == "-" {
+        return (read_puzzles(std::io::stdin(), sdm), sdm);
+    }
+
+    let path = PathBuf::from(path);
+    let path_as_str = path.clone().to_string_lossy().to_string();
+    if !path.exists() {
+        eprintln!("{} does not exist.", &path_as_str);
+        std::process::exit(1);
+    }
+    let sdm = sdm || path.extension().is_some_and(|ext| ext == "sdm");
+
+    let reader = std::fs::File::open(path);
+    if let Err(e) = reader {
+        eprintln!(
+            "Could not open {} for reading.\nWith error {}",
+            &path_as_str, e
+        );
+        std::process::exit(1);
+    }
+    let reader = reader.unwrap();
+
+    (read_puzzles(reader, sdm), sdm)
+}
+
+/// The files matching a path with `*` or `?` in its file name, in order, as
+/// the shell would expand it. Other paths, and patterns that match nothing,
+/// are left as they are.
+fn expand(pattern: &str) -> Vec<String> {
+    let path = Path::new(pattern);
+    let name = match path.file_name().and_then(|name| name.to_str()) {
+        Some(name) if name.contains(['*', '?']) && !path.exists() => name,
+        _ => return vec![pattern.to_string()],
+    };
+    let parent = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty());
+
+    let entries = match std::fs::read_dir(parent.unwrap_or(Path::new("."))) {
+        Ok(entries) => entries,
+        Err(_) => return vec![pattern.to_string()],
+    };
+    let mut matches = entries
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .filter(|entry| !entry.starts_with('.') && wildcard_match(name, entry))
+        .map(|entry| match parent {
+            Some(parent) => parent.join(entry).to_string_lossy().to_string(),
+            None => entry,
+        })
+        .collect::<Vec<_>>();
+    if matches.is_empty() {
+        return vec![pattern.to_string()];
+    }
+    matches.sort();
+    matches
+}
+
+/// Whether `name` matches `pattern`, in which `*` stands for any number of
+/// characters, and `?` for any single one.
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    fn matches(pattern: &[char], name: &[char]) -> bool {
+        match pattern.split_first() {
+            None => name.is_empty(),
+            Some(('*', rest)) => (0..=name.len()).any(|skip| matches(rest, &name[skip..])),
+            Some(('?', rest)) => !name.is_empty() && matches(rest, &name[1..]),
+            Some((c, rest)) => name.first() == Some(c) && matches(rest, &name[1..]),
+        }
+    }
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let name = name.chars().collect::<Vec<_>>();
+    matches(&pattern, &name)
+}
+
+/// Every puzzle in the input, as concatenated .sudoku boards, or as an .sdm
+/// collection.
+fn read_puzzles(reader: impl std::io::Read, sdm: bool) -> Result<Vec<Sudoku>, String> {
+    if sdm {
+        parsing::sdm::parse(reader)
+    } else {
+        parsing::sudoku::parse_many(reader)
+    }
+}
+
+/// How boards are drawn, besides the colors for violations.
+#[derive(Default)]
+struct Appearance {
+    /// A digit to highlight, dimming the rest of the board.
+    digit: Option<usize>,
+    /// Whether to number the rows and columns, and draw the boxes.
+    rulers: bool,
+}
+
+/// Prints the number of filled in and empty cells of the board, the number
+/// of times each digit appears, and the number of filled in cells of each row,
+/// column and box.
+fn print_stats(sudoku: &Sudoku) {
+    let side = sudoku.side();
+    let width = side.to_string().len();
+    let filled = |cells: &[usize]| {
+        cells
+            .iter()
+            .filter(|raw| !sudoku.get_raw(**raw).is_empty())
+            .count()
+    };
+    let line = |label: &str, counts: Vec<usize>| {
+        let counts = counts
+            .iter()
+            .map(|count| format!("{:>width$}", count))
+            .collect::<Vec<_>>();
+        println!("{:<8}{}", label, counts.join(" "));
+    };
+
+    let clues = filled(&(0..side * side).collect::<Vec<_>>());
+    println!("clues {}, empty {}", clues, side * side - clues);
+    line("digit", (1..=side).collect());
+    line(
+        "count",
+        (1..=side)
+            .map(|d| {
+                (0..side * side)
+                    .filter(|raw| sudoku.get_raw(*raw).value() == Some(d))
+                    .count()
+            })
+            .collect(),
+    );
+    for (kind, label) in [
+        (UnitKind::Row, "row"),
+        (UnitKind::Column, "column"),
+        (UnitKind::Box, "box"),
+    ] {
+        line(
+            label,
+            sudoku
+                .units()
+                .filter(|unit| unit.kind == kind)
+                .map(|unit| filled(&unit.cells))
+                .collect(),
+        );
     }
+}
 
-    let total = side * side * (side - 1) + side * side * ((side - 1) / 2 - box_side + 1);
-    let filled = filled_count == total;
-    drop(filled_count);
+/// The raw indices of the filled in cells of the board that hold another
+/// digit than in the solution, where the solution is filled in.
+fn wrong_cells(sudoku: &Sudoku, solution: &Sudoku) -> Vec<usize> {
+    (0..sudoku.side() * sudoku.side())
+        .filter(|raw| {
+            let (value, correct) = (sudoku.get_raw(*raw).value(), solution.get_raw(*raw).value());
+            matches!((value, correct), (Some(value), Some(correct)) if value != correct)
+        })
+        .collect()
+}
+
+/// Prints the board, with the cells in violation in red, the `wrong` cells
+/// in yellow, or every cell in green if the board is complete, valid, and
+/// has no wrong cells. With a digit to highlight, its cells are in bold, and
+/// in cyan unless they are otherwise colored, and every other cell is dimmed.
+///
+/// When the output is not colored, the cells in violation, the wrong cells
+/// and those of the highlighted digit are marked with brackets, angle
+/// brackets and parentheses instead.
+fn print_colored(
+    sudoku: &Sudoku,
+    conflicts: &[check::Conflict],
+    filled: bool,
+    wrong: &[usize],
+    appearance: &Appearance,
+) {
+    let side = sudoku.side();
+    let box_side = sudoku.box_side();
+    let digit = appearance.digit;
+    let mut invalid = BTreeSet::<usize>::new();
+    for conflict in conflicts.iter() {
+        let (a, b) = conflict.cells;
+        invalid.insert(a);
+        invalid.insert(b);
+    }
+
+    // Without colors, every cell has room for a marker on either side.
+    let markers = !colored::control::SHOULD_COLORIZE.should_colorize();
+    let pad = if markers { " " } else { "" };
+
+    // With rulers, every cell is as wide as the largest digit, so that the
+    // columns line up on boards larger than 9 by 9.
+    let width = if appearance.rulers {
+        side.to_string().len()
+    } else {
+        1
+    };
+    let cell_width = width + 2 * pad.len();
+    let separator = format!(
+        "{:width$} +{}",
+        "",
+        format!("{}+", "-".repeat(box_side * (cell_width + 1) + 1)).repeat(box_side)
+    );
+    if appearance.rulers {
+        let mut header = format!("{:width$}  ", "");
+        for c in 0..side {
+            header += &format!(" {pad}{:>width$}{pad}", c + 1);
+            if (c + 1) % box_side == 0 {
+                header += "  ";
+            }
+        }
+        println!("{}", header.trim_end());
+    }
 
-    // Print the sudoku with colors
     for r in 0..side {
+        if appearance.rulers {
+            if r % box_side == 0 {
+                println!("{}", separator);
+            }
+            print!("{:>width$} |", r + 1);
+        }
         for c in 0..side {
-            if let Some(value) = input.get(r, c).value() {
-                if invalid.contains(&(r * side + c)) {
-                    print!("{} ", value.to_string().red())
-                } else if filled && invalid.len() == 0 {
-                    print!("{} ", value.to_string().green());
-                } else {
-                    print!("{} ", value);
+            let value = sudoku.get(r, c).value();
+            let text = match value {
+                Some(value) => format!("{:>width$}", value),
+                None => format!("{:>width$}", "_"),
+            };
+            let violating = value.is_some() && invalid.contains(&(r * side + c));
+            let mistaken = wrong.contains(&(r * side + c));
+            let highlighted = value.is_some() && value == digit;
+            let (open, close) = match markers {
+                false => ("", ""),
+                true if violating => ("[", "]"),
+                true if mistaken => ("<", ">"),
+                true if highlighted => ("(", ")"),
+                true => (" ", " "),
+            };
+            let text = format!("{}{}{}", open, text, close);
+            let text = text.as_str();
+            let cell = match value {
+                _ if violating => text.red(),
+                _ if mistaken => text.yellow(),
+                _ if highlighted => text.cyan(),
+                Some(_) if filled && invalid.is_empty() && wrong.is_empty() => text.green(),
+                _ => text.normal(),
+            };
+            let cell = match digit {
+                Some(digit) if value == Some(digit) => cell.bold(),
+                Some(_) => cell.dimmed(),
+                None => cell,
+            };
+            if appearance.rulers {
+                print!(" {}", cell);
+                if (c + 1) % box_side == 0 {
+                    print!(" |");
                 }
+            } else if markers {
+                print!("{}", cell);
             } else {
-                print!("_ ");
+                print!("{} ", cell);
             }
         }
         print!("\n");
     }
+    if appearance.rulers {
+        println!("{}", separator);
+    }
+}
+
+/// Prints the board, its violations, and whether it is complete and valid, as
+/// JSON on a single line; see the long help for the format.
+fn print_json(
+    sudoku: &Sudoku,
+    conflicts: &[check::Conflict],
+    complete: bool,
+    wrong: Option<&[usize]>,
+) {
+    let side = sudoku.side();
+    let board = (0..side)
+        .map(|r| {
+            let row = (0..side)
+                .map(|c| match sudoku.get(r, c).value() {
+                    Some(value) => value.to_string(),
+                    None => "null".to_string(),
+                })
+                .collect::<Vec<_>>();
+            format!("[{}]", row.join(","))
+        })
+        .collect::<Vec<_>>();
+    let violations = conflicts
+        .iter()
+        .map(|conflict| {
+            let (a, b) = conflict.cells;
+            let unit = match conflict.unit {
+                UnitKind::Row => "row",
+                UnitKind::Column => "column",
+                UnitKind::Box => "box",
+            };
+            format!(
+                r#"{{"cells":[[{},{}],[{},{}]],"digit":{},"unit":"{}"}}"#,
+                a / side + 1,
+                a % side + 1,
+                b / side + 1,
+                b % side + 1,
+                conflict.value,
+                unit
+            )
+        })
+        .collect::<Vec<_>>();
+    let wrong = match wrong {
+        Some(wrong) => {
+            let cells = wrong
+                .iter()
+                .map(|raw| format!("[{},{}]", raw / side + 1, raw % side + 1))
+                .collect::<Vec<_>>();
+            format!(r#","wrong":[{}]"#, cells.join(","))
+        }
+        None => String::new(),
+    };
+    println!(
+        r#"{{"side":{},"board":[{}],"violations":[{}],"complete":{},"valid":{}{}}}"#,
+        side,
+        board.join(","),
+        violations.join(","),
+        complete,
+        conflicts.is_empty(),
+        wrong
+    );
 }
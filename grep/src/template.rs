@@ -0,0 +1,114 @@
+//! Templates that boards can be matched against, to search collections for
+//! puzzles sharing a pattern.
+
+use std::io::Read;
+use sudoku::{Sudoku, SudokuCellValue};
+
+/// What a template asks of a cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cell {
+    /// Anything, written `_`.
+    Any,
+    /// An empty cell, written `.`.
+    Empty,
+    /// The given digit.
+    Digit(usize),
+}
+
+/// A board of [`Cell`]s, written like a .sudoku board, but with `.` for the
+/// cells that must be empty, and `_` for those that can hold anything.
+#[derive(Debug, Clone)]
+pub struct Template {
+    side: usize,
+    /// The cells, in reading order.
+    cells: Vec<Cell>,
+}
+
+impl Template {
+    /// Reads a template, with one row per line, and the cells of a row
+    /// separated by whitespace; rows without whitespace are read a character
+    /// per cell, as in `4..3____.`. Empty lines are ignored.
+    pub fn parse(mut reader: impl Read) -> Result<Self, String> {
+        let mut contents = String::new();
+        reader
+            .read_to_string(&mut contents)
+            .map_err(|e| format!("Could not read the template: {}", e))?;
+
+        let mut rows = vec![];
+        for (number, line) in contents.lines().enumerate() {
+            let error = |what: &str| format!("Line {}: {}", number + 1, what);
+            let mut tokens = line
+                .split_whitespace()
+                .map(str::to_string)
+                .collect::<Vec<_>>();
+            if tokens.is_empty() {
+                continue;
+            }
+            if tokens.len() == 1 {
+                tokens = tokens[0].chars().map(String::from).collect();
+            }
+            let row = tokens
+                .iter()
+                .map(|token| match token.as_str() {
+                    "_" => Ok(Cell::Any),
+                    "." => Ok(Cell::Empty),
+                    token => match token.parse::<usize>() {
+                        Ok(d) if d > 0 => Ok(Cell::Digit(d)),
+                        _ => Err(error(&format!(
+                            "Expected a digit, '_' or '.', but found '{}'.",
+                            token
+                        ))),
+                    },
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            rows.push((number, row));
+        }
+
+        let side = rows.len();
+        let box_side = (side as f64).sqrt() as usize;
+        if side == 0 || box_side * box_side != side {
+            return Err(format!(
+                "The template has {} rows, which is not the side of a board.",
+                side
+            ));
+        }
+        let mut cells = Vec::with_capacity(side * side);
+        for (number, row) in rows {
+            if row.len() != side {
+                return Err(format!(
+                    "Line {}: Expected {} cells, as there are {} rows, but found {}.",
+                    number + 1,
+                    side,
+                    side,
+                    row.len()
+                ));
+            }
+            if let Some(Cell::Digit(d)) = row
+                .iter()
+                .find(|cell| matches!(cell, Cell::Digit(d) if *d > side))
+            {
+                return Err(format!(
+                    "Line {}: The template has {} by {} cells, but has a {} in it.",
+                    number + 1,
+                    side,
+                    side,
+                    d
+                ));
+            }
+            cells.extend(row);
+        }
+
+        Ok(Template { side, cells })
+    }
+
+    /// Whether the board is the size of the template, and has its digits
+    /// where the template does, and empty cells where it asks for them.
+    pub fn matches(&self, sudoku: &Sudoku) -> bool {
+        sudoku.side() == self.side
+            && self.cells.iter().enumerate().all(|(raw, cell)| match cell {
+                Cell::Any => true,
+                Cell::Empty => sudoku.get_raw(raw).is_empty(),
+                Cell::Digit(d) => sudoku.get_raw(raw).value() == Some(*d),
+            })
+    }
+}
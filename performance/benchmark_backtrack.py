@@ -14,7 +14,6 @@ Options:
 import random
 import subprocess
 import os
-import numpy as np
 import shutil
 import docopt
 from glob import glob
@@ -31,6 +30,16 @@ def main():
     else:
         print('What?')
 
+def parse_benchmark(stdout):
+    """The unsolved fraction and average solve time (ms) over all puzzles,
+    in the CSV output of --benchmark."""
+    lines = stdout.decode('utf-8').strip().splitlines()
+    header, values = lines[0], lines[-1]
+    stats = dict(zip(header.split(','), values.split(',')))
+    unsolved = int(stats['failed']) / int(stats['runs'])
+    solve_time = float(stats['mean_us']) / 1000. if stats['mean_us'] else -1.
+    return unsolved, solve_time
+
 def bench_top1465():
     puzzles = []
     with open('top1465', 'r') as top1465:
@@ -59,9 +68,7 @@ def bench_top1465():
                         capture_output=True,
                         timeout=90) # Timeout of 1.5 minutes
                 print(out.stderr.decode('utf-8'))
-                data = np.fromstring(out.stdout, sep='\n')
-                unsolved = np.count_nonzero(data < 0.) / data.shape[0]
-                solve_time = np.average(data[data >= 0.]) if unsolved < 1. else -1.
+                unsolved, solve_time = parse_benchmark(out.stdout)
             except subprocess.TimeoutExpired:
                 unsolved = 1.
                 solve_time = -1.
@@ -85,10 +92,7 @@ def bench_paper():
                         capture_output=True,
                         timeout=90) # Timeout of 1.5 minutes
                 print(out.stderr.decode('utf-8'))
-                data = np.fromstring(out.stdout, sep='\n')
-                print(data)
-                unsolved = np.count_nonzero(data < 0.) / data.shape[0]
-                solve_time = np.average(data[data >= 0.])
+                unsolved, solve_time = parse_benchmark(out.stdout)
             except subprocess.TimeoutExpired:
                 unsolved = 1.
                 solve_time = -1.
@@ -0,0 +1,74 @@
+//! Handing what the projections found over to the backtracking solver, to
+//! finish the job when they do not reach a solution themselves.
+
+use ndarray::Array3;
+use sudoku::constraints::Constraints;
+use sudoku::{Sudoku, SudokuCell};
+
+use crate::tensor;
+
+/// What the handoff to the backtracking solver came to.
+pub struct Handoff {
+    pub solution: Sudoku,
+    /// Number of empty cells that were filled in from the tensor before
+    /// backtracking.
+    pub fixed: usize,
+    /// Whether the cells filled in from the tensor were dropped again,
+    /// because they led to a dead end, and the puzzle was solved from its
+    /// clues alone.
+    pub dropped: bool,
+}
+
+/// The puzzle, with every empty cell in which the tensor gives a digit a
+/// probability of more than `threshold` filled in with that digit, along
+/// with how many cells were filled in.
+pub fn confident_board(puzzle: &Sudoku, tensor: &Array3<f64>, threshold: f64) -> (Sudoku, usize) {
+    let mut board = puzzle.clone();
+    let mut fixed = 0;
+    let side = puzzle.side();
+    for row in 0..side {
+        for column in 0..side {
+            if !puzzle.get(row, column).is_empty() {
+                continue;
+            }
+            if let Some((digit, p)) = tensor::top_candidates(tensor, row, column, 1).first() {
+                if *p > threshold {
+                    board.set(row, column, SudokuCell::digit(*digit));
+                    fixed += 1;
+                }
+            }
+        }
+    }
+    (board, fixed)
+}
+
+/// Fills in the cells the tensor is confident of, as by [`confident_board`],
+/// and solves the rest of the puzzle with [`backtrack::solve`].
+///
+/// If the filled in cells turn out to be wrong, so that the board cannot be
+/// solved, they are dropped, and the puzzle is solved from its clues alone;
+/// the handoff only fails if the puzzle itself has no solution.
+pub fn fallback_backtrack(
+    puzzle: &Sudoku,
+    tensor: &Array3<f64>,
+    threshold: f64,
+    constraints: &Constraints,
+) -> Result<Handoff, backtrack::SolveError> {
+    let (board, fixed) = confident_board(puzzle, tensor, threshold);
+    match backtrack::solve(&board, constraints) {
+        Ok(solution) => Ok(Handoff {
+            solution,
+            fixed,
+            dropped: false,
+        }),
+        Err(_) if fixed > 0 => {
+            let solution = backtrack::solve(puzzle, constraints)?;
+            Ok(Handoff {
+                solution,
+                fixed,
+                dropped: true,
+            })
+        }
+        Err(e) => Err(e),
+    }
+}
@@ -0,0 +1,27 @@
+//! What the probability tensor starts out as.
+
+use ndarray::Array3;
+use sudoku::Sudoku;
+
+/// The starting point of the solver.
+///
+/// Whatever the start, the entries for digits that cannot go in a cell (by
+/// the clues, or the variant constraints) are not kept by the solver, and are
+/// 0 in the last iterate; see [`Layout`](crate::layout::Layout).
+#[derive(Debug, Clone, Default)]
+pub enum Init {
+    /// All probabilities 0, or uniform over the candidates of each cell with
+    /// [`Method::Entropic`](crate::Method::Entropic), which cannot move off 0.
+    #[default]
+    Zeros,
+    /// Uniform over the digits that can go in each cell.
+    Uniform,
+    /// A given tensor, such as the last iterate of an earlier run (see
+    /// [`ProjectionResult::tensor`](crate::ProjectionResult::tensor)), of the
+    /// same size as the board.
+    Tensor(Array3<f64>),
+    /// Uniform, except that the cells filled in on the given board, of the
+    /// same size, are certain of their digit where it can go there at all.
+    /// Unlike clues, these can still change.
+    Hint(Sudoku),
+}
@@ -0,0 +1,107 @@
+//! A packed layout for the probability tensor, storing only the entries that
+//! can ever be nonzero.
+//!
+//! The dense tensor has `side³` entries, most of which are for digits ruled
+//! out by the clues, and stay 0 throughout; on 16x16 and larger boards, these
+//! make up most of the memory the solver needs, several times over.
+
+use ndarray::Array3;
+use std::ops::Range;
+
+/// Which (cell, digit) entries of the tensor are stored, and where.
+///
+/// The entries are stored cell after cell, in reading order, and by digit
+/// within each cell; the values themselves are kept apart, in a slice of
+/// [`Layout::len`] entries, so that several iterates can share a layout.
+#[derive(Debug, Clone)]
+pub struct Layout {
+    side: usize,
+    /// Where the entries of each cell start, in reading order, followed by
+    /// where the last of them ends.
+    starts: Vec<usize>,
+    /// The digit - 1 of each entry.
+    digits: Vec<u32>,
+}
+
+impl Layout {
+    /// The layout of a board of the given side, storing the entries at
+    /// `(row, column, d)` for which `stored` holds.
+    pub fn new(side: usize, mut stored: impl FnMut(usize, usize, usize) -> bool) -> Self {
+        let mut starts = Vec::with_capacity(side * side + 1);
+        let mut digits = Vec::new();
+        for row in 0..side {
+            for column in 0..side {
+                starts.push(digits.len());
+                digits.extend(
+                    (0..side)
+                        .filter(|d| stored(row, column, *d))
+                        .map(|d| d as u32),
+                );
+            }
+        }
+        starts.push(digits.len());
+        digits.shrink_to_fit();
+        Layout {
+            side,
+            starts,
+            digits,
+        }
+    }
+
+    /// Number of stored entries.
+    pub fn len(&self) -> usize {
+        self.digits.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.digits.is_empty()
+    }
+
+    /// The indices of the stored entries of a cell.
+    pub fn cell(&self, row: usize, column: usize) -> Range<usize> {
+        let cell = row * self.side + column;
+        self.starts[cell]..self.starts[cell + 1]
+    }
+
+    /// The index of the entry for digit `d + 1` in a cell, if it is stored.
+    pub fn index(&self, row: usize, column: usize, d: usize) -> Option<usize> {
+        let cell = self.cell(row, column);
+        let start = cell.start;
+        self.digits[cell]
+            .binary_search(&(d as u32))
+            .ok()
+            .map(|i| start + i)
+    }
+
+    /// The probability of digit `d + 1` in a cell, which is 0 if the entry is
+    /// not stored.
+    pub fn get(&self, values: &[f64], row: usize, column: usize, d: usize) -> f64 {
+        self.index(row, column, d).map_or(0., |i| values[i])
+    }
+
+    /// The stored entries of a dense tensor, of the side of the layout.
+    pub fn pack(&self, tensor: &Array3<f64>) -> Vec<f64> {
+        let mut values = Vec::with_capacity(self.len());
+        for row in 0..self.side {
+            for column in 0..self.side {
+                for i in self.cell(row, column) {
+                    values.push(tensor[[row, column, self.digits[i] as usize]]);
+                }
+            }
+        }
+        values
+    }
+
+    /// The dense tensor with the given stored entries, and 0 elsewhere.
+    pub fn unpack(&self, values: &[f64]) -> Array3<f64> {
+        let mut tensor = Array3::zeros((self.side, self.side, self.side));
+        for row in 0..self.side {
+            for column in 0..self.side {
+                for i in self.cell(row, column) {
+                    tensor[[row, column, self.digits[i] as usize]] = values[i];
+                }
+            }
+        }
+        tensor
+    }
+}
@@ -0,0 +1,23 @@
+//! Alternating projections solver for sudoku, as a library.
+//!
+//! The `projection` binary is a thin command line wrapper around [`solve`].
+
+pub mod hybrid;
+pub mod init;
+pub mod layout;
+pub mod method;
+pub mod options;
+pub mod progress;
+pub mod rounding;
+pub mod solver;
+pub mod sweep;
+pub mod tensor;
+
+pub use init::Init;
+pub use method::Method;
+pub use options::ProjectionOptions;
+pub use progress::{Iteration, Progress};
+pub use rounding::Rounding;
+pub use solver::{solve, Projection, ProjectionResult, SolveResult, SolveStats};
+pub use sudoku::{Budget, NoProgress, ProgressSink};
+pub use sweep::Sweep;
@@ -1,27 +1,178 @@
-use itertools::Itertools;
-use std::{convert::Infallible, iter::Peekable, path::PathBuf};
-use sudoku::parsing;
-
-mod solver;
+use ndarray::Array3;
+use projection::{
+    hybrid, solver, tensor, Init, Method, Progress, ProgressSink, ProjectionOptions, Rounding,
+    Sweep,
+};
+use std::path::{Path, PathBuf};
+use std::{convert::Infallible, iter::Peekable};
+use sudoku::constraints::Constraints;
+use sudoku::{parsing, Sudoku};
 
 const HEADER: &'static str = r#"alternating projections solver for sudoku"#;
 const USAGE: &'static str = r#"
 Usage:
-    sudoku <iteration limit> <input file>
+    sudoku [--constraints <file>] [--non-consecutive] [--verify]
+           [--method ap|dr|entropic [--temperature <t>] [--cooling <r>]]
+           [--uniform | --init <file> | --init-tensor <file>]
+           [--rounding argmax|assignment] [--sweep cyclic|random|shuffled]
+           [--seed <n>] [--dump-tensor <file>] [--progress]
+           [--confidence] [--undecided <p>] [--fallback-backtrack <p>]
+           [--tolerance <x> [--stall-window <n>]]
+           (<iteration limit> | --until-converged) <input file>
     sudoku --help
 
 Options:
-    --help      Print this text.
+    --help                  Print this text.
+    --constraints <file>    Read variant constraints from <file>. Even and odd
+                            cells narrow down the digits considered for them;
+                            other constraints only reject final states that
+                            break them.
+    --non-consecutive       Forbid consecutive digits in orthogonally adjacent
+                            cells, by also projecting onto the rule.
+    --verify                Check the solution against the input before
+                            reporting success.
+    --method <method>       Use alternating projections (ap, the default),
+                            Douglas-Rachford splitting (dr) or entropic
+                            projections (entropic).
+    --temperature <t>       With --method entropic, start at temperature <t>.
+                            The default is 1.
+    --cooling <r>           With --method entropic, multiply the temperature
+                            by <r> after every sweep. The default is 0.99.
+    --sweep <order>         Project onto the constraints in the same order
+                            every sweep (cyclic, the default), in a new random
+                            order every sweep (random), or in a random order
+                            kept for every sweep (shuffled).
+    --seed <n>              Seed the random sweep orders.
+    --uniform               Start from probabilities spread evenly over the
+                            digits that can go in each cell.
+    --init <file>           Start as with --uniform, but certain of the digits
+                            filled in on the board in <file>.
+    --init-tensor <file>    Start from the probabilities in <file>, as written
+                            by --dump-tensor.
+    --rounding <rounding>   Read the board off the probabilities cell by cell
+                            (argmax, the default), or row by row (assignment).
+    --fallback-backtrack <p>
+                            If the projections do not find a solution, fill in
+                            the cells where a digit has probability above <p>,
+                            and solve the rest by backtracking.
+    --dump-tensor <file>    Write the final probability tensor to <file>, as
+                            CSV.
+    --progress              Report on every sweep to stderr.
+    --confidence            Report how sure the solver ended up of each cell,
+                            and the entropy of the probabilities, to stderr.
+    --undecided <p>         Report the two most likely digits of the cells
+                            where no digit has probability <p> or more, to
+                            stderr.
+    --tolerance <x>         Stop, as STALLED, once the probabilities stop
+                            changing by more than <x> from one sweep to the
+                            next.
+    --stall-window <n>      With --tolerance, also stop once they come back to
+                            within <x> of any of the last <n> sweeps. The
+                            default is 1.
+    --until-converged       Do not limit the number of sweeps, as with an
+                            iteration limit of 0.
 "#;
+/// The tolerance of a run without an iteration limit, unless one is given.
+const UNTIL_CONVERGED_TOLERANCE: f64 = 1e-9;
 const LONG_HELP: &'static str = concat!(
     r#"
 An input file of "-" denotes the input data should be read from the standard
 input.
 
-The iteration count limit should be an integer.
-The input file is expected to be in .soduku format.
+The iteration count limit should be an integer. A limit of 0, or
+--until-converged in its place, lets the solver keep going until it finds a
+solution or stalls; unless --tolerance is given, it then stalls at a tolerance
+of 1e-9. Douglas-Rachford may never settle, so a stall window, or a way to
+interrupt the run, is still a good idea with it.
+The input file is expected to be in .soduku format, and the constraints file in
+.constraints format.
+
+With --verify, a solution is checked once more before ALL SATISFIED is printed:
+it must be complete, agree with every clue, and break none of the rules. If it
+does not, what is wrong with it is reported to stderr, along with the board,
+and the program exits with code 1.
+
+Alternating projections, with --method ap, projects the probabilities onto each
+of the constraints in turn, and often gets stuck close to, but not at, a
+solution. Douglas-Rachford splitting, with --method dr, keeps a copy of the
+probabilities for each of the rows, columns, boxes and cells, and moves each
+copy by how far its constraints, which it takes to mean that a single digit or
+position is certain, pull away from the average of the copies. The board is
+then read off the average. This solves many more puzzles than alternating
+projections does, often in a few hundred sweeps.
+
+Entropic projections, with --method entropic, also projects onto each of the
+constraints in turn, but by rescaling the probabilities to add up to 1, as the
+Sinkhorn algorithm does. Before every sweep, each probability is raised to the
+power of one over the temperature, which smooths the probabilities out above 1
+and sharpens them below, and the temperature is then lowered. Starting hot and
+cooling slowly lets the probabilities settle gracefully before they are forced
+towards a single digit per cell.
+
+Besides ALL SATISFIED, the outcome printed is EXHAUSTED when the iteration limit
+is reached, STALLED when --tolerance is given and the solver stops making
+progress, or INFEASIBLE, right away, when the clues contradict each other or
+leave a digit with nowhere to go in some row, column or box, or a cell with no
+digit that can go in it.
+
+Alternating projections often settles on a fixed point that is not a solution,
+or cycles between a few iterates, and then never gets any further; a tolerance
+of about 1e-9 tells these apart from slow progress, and a stall window of a few
+dozen sweeps catches most cycles.
+
+Alternating and entropic projections depend on the order in which the
+constraints are projected onto, which --sweep sets: by default, the rows come
+first, then the columns, the boxes and the cells. Douglas-Rachford does not
+depend on it. A random order can be replayed by giving the same --seed.
+
+After every sweep, a board is read off the probabilities and checked. By
+default, each cell gets its most likely digit. With --rounding assignment, each
+row instead gets the arrangement of digits that is most likely as a whole, so
+that no row repeats a digit, even if that means some cells do not get their
+most likely digit.
+
+By default, the probabilities start out at 0, except with --method entropic,
+where they start out as with --uniform. A run can be warm started from the
+tensor where an earlier run left off, with --init-tensor, e.g. after tweaking
+a clue; entries for digits that can no longer go in a cell are then ignored.
+The board given with --init is only a hint: unlike the clues, the solver can
+still change the digits on it.
+
+With --progress, a line is written to stderr after every sweep, of the form
+
+    sweep <n> violations <v> change <c>
+
+where <v> is the number of pairs of equal digits in the same row, column or box
+on the board read off the probabilities, plus the number of times it breaks the
+variant constraints, and <c> is the largest change of any probability over the
+sweep.
+
+With --fallback-backtrack, when the solver runs out of iterations or stalls,
+what it found is handed over to the backtracking solver: every empty cell where
+a digit has a probability above the given threshold, e.g. 0.9, is filled in
+with it, and the rest of the board is solved by backtracking. If the cells
+filled in lead to a dead end, they are dropped again, and the puzzle is solved
+from its clues alone. Either way, how it went is reported to stderr, and ALL
+SATISFIED is printed along with the solution.
+
+With --confidence, once the solver stops, whatever the outcome, the total
+entropy of the cells' probabilities is written to stderr, in bits, followed by
+the largest probability of any digit in each cell, laid out as the board. An
+entropy of 0 means every cell is certain of a digit. With --undecided, each
+cell where no digit is that likely is also listed, as
+
+    r<row>c<column> <digit> <probability> <digit> <probability>
+
+with its two most likely digits, so that an unsuccessful run still tells which
+cells it was sure about, and which it was torn between.
+
+With --dump-tensor, the probabilities the solver ended on are written to the
+given file, whatever the outcome, one line per cell and digit, under the header
+row,column,digit,probability. Rows, columns and digits are numbered from 1.
 "#,
-    include_str!("../../FORMATTING.txt")
+    include_str!("../../FORMATTING.txt"),
+    "\n\n",
+    include_str!("../../CONSTRAINTS.txt")
 );
 
 trait OrUsage<T> {
@@ -83,8 +234,166 @@ impl<T> OrUsage<T> for Result<T, parsing::ParseError> {
 }
 
 fn main() {
-    let mut args = std::env::args().skip(1); // Skip the filename
-    let args = args.join(" ");
+    let args = std::env::args().skip(1).collect::<Vec<_>>(); // Skip the filename
+
+    // Flags can go anywhere, so take them out before parsing the rest.
+    let mut non_consecutive = false;
+    let mut show_progress = false;
+    let mut show_confidence = false;
+    let mut undecided: Option<f64> = None;
+    let mut fallback: Option<f64> = None;
+    let mut verify = false;
+    let mut constraints_path: Option<PathBuf> = None;
+    let mut dump_tensor_path: Option<PathBuf> = None;
+    let mut uniform = false;
+    let mut init_path: Option<PathBuf> = None;
+    let mut init_tensor_path: Option<PathBuf> = None;
+    let mut tolerance: Option<f64> = None;
+    let mut method = Method::default();
+    let mut rounding = Rounding::default();
+    let mut sweep = Sweep::default();
+    let mut seed: Option<u64> = None;
+    let mut temperature: Option<f64> = None;
+    let mut cooling: Option<f64> = None;
+    let mut stall_window: Option<usize> = None;
+    let mut until_converged = false;
+    let mut rest = vec![];
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--non-consecutive" => non_consecutive = true,
+            "--verify" => verify = true,
+            "--progress" => show_progress = true,
+            "--confidence" => show_confidence = true,
+            "--until-converged" => until_converged = true,
+            "--fallback-backtrack" => match args.next().map(|p| p.parse::<f64>()) {
+                Some(Ok(p)) if (0. ..=1.).contains(&p) => fallback = Some(p),
+                _ => {
+                    eprintln!("Expected a probability after --fallback-backtrack.");
+                    eprintln!("{}", USAGE);
+                    std::process::exit(1);
+                }
+            },
+            "--undecided" => match args.next().map(|p| p.parse::<f64>()) {
+                Some(Ok(p)) if (0. ..=1.).contains(&p) => undecided = Some(p),
+                _ => {
+                    eprintln!("Expected a probability after --undecided.");
+                    eprintln!("{}", USAGE);
+                    std::process::exit(1);
+                }
+            },
+            "--constraints" => match args.next() {
+                Some(path) => constraints_path = Some(PathBuf::from(path)),
+                None => {
+                    eprintln!("{}", USAGE);
+                    std::process::exit(1);
+                }
+            },
+            "--dump-tensor" => match args.next() {
+                Some(path) => dump_tensor_path = Some(PathBuf::from(path)),
+                None => {
+                    eprintln!("{}", USAGE);
+                    std::process::exit(1);
+                }
+            },
+            "--method" => match args.next().as_deref().and_then(Method::from_name) {
+                Some(m) => method = m,
+                None => {
+                    eprintln!("Expected \"ap\", \"dr\" or \"entropic\" after --method.");
+                    eprintln!("{}", USAGE);
+                    std::process::exit(1);
+                }
+            },
+            "--rounding" => match args.next().as_deref().and_then(Rounding::from_name) {
+                Some(r) => rounding = r,
+                None => {
+                    eprintln!("Expected \"argmax\" or \"assignment\" after --rounding.");
+                    eprintln!("{}", USAGE);
+                    std::process::exit(1);
+                }
+            },
+            "--sweep" => match args.next().as_deref().and_then(Sweep::from_name) {
+                Some(order) => sweep = order,
+                None => {
+                    eprintln!("Expected \"cyclic\", \"random\" or \"shuffled\" after --sweep.");
+                    eprintln!("{}", USAGE);
+                    std::process::exit(1);
+                }
+            },
+            "--seed" => match args.next().map(|n| n.parse::<u64>()) {
+                Some(Ok(n)) => seed = Some(n),
+                _ => {
+                    eprintln!("Expected a number after --seed.");
+                    eprintln!("{}", USAGE);
+                    std::process::exit(1);
+                }
+            },
+            "--temperature" => match args.next().map(|t| t.parse::<f64>()) {
+                Some(Ok(t)) if t > 0. => temperature = Some(t),
+                _ => {
+                    eprintln!("Expected a positive temperature after --temperature.");
+                    eprintln!("{}", USAGE);
+                    std::process::exit(1);
+                }
+            },
+            "--cooling" => match args.next().map(|r| r.parse::<f64>()) {
+                Some(Ok(r)) if r > 0. && r <= 1. => cooling = Some(r),
+                _ => {
+                    eprintln!("Expected a cooling factor in (0, 1] after --cooling.");
+                    eprintln!("{}", USAGE);
+                    std::process::exit(1);
+                }
+            },
+            "--uniform" => uniform = true,
+            "--init" => match args.next() {
+                Some(path) => init_path = Some(PathBuf::from(path)),
+                None => {
+                    eprintln!("{}", USAGE);
+                    std::process::exit(1);
+                }
+            },
+            "--init-tensor" => match args.next() {
+                Some(path) => init_tensor_path = Some(PathBuf::from(path)),
+                None => {
+                    eprintln!("{}", USAGE);
+                    std::process::exit(1);
+                }
+            },
+            "--tolerance" => match args.next().map(|x| x.parse::<f64>()) {
+                Some(Ok(x)) if x >= 0. => tolerance = Some(x),
+                _ => {
+                    eprintln!("Expected a non-negative tolerance after --tolerance.");
+                    eprintln!("{}", USAGE);
+                    std::process::exit(1);
+                }
+            },
+            "--stall-window" => match args.next().map(|n| n.replace('_', "").parse::<usize>()) {
+                Some(Ok(n)) if n > 0 => stall_window = Some(n),
+                _ => {
+                    eprintln!("Expected a positive number of sweeps after --stall-window.");
+                    eprintln!("{}", USAGE);
+                    std::process::exit(1);
+                }
+            },
+            _ => rest.push(arg),
+        }
+    }
+    if (temperature.is_some() || cooling.is_some()) && method != Method::Entropic {
+        eprintln!("--temperature and --cooling need --method entropic.");
+        eprintln!("{}", USAGE);
+        std::process::exit(1);
+    }
+    if [uniform, init_path.is_some(), init_tensor_path.is_some()]
+        .iter()
+        .filter(|given| **given)
+        .count()
+        > 1
+    {
+        eprintln!("Only one of --uniform, --init and --init-tensor can be given.");
+        eprintln!("{}", USAGE);
+        std::process::exit(1);
+    }
+    let args = rest.join(" ");
     let mut parse =
         parsing::Parser::new(args.chars().map::<Result<char, Infallible>, _>(|c| Ok(c)));
 
@@ -92,12 +401,21 @@ fn main() {
         .eat_space()
         .expect("Something unexpected happened while reading from stdin.");
 
-    let max_iterations = parse
-        .expect_integer()
-        .or_match_help(&mut parse)
-        .or_usage_msg("Expected a number of iterations.");
-
-    parse.expect_space().or_usage();
+    let max_iterations = if until_converged {
+        0
+    } else {
+        let max_iterations = parse
+            .expect_integer()
+            .or_match_help(&mut parse)
+            .or_usage_msg("Expected a number of iterations.");
+        parse.expect_space().or_usage();
+        max_iterations
+    };
+    if stall_window.is_some() && tolerance.is_none() && max_iterations != 0 {
+        eprintln!("--stall-window needs a --tolerance, or no iteration limit.");
+        eprintln!("{}", USAGE);
+        std::process::exit(1);
+    }
 
     let input = if parse
         .try_match('-')
@@ -145,12 +463,226 @@ fn main() {
         }
     };
 
-    let result = solver::solve(&mut input, max_iterations);
+    let mut variant = match constraints_path {
+        Some(path) => {
+            let reader = open_or_exit(&path);
+            match parsing::constraints::parse(reader, input.side()) {
+                Ok(constraints) => constraints,
+                Err(e) => {
+                    println!("Constraints file malformed.");
+                    println!("{}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        None => Constraints::new(),
+    };
+    variant.non_consecutive = non_consecutive;
 
-    match result {
+    let init = if uniform {
+        Init::Uniform
+    } else if let Some(path) = init_path {
+        match parsing::sudoku::parse(open_or_exit(&path)) {
+            Ok(hint) if hint.side() == input.side() => Init::Hint(hint),
+            Ok(_) => {
+                println!("The board given with --init is not the size of the input.");
+                std::process::exit(1);
+            }
+            Err(e) => {
+                println!("Init board malformed.");
+                println!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    } else if let Some(path) = init_tensor_path {
+        match tensor::read_csv(open_or_exit(&path), input.side()) {
+            Ok(start) => Init::Tensor(start),
+            Err(e) => {
+                println!("Init tensor malformed.");
+                println!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        Init::Zeros
+    };
+
+    let puzzle = input.clone();
+    let defaults = ProjectionOptions::default();
+    let options = ProjectionOptions {
+        max_iterations,
+        constraints: variant,
+        method,
+        init,
+        rounding,
+        sweep,
+        seed,
+        temperature: temperature.unwrap_or(defaults.temperature),
+        cooling: cooling.unwrap_or(defaults.cooling),
+        // Without a limit, something has to stop the solver short of a
+        // solution.
+        tolerance: tolerance.or((max_iterations == 0).then_some(UNTIL_CONVERGED_TOLERANCE)),
+        stall_window: stall_window.unwrap_or(defaults.stall_window),
+        ..defaults
+    };
+    let mut report = Report { show_progress };
+    let result = solver::solve(&mut input, &options, &mut report);
+    let variant = options.constraints;
+
+    if let Some(path) = dump_tensor_path {
+        dump_tensor_or_exit(&path, &result.tensor);
+    }
+
+    let mut outcome = result.outcome;
+    let gave_up = matches!(
+        outcome,
+        solver::SolveResult::IterationsExhausted | solver::SolveResult::Stalled
+    );
+    if let (Some(threshold), true) = (fallback, gave_up) {
+        match hybrid::fallback_backtrack(&puzzle, &result.tensor, threshold, &variant) {
+            Ok(handoff) => {
+                if handoff.dropped {
+                    eprintln!(
+                        "The {} cells filled in from the probabilities led to a dead end; \
+                         solved the puzzle by backtracking from its clues instead.",
+                        handoff.fixed
+                    );
+                } else {
+                    eprintln!(
+                        "Solved the puzzle by backtracking, from {} cells filled in from the \
+                         probabilities.",
+                        handoff.fixed
+                    );
+                }
+                input = handoff.solution;
+                outcome = solver::SolveResult::Success;
+            }
+            Err(e) => {
+                eprintln!("The backtracking solver could not finish the board either.");
+                eprintln!("{}", e);
+            }
+        }
+    }
+
+    match outcome {
         solver::SolveResult::IterationsExhausted => println!("EXHAUSTED"),
-        solver::SolveResult::Success => println!("ALL SATISFIED"),
+        solver::SolveResult::Cancelled => println!("CANCELLED"),
+        solver::SolveResult::Stalled => println!("STALLED"),
+        solver::SolveResult::Infeasible => println!("INFEASIBLE"),
+        solver::SolveResult::Success => {
+            if verify {
+                verify_or_exit(&puzzle, &input, &variant);
+            }
+            println!("ALL SATISFIED")
+        }
     }
 
     println!("{}", input);
+
+    if show_confidence {
+        report_confidence(&result.tensor);
+    }
+    if let Some(threshold) = undecided {
+        report_undecided(&result.tensor, threshold);
+    }
+}
+
+/// Writes the entropy of the tensor, and the confidence of each cell, to
+/// stderr.
+fn report_confidence(probabilities: &Array3<f64>) {
+    eprintln!("entropy {:.3} bits", tensor::entropy(probabilities));
+    eprintln!("confidence");
+    for row in tensor::confidence(probabilities).rows() {
+        let row = row.iter().map(|p| format!("{:.2}", p)).collect::<Vec<_>>();
+        eprintln!("{}", row.join(" "));
+    }
+}
+
+/// Lists the two most likely digits of every cell where no digit has
+/// probability `threshold` or more, to stderr.
+fn report_undecided(probabilities: &Array3<f64>, threshold: f64) {
+    let confidence = tensor::confidence(probabilities);
+    for ((row, column), sure) in confidence.indexed_iter() {
+        if *sure >= threshold {
+            continue;
+        }
+        let candidates = tensor::top_candidates(probabilities, row, column, 2)
+            .into_iter()
+            .map(|(digit, p)| format!("{} {:.2}", digit, p))
+            .collect::<Vec<_>>();
+        eprintln!("r{}c{} {}", row + 1, column + 1, candidates.join(" "));
+    }
+}
+
+/// Reports how many constraints the solver set up, and with --progress how
+/// every sweep went, to stderr.
+struct Report {
+    show_progress: bool,
+}
+
+impl ProgressSink<Progress> for Report {
+    fn on_progress(&mut self, progress: &Progress) {
+        match progress {
+            Progress::Constraints(count) => {
+                eprintln!("Finished computing constraints. Got {} constraints.", count);
+            }
+            Progress::Iteration(progress) if self.show_progress => {
+                eprintln!(
+                    "sweep {} violations {} change {:.3e}",
+                    progress.iteration, progress.violations, progress.change
+                );
+            }
+            Progress::Iteration(_) => {}
+        }
+    }
+}
+
+/// Opens `path` for reading, or exits with code 1 if it cannot.
+fn open_or_exit(path: &Path) -> std::fs::File {
+    match std::fs::File::open(path) {
+        Ok(reader) => reader,
+        Err(e) => {
+            eprintln!(
+                "Could not open {} for reading.\nWith error {}",
+                path.to_string_lossy(),
+                e
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Writes the tensor to `path` as CSV, or exits with code 1 if it cannot.
+fn dump_tensor_or_exit(path: &Path, probabilities: &Array3<f64>) {
+    let written = std::fs::File::create(path)
+        .map(std::io::BufWriter::new)
+        .and_then(|writer| tensor::write_csv(probabilities, writer));
+    if let Err(e) = written {
+        eprintln!(
+            "Could not write the tensor to {}.\nWith error {}",
+            path.to_string_lossy(),
+            e
+        );
+        std::process::exit(1);
+    }
+}
+
+/// Checks the solution against the puzzle, and the variant constraints, and
+/// exits with code 1 if anything is wrong with it.
+fn verify_or_exit(puzzle: &Sudoku, solution: &Sudoku, constraints: &Constraints) {
+    let mismatches = sudoku::verify_solution(puzzle, solution).err();
+    let broken = constraints.validate(solution).err();
+    if mismatches.is_none() && broken.is_none() {
+        return;
+    }
+
+    eprintln!("The solver reported a solution, but it is wrong!");
+    for mismatch in mismatches.unwrap_or_default() {
+        eprintln!("{}", mismatch);
+    }
+    for constraint in broken.unwrap_or_default() {
+        eprintln!("It breaks the constraint: {}", constraint);
+    }
+    eprintln!("{}", solution);
+    std::process::exit(1);
 }
@@ -0,0 +1,40 @@
+//! The iteration used to look for a point in every constraint set at once.
+
+/// How the solver combines the projections onto the constraint sets.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Method {
+    /// Alternating projections: project the iterate onto each constraint in
+    /// turn, every sweep.
+    #[default]
+    Alternating,
+    /// Douglas–Rachford splitting (the difference map), in its "divide and
+    /// concur" form: each of the rows, columns, boxes and cells gets a copy
+    /// of the iterate, which is reflected through the average of the copies
+    /// and then through the projection onto its constraints. The constraints
+    /// are taken to be the vertices of the simplexes, i.e., a single digit or
+    /// position being certain. Unlike alternating projections, it does not
+    /// settle on points that are merely close to every set, and so stagnates
+    /// much less on sudoku.
+    DouglasRachford,
+    /// Entropic, or Sinkhorn-style, projections: the constraints are
+    /// projected onto in turn, as with alternating projections, but by
+    /// rescaling the entries of each to sum to 1. Before every sweep, the
+    /// entries are raised to the power of one over a temperature, which
+    /// goes down from sweep to sweep as set by
+    /// [`ProjectionOptions::temperature`](crate::ProjectionOptions::temperature)
+    /// and [`ProjectionOptions::cooling`](crate::ProjectionOptions::cooling),
+    /// so that the iterate is smooth at first and sharpens as it goes.
+    Entropic,
+}
+
+impl Method {
+    /// The method with the given name, as in `ap`, `dr` or `entropic`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "ap" => Some(Method::Alternating),
+            "dr" => Some(Method::DouglasRachford),
+            "entropic" => Some(Method::Entropic),
+            _ => None,
+        }
+    }
+}
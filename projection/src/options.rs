@@ -0,0 +1,68 @@
+use sudoku::constraints::Constraints;
+use sudoku::Budget;
+
+use crate::init::Init;
+use crate::method::Method;
+use crate::rounding::Rounding;
+use crate::sweep::Sweep;
+
+/// The options of the projection solver, as taken by [`crate::solve`].
+///
+/// Unset options can be filled in from the default, e.g.
+/// `ProjectionOptions { max_iterations: 500, ..Default::default() }`.
+#[derive(Clone)]
+pub struct ProjectionOptions {
+    /// Maximum number of sweeps over the constraints, or 0 to keep going
+    /// until a solution is found, or the solver stalls (see
+    /// [`Self::tolerance`]) or runs out of [`Self::budget`].
+    pub max_iterations: usize,
+    /// Variant constraints a state must follow to count as a solution.
+    pub constraints: Constraints,
+    /// Limits on the run, whose steps are sweeps; unlike running out of
+    /// iterations, running out of budget cancels the solver.
+    pub budget: Budget,
+    pub method: Method,
+    pub init: Init,
+    pub rounding: Rounding,
+    pub sweep: Sweep,
+    /// Seed for the random sweep orders, or `None` to seed from the system's
+    /// entropy.
+    pub seed: Option<u64>,
+    /// With [`Method::Entropic`], the temperature of the first sweep. Above
+    /// 1, the tensor is smoothed out before the sweep; below 1, sharpened.
+    pub temperature: f64,
+    /// With [`Method::Entropic`], the factor by which the temperature goes
+    /// down after every sweep.
+    pub cooling: f64,
+    /// How close, entry by entry, two iterates must be to count as the same.
+    ///
+    /// When set, the solver stops with [`crate::SolveResult::Stalled`] as soon as
+    /// an iterate is this close to one of the last [`Self::stall_window`]
+    /// iterates, since it will then most likely keep going around in
+    /// circles. When unset, only a solution or the limits stop the solver.
+    pub tolerance: Option<f64>,
+    /// How many of the last iterates to compare against, with a tolerance.
+    ///
+    /// With 1, the solver only stops when an iterate barely differs from the
+    /// one before; with more, it also catches longer cycles.
+    pub stall_window: usize,
+}
+
+impl Default for ProjectionOptions {
+    fn default() -> Self {
+        ProjectionOptions {
+            max_iterations: 10_000,
+            constraints: Constraints::new(),
+            budget: Budget::unlimited(),
+            method: Method::Alternating,
+            init: Init::Zeros,
+            rounding: Rounding::Argmax,
+            sweep: Sweep::Cyclic,
+            seed: None,
+            temperature: 1.,
+            cooling: 0.99,
+            tolerance: None,
+            stall_window: 1,
+        }
+    }
+}
@@ -0,0 +1,23 @@
+use std::time::Duration;
+
+/// What the projection solver reports to a
+/// [`ProgressSink`](sudoku::ProgressSink).
+pub enum Progress {
+    /// Reported once the constraints have been set up, before the first
+    /// sweep, with how many there are.
+    Constraints(usize),
+    /// Reported after every sweep over the constraints.
+    Iteration(Iteration),
+}
+
+/// A snapshot of the state of the projection loop, after a sweep.
+pub struct Iteration {
+    /// Number of sweeps over the constraints completed so far.
+    pub iteration: usize,
+    pub elapsed: Duration,
+    /// Number of conflicts on the board read off the tensor after the sweep,
+    /// plus the number of times it breaks the variant constraints.
+    pub violations: usize,
+    /// The largest change of any probability over the sweep.
+    pub change: f64,
+}
@@ -0,0 +1,138 @@
+//! How a board is read off the probability tensor.
+
+use ndarray::Array3;
+use sudoku::Sudoku;
+
+/// How the solver turns the probabilities into a board, to check whether it
+/// is a solution.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Rounding {
+    /// Each cell gets its most likely digit, independently of the others.
+    #[default]
+    Argmax,
+    /// Each row gets the arrangement of digits with the highest total
+    /// probability, as found by solving the assignment problem of digits to
+    /// the cells of the row, so that no digit is repeated in a row. Greedy
+    /// rounding often repeats a digit even when the tensor is nearly
+    /// integral.
+    Assignment,
+}
+
+impl Rounding {
+    /// The rounding with the given name, as in `argmax` or `assignment`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "argmax" => Some(Rounding::Argmax),
+            "assignment" => Some(Rounding::Assignment),
+            _ => None,
+        }
+    }
+
+    /// Sets the cells of `sudoku` according to the tensor.
+    ///
+    /// With [`Rounding::Argmax`], cells where every digit has probability 0
+    /// are left as they were.
+    pub fn round(self, tensor: &Array3<f64>, sudoku: &mut Sudoku) {
+        self.round_by(|r, c, d| tensor[[r, c, d]], sudoku);
+    }
+
+    /// As [`Rounding::round`], with the probability of digit `d + 1` in the
+    /// cell at `(r, c)` given by `probability(r, c, d)`, for tensors that are
+    /// not stored densely.
+    pub fn round_by(self, probability: impl Fn(usize, usize, usize) -> f64, sudoku: &mut Sudoku) {
+        let side = sudoku.side();
+        match self {
+            Rounding::Argmax => {
+                for r in 0..side {
+                    for c in 0..side {
+                        let mut best_prob = 0.;
+                        for d in 0..side {
+                            let prob = probability(r, c, d);
+                            if prob > best_prob {
+                                best_prob = prob;
+                                sudoku.set(r, c, sudoku::SudokuCell::digit(d + 1));
+                            }
+                        }
+                    }
+                }
+            }
+            Rounding::Assignment => {
+                for r in 0..side {
+                    let cost = (0..side)
+                        .map(|c| (0..side).map(|d| -probability(r, c, d)).collect())
+                        .collect::<Vec<Vec<f64>>>();
+                    for (c, d) in hungarian(&cost).into_iter().enumerate() {
+                        sudoku.set(r, c, sudoku::SudokuCell::digit(d + 1));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Solves the assignment problem for a square matrix of costs, with the
+/// Hungarian algorithm, returning the column assigned to each row so that the
+/// total cost is least.
+fn hungarian(cost: &[Vec<f64>]) -> Vec<usize> {
+    // Following the usual O(n^3) formulation, with potentials on the rows and
+    // columns, and a dummy column 0 to start each augmenting path from.
+    let n = cost.len();
+    let mut row_potential = vec![0.; n + 1];
+    let mut column_potential = vec![0.; n + 1];
+    // The row (1-based, 0 for none) matched to each column.
+    let mut matched = vec![0; n + 1];
+    // The previous column on the shortest augmenting path to each column.
+    let mut way = vec![0; n + 1];
+
+    for row in 1..=n {
+        matched[0] = row;
+        let mut column = 0;
+        let mut slack = vec![f64::INFINITY; n + 1];
+        let mut visited = vec![false; n + 1];
+        loop {
+            visited[column] = true;
+            let current_row = matched[column];
+            let mut delta = f64::INFINITY;
+            let mut next = 0;
+            for j in 1..=n {
+                if visited[j] {
+                    continue;
+                }
+                let reduced =
+                    cost[current_row - 1][j - 1] - row_potential[current_row] - column_potential[j];
+                if reduced < slack[j] {
+                    slack[j] = reduced;
+                    way[j] = column;
+                }
+                if slack[j] < delta {
+                    delta = slack[j];
+                    next = j;
+                }
+            }
+            for j in 0..=n {
+                if visited[j] {
+                    row_potential[matched[j]] += delta;
+                    column_potential[j] -= delta;
+                } else {
+                    slack[j] -= delta;
+                }
+            }
+            column = next;
+            if matched[column] == 0 {
+                break;
+            }
+        }
+        // Flip the augmenting path.
+        while column != 0 {
+            let previous = way[column];
+            matched[column] = matched[previous];
+            column = previous;
+        }
+    }
+
+    let mut assignment = vec![0; n];
+    for j in 1..=n {
+        assignment[matched[j] - 1] = j - 1;
+    }
+    assignment
+}
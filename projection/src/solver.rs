@@ -1,14 +1,92 @@
 use itertools::Itertools;
 use ndarray::prelude::*;
-use std::collections::{HashMap, HashSet};
-use sudoku::SudokuCellValue;
+use rand::rngs::StdRng;
+use rand::{prelude::SliceRandom, SeedableRng};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
+use sudoku::{check, NoProgress, ProgressSink, SolveError, Solver, SudokuCellValue};
+
+use crate::init::Init;
+use crate::layout::Layout;
+use crate::method::Method;
+use crate::options::ProjectionOptions;
+use crate::progress::{Iteration, Progress};
+use crate::sweep::Sweep;
+
+/// Statistics about a finished run of the projection solver.
+#[derive(Debug, Clone)]
+pub struct SolveStats {
+    pub elapsed: Duration,
+    /// Number of sweeps over the constraints.
+    pub iterations: usize,
+}
 
 pub enum SolveResult {
     IterationsExhausted,
+    Cancelled,
+    /// The iterate came back to within the tolerance of one of the last
+    /// few, without being a solution; see [`ProjectionOptions::tolerance`].
+    Stalled,
+    /// The clues contradict each other, or leave a digit with nowhere to go
+    /// in some unit, or a cell with no digit that can go in it, so there is
+    /// no solution. This is found out before the first sweep.
+    Infeasible,
     Success,
 }
 
-pub fn solve(sudoku: &mut sudoku::Sudoku, max_iterations: usize) -> SolveResult {
+/// What a run of [`solve`] came to, and how long it took to get there.
+pub struct ProjectionResult {
+    pub outcome: SolveResult,
+    pub stats: SolveStats,
+    /// The last iterate: the probability of digit `d + 1` in the cell at
+    /// `(row, column)` is at `[row, column, d]`.
+    pub tensor: Array3<f64>,
+}
+
+/// The projection solver, as a [`Solver`], with the options of [`solve`].
+///
+/// Every outcome but [`SolveResult::Success`] is reported as an error, with
+/// [`SolveResult::Stalled`] as [`SolveError::Glassed`]; [`solve`] also gives
+/// the last iterate.
+#[derive(Clone, Default)]
+pub struct Projection {
+    pub options: ProjectionOptions,
+}
+
+impl Solver for Projection {
+    type Report = SolveStats;
+
+    fn solve(&self, sudoku: &mut sudoku::Sudoku) -> Result<SolveStats, SolveError> {
+        let result = solve(sudoku, &self.options, &mut NoProgress);
+        match result.outcome {
+            SolveResult::Success => Ok(result.stats),
+            SolveResult::IterationsExhausted => Err(SolveError::IterationsExhausted),
+            SolveResult::Cancelled => Err(SolveError::Cancelled),
+            SolveResult::Stalled => Err(SolveError::Glassed),
+            SolveResult::Infeasible => Err(SolveError::Infeasible),
+        }
+    }
+}
+
+/// Runs alternating projections on `sudoku`, and leaves on it the board read
+/// off the last iterate when a solution is found.
+///
+/// # Panics
+///
+/// If the starting tensor or hint in the options is not the size of the board.
+pub fn solve(
+    sudoku: &mut sudoku::Sudoku,
+    options: &ProjectionOptions,
+    progress: &mut dyn ProgressSink<Progress>,
+) -> ProjectionResult {
+    let started = Instant::now();
+    let max_iterations = match options.max_iterations {
+        0 => usize::MAX,
+        limit => limit,
+    };
+    let variant = &options.constraints;
+    let budget = &options.budget;
+
     // Here, we will not use the internal representation of the Sudoku, and
     // will instead work with the probability 3-tensor described in [0].
     //
@@ -17,32 +95,25 @@ pub fn solve(sudoku: &mut sudoku::Sudoku, max_iterations: usize) -> SolveResult
     let side = sudoku.side();
     let box_side = sudoku.box_side();
 
-    let mut tensor = ndarray::Array::<f64, _>::zeros((side, side, side));
-
-    let influence_pairs = (0..side)
-        .cartesian_product(0..side)
-        .tuple_combinations()
-        .filter(|((r, c), (rr, cc))| {
-            if r == rr || c == cc {
-                return true;
-            }
-            (r / box_side) == (rr / box_side) && (c / box_side) == (cc / box_side)
-        });
-
-    // Precompute the valid elements of the rows, columns, subgrids and cells.
-    let mut row_digit_simplexes =
-        HashMap::<(usize, usize), Vec<&mut f64>>::with_capacity(side * side);
+    // Precompute the valid elements of the rows, columns, subgrids and cells,
+    // as indices into the tensor, which only stores the entries for these,
+    // and for the clues; the rest are 0 throughout.
+    let mut row_digit_simplexes = HashMap::<(usize, usize), Vec<usize>>::with_capacity(side * side);
     let mut column_digit_simplexes =
-        HashMap::<(usize, usize), Vec<&mut f64>>::with_capacity(side * side);
+        HashMap::<(usize, usize), Vec<usize>>::with_capacity(side * side);
     let mut subgrid_digit_simplexes =
-        HashMap::<(usize, usize, usize), Vec<&mut f64>>::with_capacity(side * side);
-    let mut cell_simplexes = HashMap::<(usize, usize), Vec<&mut f64>>::with_capacity(side * side);
+        HashMap::<(usize, usize, usize), Vec<usize>>::with_capacity(side * side);
+    let mut cell_simplexes = HashMap::<(usize, usize), Vec<usize>>::with_capacity(side * side);
+    let layout;
 
     {
         let digit_can_go_here = |row, column, d| {
             if !sudoku.get(row, column).is_empty() {
                 return false;
             }
+            if !variant.allows(row, column, d + 1, side) {
+                return false;
+            }
 
             for rr in 0..side {
                 if rr == column {
@@ -78,21 +149,15 @@ pub fn solve(sudoku: &mut sudoku::Sudoku, max_iterations: usize) -> SolveResult
             return true;
         };
 
-        let base_ptr = tensor.as_ptr();
-        let strides = tensor.strides();
+        layout = Layout::new(side, |row, column, d| {
+            digit_can_go_here(row, column, d) || sudoku.get(row, column).value() == Some(d + 1)
+        });
+        let at = |row, column, d| layout.index(row, column, d).unwrap();
 
         for row in 0..side {
             for d in 0..side {
                 let valid_cols = (0..side).filter(|cc| digit_can_go_here(row, *cc, d));
-                let simplex = valid_cols
-                    .map(|cc| unsafe {
-                        &mut *(base_ptr.offset(
-                            row as isize * strides[0]
-                                + cc as isize * strides[1]
-                                + d as isize * strides[2],
-                        ) as *mut f64)
-                    })
-                    .collect_vec();
+                let simplex = valid_cols.map(|cc| at(row, cc, d)).collect_vec();
                 row_digit_simplexes.insert((row, d), simplex);
             }
         }
@@ -100,15 +165,7 @@ pub fn solve(sudoku: &mut sudoku::Sudoku, max_iterations: usize) -> SolveResult
         for column in 0..side {
             for d in 0..side {
                 let valid_rows = (0..side).filter(|rr| digit_can_go_here(*rr, column, d));
-                let simplex = valid_rows
-                    .map(|rr| unsafe {
-                        &mut *(base_ptr.offset(
-                            rr as isize * strides[0]
-                                + column as isize * strides[1]
-                                + d as isize * strides[2],
-                        ) as *mut f64)
-                    })
-                    .collect_vec();
+                let simplex = valid_rows.map(|rr| at(rr, column, d)).collect_vec();
                 column_digit_simplexes.insert((column, d), simplex);
             }
         }
@@ -125,13 +182,7 @@ pub fn solve(sudoku: &mut sudoku::Sudoku, max_iterations: usize) -> SolveResult
                         })
                         .map(|(v, h)| (subgrid_base_row + v, subgrid_base_col + h));
                     let simplex = valid_subgrid_positions
-                        .map(|(rr, cc)| unsafe {
-                            &mut *(base_ptr.offset(
-                                rr as isize * strides[0]
-                                    + cc as isize * strides[1]
-                                    + d as isize * strides[2],
-                            ) as *mut f64)
-                        })
+                        .map(|(rr, cc)| at(rr, cc, d))
                         .collect_vec();
                     subgrid_digit_simplexes
                         .insert((subgrid_base_row, subgrid_base_col, d), simplex);
@@ -142,53 +193,31 @@ pub fn solve(sudoku: &mut sudoku::Sudoku, max_iterations: usize) -> SolveResult
         for row in 0..side {
             for column in 0..side {
                 let valid_digits_here = (0..side).filter(|d| digit_can_go_here(row, column, *d));
-                let simplex = valid_digits_here
-                    .map(|d| unsafe {
-                        &mut *(base_ptr.offset(
-                            row as isize * strides[0]
-                                + column as isize * strides[1]
-                                + d as isize * strides[2],
-                        ) as *mut f64)
-                    })
-                    .collect_vec();
+                let simplex = valid_digits_here.map(|d| at(row, column, d)).collect_vec();
                 cell_simplexes.insert((row, column), simplex);
             }
         }
     }
 
-    let set_according_to_tensor =
-        |sudoku: &mut sudoku::Sudoku,
-         tensor: ArrayBase<ndarray::OwnedRepr<f64>, Dim<[usize; 3]>>| {
-            for r in 0..side {
-                for c in 0..side {
-                    let mut best_prob = 0.;
-                    for (index, prob) in tensor.slice(s![r, c, ..]).iter().enumerate() {
-                        if *prob > best_prob {
-                            best_prob = *prob;
-                            sudoku.set(r, c, sudoku::SudokuCell::Digit(index + 1));
-                        }
-                    }
-                }
-            }
-        };
-
-    let simplex_projection = |y: &mut [&mut f64]| {
+    // The projections onto the constraints take the entries of the tensor,
+    // and the indices of the entries that the constraint is about.
+    let simplex_projection = |y: &mut [f64], group: &[usize]| {
         // Following the formulation of Algorithm 1 [0].
         // Insertion sort; we need to preserve a copy of y anyway
         // (I started by implementing quick sort in place and was very proud)
         let w = {
             let mut w = Vec::<f64>::with_capacity(side);
 
-            for element in y.iter() {
+            for element in group.iter().map(|i| y[*i]) {
                 let insert_in = match w.binary_search_by(|e| {
-                    e.partial_cmp(element)
+                    e.partial_cmp(&element)
                         .unwrap_or(std::cmp::Ordering::Equal)
                         .reverse()
                 }) {
                     Ok(i) => i,
                     Err(i) => i,
                 };
-                w.insert(insert_in, **element);
+                w.insert(insert_in, element);
             }
             w
         };
@@ -206,12 +235,12 @@ pub fn solve(sudoku: &mut sudoku::Sudoku, max_iterations: usize) -> SolveResult
         let lambda = (cw - 1.) / ((k + 1) as f64);
 
         // Project
-        for i in 0..y.len() {
-            *y[i] = (*y[i] - lambda).max(0.);
+        for i in group.iter() {
+            y[*i] = (y[*i] - lambda).max(0.);
         }
 
-        debug_assert!(y.iter().all(|x| **x >= 0.));
-        debug_assert!((y.iter().map(|x: &&mut f64| **x).sum::<f64>() - 1.).abs() <= 1e-6);
+        debug_assert!(group.iter().all(|i| y[*i] >= 0.));
+        debug_assert!((group.iter().map(|i| y[*i]).sum::<f64>() - 1.).abs() <= 1e-6);
     };
 
     #[derive(Debug)]
@@ -231,6 +260,28 @@ pub fn solve(sudoku: &mut sudoku::Sudoku, max_iterations: usize) -> SolveResult
         /// (row, col, digit - 1)
         /// Probability of this digit in this place is 1
         Known(usize, usize, usize),
+        /// (entry, entry), as indices into the tensor
+        /// Probabilities of consecutive digits in orthogonally adjacent cells
+        /// add up to at most 1
+        NotConsecutive(usize, usize),
+    }
+
+    impl Constraint {
+        /// Which of the rows, columns, subgrids or cells the constraint is
+        /// about, as an index into the copies of the iterate kept by
+        /// Douglas-Rachford; the constraints of each are on disjoint entries.
+        fn family(&self) -> usize {
+            match self {
+                Constraint::RowSimplex(..) => 0,
+                Constraint::ColSimplex(..) => 1,
+                Constraint::SubgridSimplex(..) => 2,
+                Constraint::DigitSimplex(..) | Constraint::Known(..) => 3,
+                // These overlap, so for Douglas-Rachford, projecting onto
+                // them one after the other only approximates projecting onto
+                // all of them at once.
+                Constraint::NotConsecutive(..) => 4,
+            }
+        }
     }
 
     let constraints = ((0..side)
@@ -283,51 +334,333 @@ pub fn solve(sudoku: &mut sudoku::Sudoku, max_iterations: usize) -> SolveResult
             .value()
             .map(|digit| Constraint::Known(r, c, digit - 1))
     }))
+    .chain(
+        // Each pair of adjacent cells once, as a cell and the one to its
+        // right or below it, with each pair of consecutive digits that can
+        // go in them.
+        (0..side)
+            .cartesian_product(0..side)
+            .flat_map(|(r, c)| [(r, c, r, c + 1), (r, c, r + 1, c)])
+            .filter(|&(_, _, rr, cc)| variant.non_consecutive && rr < side && cc < side)
+            .flat_map(|(r, c, rr, cc)| {
+                let layout = &layout;
+                (0..side)
+                    .cartesian_product(0..side)
+                    .filter(|(d, dd)| d.abs_diff(*dd) == 1)
+                    .map(move |(d, dd)| (layout.index(r, c, d), layout.index(rr, cc, dd)))
+            })
+            .filter_map(|pair| match pair {
+                (Some(a), Some(b)) => Some(Constraint::NotConsecutive(a, b)),
+                _ => None,
+            }),
+    )
     .collect::<Vec<Constraint>>();
+    let families = 1 + constraints
+        .iter()
+        .map(Constraint::family)
+        .max()
+        .unwrap_or(0);
+
+    progress.on_progress(&Progress::Constraints(constraints.len()));
+
+    // The entries a constraint is about, or None for a known digit, which
+    // is not a simplex.
+    let group_of = |constraint: &Constraint| -> Option<&[usize]> {
+        match constraint {
+            Constraint::RowSimplex(row, d) => Some(&row_digit_simplexes[&(*row, *d)]),
+            Constraint::ColSimplex(col, d) => Some(&column_digit_simplexes[&(*col, *d)]),
+            Constraint::SubgridSimplex(a, b, d) => Some(&subgrid_digit_simplexes[&(*a, *b, *d)]),
+            Constraint::DigitSimplex(row, col) => Some(&cell_simplexes[&(*row, *col)]),
+            Constraint::Known(..) | Constraint::NotConsecutive(..) => None,
+        }
+    };
+
+    // A digit with nowhere to go in a unit, or a cell with no digit that can
+    // go in it, means there is no solution at all; no amount of sweeping
+    // would find one, so there is no point starting.
+    let infeasible = check::has_conflicts(sudoku)
+        || constraints
+            .iter()
+            .filter_map(group_of)
+            .any(|group| group.is_empty());
+    let mut tensor = vec![0.; layout.len()];
+    if infeasible {
+        let stats = SolveStats {
+            elapsed: started.elapsed(),
+            iterations: 0,
+        };
+        return ProjectionResult {
+            outcome: SolveResult::Infeasible,
+            stats,
+            tensor: layout.unpack(&tensor),
+        };
+    }
+
+    // Douglas-Rachford projects onto the vertices of the simplexes instead,
+    // i.e., onto a single digit or position being certain. Every simplex
+    // shares fractional points with the others, on which it would otherwise
+    // settle just like alternating projections.
+    let vertex_projection = |y: &mut [f64], group: &[usize]| {
+        let best = group.iter().copied().max_by(|i, j| {
+            y[*i]
+                .partial_cmp(&y[*j])
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        for i in group.iter() {
+            y[*i] = if Some(*i) == best { 1. } else { 0. };
+        }
+    };
+    // The entropic method works multiplicatively, projecting in the sense of
+    // the Kullback-Leibler divergence rather than of the distance, which only
+    // takes rescaling the entries to sum to 1.
+    let normalization = |y: &mut [f64], group: &[usize]| {
+        let sum = group.iter().map(|i| y[*i]).sum::<f64>();
+        let uniform = 1. / group.len() as f64;
+        for i in group.iter() {
+            y[*i] = if sum > 0. { y[*i] / sum } else { uniform };
+        }
+    };
+
+    let project_onto = |y: &mut [f64], group: &[usize]| match options.method {
+        Method::Alternating => simplex_projection(y, group),
+        Method::DouglasRachford => vertex_projection(y, group),
+        Method::Entropic => normalization(y, group),
+    };
+
+    // Two entries adding up to at most 1 is a half-plane, so the projection
+    // onto it (and the nonnegative entries) takes the excess off both
+    // equally, or, in the sense of the Kullback-Leibler divergence, rescales
+    // them.
+    let pair_projection = |y: &mut [f64], a: usize, b: usize| {
+        let sum = y[a] + y[b];
+        if sum <= 1. {
+            return;
+        }
+        if options.method == Method::Entropic {
+            y[a] /= sum;
+            y[b] /= sum;
+            return;
+        }
+        let excess = (sum - 1.) / 2.;
+        (y[a], y[b]) = if y[b] < excess {
+            (y[a].min(1.), 0.)
+        } else if y[a] < excess {
+            (0., y[b].min(1.))
+        } else {
+            (y[a] - excess, y[b] - excess)
+        };
+    };
+
+    // Multiplicative updates never move off 0, so the entropic method starts
+    // out uniform over the digits that can go in each cell by default.
+    let uniform = match &options.init {
+        Init::Zeros => options.method == Method::Entropic,
+        Init::Uniform | Init::Hint(_) => true,
+        Init::Tensor(_) => false,
+    };
+    if uniform {
+        for simplex in cell_simplexes.values() {
+            let uniform = 1. / simplex.len() as f64;
+            for i in simplex.iter() {
+                tensor[*i] = uniform;
+            }
+        }
+    }
+    match &options.init {
+        Init::Tensor(start) => {
+            assert_eq!(
+                start.dim(),
+                (side, side, side),
+                "The starting tensor is not the size of the board"
+            );
+            tensor = layout.pack(start);
+        }
+        Init::Hint(hint) => {
+            assert_eq!(hint.side(), side, "The hint is not the size of the board");
+            for row in 0..side {
+                for column in 0..side {
+                    let d = match hint.get(row, column).value() {
+                        Some(digit) => digit - 1,
+                        None => continue,
+                    };
+                    // Only where the digit is one of the candidates.
+                    if let Some(at) = layout.index(row, column, d).filter(|i| tensor[*i] > 0.) {
+                        for i in layout.cell(row, column) {
+                            tensor[i] = if i == at { 1. } else { 0. };
+                        }
+                    }
+                }
+            }
+        }
+        Init::Zeros | Init::Uniform => {}
+    }
+
+    let project = |constraint: &Constraint, y: &mut [f64]| {
+        match constraint {
+            // The other digits of the cell are not stored, and so already 0.
+            Constraint::Known(row, col, d) => y[layout.index(*row, *col, *d).unwrap()] = 1.,
+            Constraint::NotConsecutive(a, b) => pair_projection(y, *a, *b),
+            _ => project_onto(y, group_of(constraint).unwrap()),
+        }
+    };
+
+    // With Douglas-Rachford, one copy of the iterate for each family of
+    // constraints; the tensor is then their average.
+    let mut copies = match options.method {
+        Method::DouglasRachford => vec![tensor.clone(); families],
+        Method::Alternating | Method::Entropic => vec![],
+    };
+
+    // The last few iterates, to tell when the solver is going around in
+    // circles; only kept when there is a tolerance to compare them with.
+    let mut recent = VecDeque::<Vec<f64>>::new();
 
-    eprintln!(
-        "Finished computing constraints. Got {} constraints.",
-        constraints.len()
-    );
+    // The order of the constraints in a sweep, as indices into them.
+    let mut rng = match options.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    let mut order = (0..constraints.len()).collect_vec();
+    if options.sweep == Sweep::Shuffled {
+        order.shuffle(&mut rng);
+    }
+
+    // The iterate before the sweep, to report how much it changed.
+    let mut previous = tensor.clone();
+
+    for iteration in 0..max_iterations {
+        if budget.is_exhausted(&started, iteration) {
+            let stats = SolveStats {
+                elapsed: started.elapsed(),
+                iterations: iteration,
+            };
+            return ProjectionResult {
+                outcome: SolveResult::Cancelled,
+                stats,
+                tensor: layout.unpack(&tensor),
+            };
+        }
 
-    for _iteration in 0..max_iterations {
-        for constraint in constraints.iter() {
-            match constraint {
-                Constraint::RowSimplex(row, d) => {
-                    simplex_projection(row_digit_simplexes.get_mut(&(*row, *d)).unwrap())
+        previous.copy_from_slice(&tensor);
+        if options.sweep == Sweep::Random {
+            order.shuffle(&mut rng);
+        }
+        match options.method {
+            Method::Alternating => {
+                for constraint in order.iter().map(|i| &constraints[*i]) {
+                    project(constraint, &mut tensor);
                 }
-                Constraint::ColSimplex(col, d) => {
-                    simplex_projection(column_digit_simplexes.get_mut(&(*col, *d)).unwrap())
+            }
+            Method::Entropic => {
+                // Sharpen the tensor before the sweep, more so as the
+                // temperature goes down, so that it is pushed towards a
+                // single digit per cell rather than balancing out.
+                let temperature = options.temperature * options.cooling.powf(iteration as f64);
+                let sharpness = 1. / temperature.max(f64::MIN_POSITIVE);
+                for x in tensor.iter_mut() {
+                    *x = x.powf(sharpness);
                 }
-                Constraint::DigitSimplex(row, col) => {
-                    simplex_projection(cell_simplexes.get_mut(&(*row, *col)).unwrap())
+                for constraint in order.iter().map(|i| &constraints[*i]) {
+                    project(constraint, &mut tensor);
                 }
-                Constraint::SubgridSimplex(a, b, d) => {
-                    simplex_projection(subgrid_digit_simplexes.get_mut(&(*a, *b, *d)).unwrap())
+            }
+            Method::DouglasRachford => {
+                // The tensor holds the average of the copies; each copy is
+                // moved by the difference between the projection of its
+                // reflection through the average, and the average.
+                let average = tensor.clone();
+                for (family, copy) in copies.iter_mut().enumerate() {
+                    for ((y, a), x) in tensor.iter_mut().zip(&average).zip(copy.iter()) {
+                        *y = a * 2. - x;
+                    }
+                    for constraint in constraints.iter().filter(|c| c.family() == family) {
+                        project(constraint, &mut tensor);
+                    }
+                    for ((x, y), a) in copy.iter_mut().zip(&tensor).zip(&average) {
+                        *x += y - a;
+                    }
                 }
-                Constraint::Known(row, col, d) => {
-                    for dd in 0..side {
-                        tensor[[*row, *col, dd]] = if dd == *d { 1. } else { 0. };
+                tensor.fill(0.);
+                for copy in copies.iter() {
+                    for (y, x) in tensor.iter_mut().zip(copy) {
+                        *y += x;
                     }
                 }
+                for y in tensor.iter_mut() {
+                    *y /= families as f64;
+                }
             }
         }
 
         // Count violations
 
-        set_according_to_tensor(sudoku, tensor.clone());
-        let some_violation = influence_pairs.clone().any(|((r, c), (rr, cc))| {
-            sudoku.get(r, c).value().map_or(false, |v| {
-                sudoku.get(rr, cc).value().map_or(false, |vv| v == vv)
-            })
-        });
-        if !some_violation {
-            //println!("{:?}", tensor);
-            return SolveResult::Success;
+        // The projections only follow the usual rules and the
+        // non-consecutive rule, so the other variant constraints are only
+        // checked here.
+        options
+            .rounding
+            .round_by(|r, c, d| layout.get(&tensor, r, c, d), sudoku);
+        if !check::has_conflicts(sudoku) && variant.validate(sudoku).is_ok() {
+            let stats = SolveStats {
+                elapsed: started.elapsed(),
+                iterations: iteration + 1,
+            };
+            return ProjectionResult {
+                outcome: SolveResult::Success,
+                stats,
+                tensor: layout.unpack(&tensor),
+            };
+        }
+
+        let violations = check::conflicts(sudoku).len()
+            + variant
+                .iter()
+                .map(|constraint| constraint.violations(sudoku))
+                .sum::<usize>();
+        let change = previous
+            .iter()
+            .zip(tensor.iter())
+            .map(|(a, b)| (a - b).abs())
+            .fold(0., f64::max);
+        progress.on_progress(&Progress::Iteration(Iteration {
+            iteration: iteration + 1,
+            elapsed: started.elapsed(),
+            violations,
+            change,
+        }));
+
+        if let Some(tolerance) = options.tolerance {
+            let revisited = recent.iter().any(|earlier| {
+                earlier
+                    .iter()
+                    .zip(tensor.iter())
+                    .all(|(a, b)| (a - b).abs() <= tolerance)
+            });
+            if revisited {
+                let stats = SolveStats {
+                    elapsed: started.elapsed(),
+                    iterations: iteration + 1,
+                };
+                return ProjectionResult {
+                    outcome: SolveResult::Stalled,
+                    stats,
+                    tensor: layout.unpack(&tensor),
+                };
+            }
+            if recent.len() == options.stall_window.max(1) {
+                recent.pop_front();
+            }
+            recent.push_back(tensor.clone());
         }
     }
 
-    //println!("{:?}", tensor);
-    //set_according_to_tensor(sudoku, tensor);
-    SolveResult::IterationsExhausted
+    let stats = SolveStats {
+        elapsed: started.elapsed(),
+        iterations: max_iterations,
+    };
+    ProjectionResult {
+        outcome: SolveResult::IterationsExhausted,
+        stats,
+        tensor: layout.unpack(&tensor),
+    }
 }
@@ -0,0 +1,30 @@
+//! The order in which the constraints are projected onto in a sweep.
+
+/// The order in which a sweep goes over the constraints.
+///
+/// Alternating and entropic projections depend on the order, as each
+/// projection starts from where the last one left off. Douglas-Rachford does
+/// not, as it projects every copy of the iterate from the same average.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Sweep {
+    /// The same order every sweep: the rows, then the columns, the boxes, and
+    /// the cells, digit by digit.
+    #[default]
+    Cyclic,
+    /// A new random order every sweep.
+    Random,
+    /// A random order, drawn once and kept for every sweep.
+    Shuffled,
+}
+
+impl Sweep {
+    /// The order with the given name, as in `cyclic`, `random` or `shuffled`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "cyclic" => Some(Sweep::Cyclic),
+            "random" => Some(Sweep::Random),
+            "shuffled" => Some(Sweep::Shuffled),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use ndarray::{s, Array2, Array3, Axis};
+use std::io::{Read, Write};
+
+/// Writes the probability tensor as CSV, one line per cell and digit, with a
+/// header, so that the marginals can be inspected or plotted elsewhere.
+///
+/// Rows, columns and digits are numbered from 1, as on the board.
+pub fn write_csv(tensor: &Array3<f64>, mut writer: impl Write) -> std::io::Result<()> {
+    writeln!(writer, "row,column,digit,probability")?;
+    for ((row, column, d), probability) in tensor.indexed_iter() {
+        writeln!(
+            writer,
+            "{},{},{},{}",
+            row + 1,
+            column + 1,
+            d + 1,
+            probability
+        )?;
+    }
+    Ok(())
+}
+
+/// Reads a tensor written by [`write_csv`], for a board of the given side.
+///
+/// Entries that are not listed are 0.
+pub fn read_csv(mut reader: impl Read, side: usize) -> Result<Array3<f64>, String> {
+    let mut contents = String::new();
+    reader
+        .read_to_string(&mut contents)
+        .map_err(|e| format!("Could not read the tensor: {}", e))?;
+
+    let mut tensor = Array3::<f64>::zeros((side, side, side));
+    for (number, line) in contents.lines().enumerate() {
+        let error = |what: &str| format!("Line {}: {}", number + 1, what);
+        let line = line.trim();
+        if line.is_empty() || line == "row,column,digit,probability" {
+            continue;
+        }
+        let fields = line.split(',').map(str::trim).collect::<Vec<_>>();
+        if fields.len() != 4 {
+            return Err(error("Expected row,column,digit,probability."));
+        }
+        let index = |i: usize, what: &str| -> Result<usize, String> {
+            match fields[i].parse::<usize>() {
+                Ok(n) if (1..=side).contains(&n) => Ok(n - 1),
+                _ => Err(error(&format!("Expected a {} from 1 to {}.", what, side))),
+            }
+        };
+        let (row, column, d) = (index(0, "row")?, index(1, "column")?, index(2, "digit")?);
+        tensor[[row, column, d]] = fields[3]
+            .parse::<f64>()
+            .ok()
+            .filter(|p| p.is_finite())
+            .ok_or_else(|| error("Expected a probability."))?;
+    }
+    Ok(tensor)
+}
+
+/// How sure the tensor is of each cell: the largest probability of any digit
+/// there, indexed by row and column.
+pub fn confidence(tensor: &Array3<f64>) -> Array2<f64> {
+    tensor.map_axis(Axis(2), |probabilities| {
+        probabilities.iter().copied().fold(0., f64::max)
+    })
+}
+
+/// The total entropy of the cells, in bits, taking the probabilities of each
+/// cell as a distribution over its digits, after rescaling them to sum to 1.
+///
+/// This is 0 when every cell is certain of a digit, and grows with the
+/// number of cells left undecided.
+pub fn entropy(tensor: &Array3<f64>) -> f64 {
+    tensor
+        .lanes(Axis(2))
+        .into_iter()
+        .map(|probabilities| {
+            let sum = probabilities.iter().filter(|p| **p > 0.).sum::<f64>();
+            probabilities
+                .iter()
+                .filter(|p| **p > 0.)
+                .map(|p| p / sum)
+                .map(|p| -p * p.log2())
+                .sum::<f64>()
+        })
+        .sum()
+}
+
+/// The `n` most likely digits of a cell, with their probabilities, most
+/// likely first, leaving out digits of probability 0.
+pub fn top_candidates(
+    tensor: &Array3<f64>,
+    row: usize,
+    column: usize,
+    n: usize,
+) -> Vec<(usize, f64)> {
+    let mut candidates = tensor
+        .slice(s![row, column, ..])
+        .iter()
+        .enumerate()
+        .filter(|(_, p)| **p > 0.)
+        .map(|(d, p)| (d + 1, *p))
+        .collect::<Vec<_>>();
+    candidates.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    candidates.truncate(n);
+    candidates
+}
@@ -0,0 +1,90 @@
+use itertools::Itertools;
+use sudoku::constraints::Constraints;
+use sudoku::{Sudoku, SudokuCellValue};
+
+/// A literal, in DIMACS convention: variables are numbered from 1, and a
+/// negative number denotes the negation of the variable.
+pub type Literal = i32;
+
+/// A formula in conjunctive normal form.
+pub struct Cnf {
+    pub variables: usize,
+    pub clauses: Vec<Vec<Literal>>,
+}
+
+impl Cnf {
+    /// The standard encoding of a sudoku of side `n`: there is one variable per
+    /// (row, column, digit), true if the digit is placed there, and clauses
+    /// requiring that
+    ///  - every cell holds exactly one digit,
+    ///  - every row, column and box holds every digit exactly once,
+    ///  - every clue is respected,
+    ///  - no cell holds a digit that the constraints do not allow there,
+    ///  - with the non-consecutive rule, no two orthogonally adjacent cells
+    ///    hold consecutive digits.
+    ///
+    /// The other variant constraints are not encoded; see
+    /// [`crate::solve`] for how they are enforced.
+    pub fn for_sudoku(sudoku: &Sudoku, constraints: &Constraints) -> Self {
+        let side = sudoku.side();
+        let box_side = sudoku.box_side();
+        let var = |r: usize, c: usize, d: usize| Self::variable(side, r, c, d);
+
+        let mut clauses = vec![];
+
+        // Each of the groups of (row, column) positions below must hold every
+        // digit exactly once.
+        let rows = (0..side).map(|r| (0..side).map(|c| (r, c)).collect_vec());
+        let columns = (0..side).map(|c| (0..side).map(|r| (r, c)).collect_vec());
+        let boxes = (0..box_side).cartesian_product(0..box_side).map(|(a, b)| {
+            (0..box_side)
+                .cartesian_product(0..box_side)
+                .map(|(v, h)| (a * box_side + v, b * box_side + h))
+                .collect_vec()
+        });
+        for unit in rows.chain(columns).chain(boxes) {
+            for d in 0..side {
+                clauses.push(unit.iter().map(|&(r, c)| var(r, c, d)).collect_vec());
+                for (&(r, c), &(rr, cc)) in unit.iter().tuple_combinations() {
+                    clauses.push(vec![-var(r, c, d), -var(rr, cc, d)]);
+                }
+            }
+        }
+
+        for (r, c) in (0..side).cartesian_product(0..side) {
+            clauses.push((0..side).map(|d| var(r, c, d)).collect_vec());
+            for (d, dd) in (0..side).tuple_combinations() {
+                clauses.push(vec![-var(r, c, d), -var(r, c, dd)]);
+            }
+
+            if let Some(digit) = sudoku.get(r, c).value() {
+                clauses.push(vec![var(r, c, digit - 1)]);
+            }
+
+            for d in (0..side).filter(|&d| !constraints.allows(r, c, d + 1, side)) {
+                clauses.push(vec![-var(r, c, d)]);
+            }
+
+            if constraints.non_consecutive {
+                let neighbours = [(r + 1, c), (r, c + 1)];
+                for (rr, cc) in neighbours.into_iter().filter(|&(rr, cc)| rr.max(cc) < side) {
+                    for d in 0..side - 1 {
+                        clauses.push(vec![-var(r, c, d), -var(rr, cc, d + 1)]);
+                        clauses.push(vec![-var(r, c, d + 1), -var(rr, cc, d)]);
+                    }
+                }
+            }
+        }
+
+        Cnf {
+            variables: side * side * side,
+            clauses,
+        }
+    }
+
+    /// The variable that is true if `digit`, counting from 0, is placed at
+    /// the given cell of a board of `side` by `side` cells.
+    pub fn variable(side: usize, row: usize, column: usize, digit: usize) -> Literal {
+        ((row * side + column) * side + digit + 1) as Literal
+    }
+}
@@ -0,0 +1,190 @@
+use crate::cnf::{Cnf, Literal};
+
+pub enum SatResult {
+    /// The value of every variable, indexed from 0 (i.e., variable 1 is at
+    /// index 0).
+    Satisfiable(Vec<bool>),
+    Unsatisfiable,
+}
+
+/// Decides the satisfiability of a CNF formula with DPLL: unit propagation
+/// (using two watched literals per clause) and chronological backtracking.
+pub fn solve(cnf: &Cnf) -> SatResult {
+    let mut state = match State::new(cnf) {
+        Some(state) => state,
+        None => return SatResult::Unsatisfiable,
+    };
+
+    // Decisions taken so far, and whether the opposite branch has already been
+    // tried.
+    let mut decisions: Vec<(usize, bool)> = vec![];
+
+    loop {
+        if state.propagate() {
+            match state.unassigned() {
+                None => {
+                    let model = state.values.iter().map(|v| *v == Some(true)).collect();
+                    return SatResult::Satisfiable(model);
+                }
+                Some(var) => {
+                    let lit = positive(var);
+                    decisions.push((lit, false));
+                    state.levels.push(state.trail.len());
+                    state.assign(lit);
+                }
+            }
+        } else {
+            // Conflict; undo decisions up to the latest one whose opposite
+            // has not been tried, and try it.
+            loop {
+                let (lit, flipped) = match decisions.pop() {
+                    Some(decision) => decision,
+                    None => return SatResult::Unsatisfiable,
+                };
+                let level = state.levels.pop().expect("One level per decision");
+                state.undo_until(level);
+
+                if !flipped {
+                    decisions.push((negate(lit), true));
+                    state.levels.push(state.trail.len());
+                    state.assign(negate(lit));
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Literals are stored internally as 2 * variable + (1 if negated), with
+/// variables numbered from 0.
+fn internal(lit: Literal) -> usize {
+    let var = lit.unsigned_abs() as usize - 1;
+    2 * var + (lit < 0) as usize
+}
+
+fn positive(var: usize) -> usize {
+    2 * var
+}
+
+fn negate(lit: usize) -> usize {
+    lit ^ 1
+}
+
+struct State {
+    clauses: Vec<Vec<usize>>,
+    /// For each literal, the clauses in which it is one of the two watched
+    /// literals (the first two of the clause).
+    watches: Vec<Vec<usize>>,
+    values: Vec<Option<bool>>,
+    /// Assigned literals, in assignment order.
+    trail: Vec<usize>,
+    /// Position in the trail where each decision level begins.
+    levels: Vec<usize>,
+    /// Position in the trail up to which propagation has been done.
+    propagated: usize,
+}
+
+impl State {
+    /// Returns `None` if the formula is trivially unsatisfiable.
+    fn new(cnf: &Cnf) -> Option<Self> {
+        let mut state = State {
+            clauses: vec![],
+            watches: vec![vec![]; 2 * cnf.variables],
+            values: vec![None; cnf.variables],
+            trail: vec![],
+            levels: vec![],
+            propagated: 0,
+        };
+
+        for clause in &cnf.clauses {
+            let clause: Vec<usize> = clause.iter().map(|&lit| internal(lit)).collect();
+            match clause.len() {
+                0 => return None,
+                1 => match state.value(clause[0]) {
+                    Some(false) => return None,
+                    Some(true) => {}
+                    None => state.assign(clause[0]),
+                },
+                _ => {
+                    let index = state.clauses.len();
+                    state.watches[clause[0]].push(index);
+                    state.watches[clause[1]].push(index);
+                    state.clauses.push(clause);
+                }
+            }
+        }
+
+        Some(state)
+    }
+
+    fn value(&self, lit: usize) -> Option<bool> {
+        self.values[lit / 2].map(|v| v != (lit % 2 == 1))
+    }
+
+    fn assign(&mut self, lit: usize) {
+        self.values[lit / 2] = Some(lit.is_multiple_of(2));
+        self.trail.push(lit);
+    }
+
+    fn undo_until(&mut self, length: usize) {
+        for lit in self.trail.drain(length..) {
+            self.values[lit / 2] = None;
+        }
+        self.propagated = self.propagated.min(length);
+    }
+
+    fn unassigned(&self) -> Option<usize> {
+        self.values.iter().position(|v| v.is_none())
+    }
+
+    /// Unit propagation. Returns `false` on conflict.
+    fn propagate(&mut self) -> bool {
+        while self.propagated < self.trail.len() {
+            let false_lit = negate(self.trail[self.propagated]);
+            self.propagated += 1;
+
+            let watching = std::mem::take(&mut self.watches[false_lit]);
+            let mut kept = Vec::with_capacity(watching.len());
+            let mut conflict = false;
+
+            for (i, &index) in watching.iter().enumerate() {
+                if conflict {
+                    kept.extend_from_slice(&watching[i..]);
+                    break;
+                }
+
+                // Keep the false literal in second place
+                let clause = &mut self.clauses[index];
+                if clause[0] == false_lit {
+                    clause.swap(0, 1);
+                }
+                let other = clause[0];
+
+                // Look for a new literal to watch
+                let replacement = (2..clause.len()).find(|&k| {
+                    let lit = clause[k];
+                    self.values[lit / 2] != Some(lit % 2 == 1)
+                });
+                if let Some(k) = replacement {
+                    clause.swap(1, k);
+                    let new_watch = clause[1];
+                    self.watches[new_watch].push(index);
+                    continue;
+                }
+
+                kept.push(index);
+                match self.value(other) {
+                    Some(true) => {}
+                    Some(false) => conflict = true,
+                    None => self.assign(other),
+                }
+            }
+
+            self.watches[false_lit] = kept;
+            if conflict {
+                return false;
+            }
+        }
+        true
+    }
+}
@@ -0,0 +1,14 @@
+//! SAT-based solver for sudoku, as a library.
+//!
+//! The board is encoded as a CNF formula (see [`cnf`]) and solved with a small
+//! embedded DPLL engine (see [`dpll`]). Variant constraints are either encoded
+//! in the formula, or enforced by adding clauses as their violations are found
+//! (see [`solve`]). The `sat` binary is a thin command line wrapper around
+//! [`solve`] and [`check_unique`].
+
+pub mod cnf;
+pub mod dpll;
+pub mod solver;
+
+pub use solver::{check_unique, solve, Sat, Uniqueness};
+pub use sudoku::SolveError;
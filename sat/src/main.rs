@@ -0,0 +1,180 @@
+use sat::Uniqueness;
+use std::path::PathBuf;
+use sudoku::constraints::Constraints;
+use sudoku::*;
+
+const HEADER: &str = r#"SAT-based solver for sudoku
+"#;
+
+const USAGE: &str = r#"
+Usage:
+    sat [--unique] [--constraints <file>] [--non-consecutive] <input file>
+    sat --help
+
+Options:
+    --unique            Also prove or disprove that the solution is unique.
+    --constraints <file>
+                        Read variant constraints from <file>, which the
+                        solution must follow too.
+    --non-consecutive   Forbid consecutive digits in orthogonally adjacent
+                        cells.
+    --help              Print help information.
+"#;
+
+const LONG_HELP: &str = concat!(
+    r#"
+An input file of "-" denotes the input data should be read from the standard
+input. The input file is expected to be in .sudoku format, and the constraints
+file in .constraints format.
+
+The input is encoded as a boolean formula in conjunctive normal form, with one
+variable per (row, column, digit), and its satisfiability decided with the DPLL
+algorithm. The non-consecutive rule, and the digits that the constraints rule
+out of single cells (such as even digits in odd cells), are part of the
+formula. Whenever a solution of the formula breaks one of the other constraints,
+a clause ruling out those digits in the cells of the constraint is added, and
+the formula is solved again.
+
+The program prints to stdout a single line with the outcome, followed by a
+solution in .sudoku format, if there is one, and exits with code 0. Other
+errors are reported to stderr, and cause the program to exit with code 1.
+The outcome can be
+
+    SATISFIABLE     The .sudoku below is a solution to the given input.
+    UNSATISFIABLE   The input has no solution.
+
+With --unique, instead of SATISFIABLE, the outcome is one of
+
+    UNIQUE          The .sudoku below is the only solution to the input.
+    MULTIPLE        The input has more than one solution; two of them are
+                    given below, separated by an empty line.
+"#,
+    include_str!("../../FORMATTING.txt"),
+    "\n\n",
+    include_str!("../../CONSTRAINTS.txt")
+);
+
+fn main() {
+    let mut args = std::env::args().skip(1); // Skip the filename
+
+    let mut input: Option<Result<Sudoku, String>> = None;
+    let mut check_unique = false;
+    let mut constraints_path: Option<PathBuf> = None;
+    let mut non_consecutive = false;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--help" => {
+                println!("{}", HEADER);
+                println!("{}", USAGE);
+                println!("{}", LONG_HELP);
+                std::process::exit(0);
+            }
+            "--unique" => check_unique = true,
+            "--non-consecutive" => non_consecutive = true,
+            "--constraints" => match args.next() {
+                Some(path) => constraints_path = Some(PathBuf::from(path)),
+                None => {
+                    eprintln!("{}", USAGE);
+                    std::process::exit(1);
+                }
+            },
+            _ if input.is_some() => {
+                eprintln!("Too many arguments!");
+                eprintln!("{}", USAGE);
+                std::process::exit(1);
+            }
+            "-" => input = Some(parsing::sudoku::parse(std::io::stdin())),
+            path => {
+                let path = PathBuf::from(path);
+                let path_as_str = path.clone().to_string_lossy().to_string();
+                if !path.exists() {
+                    eprintln!("{} does not exist.", &path_as_str);
+                    std::process::exit(1);
+                }
+
+                let reader = std::fs::File::open(path);
+                if let Err(e) = reader {
+                    eprintln!(
+                        "Could not open {} for reading.\nWith error {}",
+                        &path_as_str, e
+                    );
+                    std::process::exit(1);
+                }
+                input = Some(parsing::sudoku::parse(reader.unwrap()));
+            }
+        }
+    }
+
+    let mut input = match input {
+        Some(Ok(input)) => input,
+        Some(Err(e)) => {
+            eprintln!("Input board malformed.");
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        None => {
+            eprintln!("No sudoku file specified.");
+            eprintln!("{}", USAGE);
+            std::process::exit(1);
+        }
+    };
+
+    let constraints = read_constraints(constraints_path, input.side(), non_consecutive);
+
+    if check_unique {
+        match sat::check_unique(&input, &constraints) {
+            Uniqueness::None => println!("UNSATISFIABLE"),
+            Uniqueness::Unique(solution) => {
+                println!("UNIQUE");
+                println!("{}", solution);
+            }
+            Uniqueness::Multiple(first, second) => {
+                println!("MULTIPLE");
+                println!("{}", first);
+                println!();
+                println!("{}", second);
+            }
+        }
+        std::process::exit(0);
+    }
+
+    match sat::solve(&mut input, &constraints) {
+        Ok(()) => {
+            println!("SATISFIABLE");
+            println!("{}", input);
+        }
+        Err(_) => println!("UNSATISFIABLE"),
+    }
+}
+
+/// The constraints in the file at `path`, if any, for a board of `side` by
+/// `side` cells.
+fn read_constraints(path: Option<PathBuf>, side: usize, non_consecutive: bool) -> Constraints {
+    let mut constraints = match path {
+        Some(path) => {
+            let path_as_str = path.to_string_lossy().to_string();
+            let reader = match std::fs::File::open(path) {
+                Ok(reader) => reader,
+                Err(e) => {
+                    eprintln!(
+                        "Could not open {} for reading.\nWith error {}",
+                        &path_as_str, e
+                    );
+                    std::process::exit(1);
+                }
+            };
+            match parsing::constraints::parse(reader, side) {
+                Ok(constraints) => constraints,
+                Err(e) => {
+                    eprintln!("Constraints file malformed.");
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        None => Constraints::new(),
+    };
+    constraints.non_consecutive = non_consecutive;
+    constraints
+}
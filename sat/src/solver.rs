@@ -0,0 +1,112 @@
+use crate::cnf::{Cnf, Literal};
+use crate::dpll::{self, SatResult};
+use sudoku::constraints::Constraints;
+use sudoku::{SolveError, Solver, Sudoku, SudokuCell, SudokuCellValue};
+
+/// The SAT solver, as a [`Solver`], with the options of [`solve`].
+#[derive(Clone, Default)]
+pub struct Sat {
+    /// Variant constraints the solution must also follow.
+    pub constraints: Constraints,
+}
+
+impl Solver for Sat {
+    type Report = ();
+
+    fn solve(&self, sudoku: &mut Sudoku) -> Result<(), SolveError> {
+        solve(sudoku, &self.constraints)
+    }
+}
+
+pub enum Uniqueness {
+    /// The board has no solution.
+    None,
+    /// The board has exactly this solution.
+    Unique(Sudoku),
+    /// The board has at least these two solutions.
+    Multiple(Sudoku, Sudoku),
+}
+
+/// Solves the board in place, so that it also follows `constraints`. If there
+/// is no solution, the board is left untouched.
+///
+/// The rules that [`Cnf::for_sudoku`] does not encode are enforced lazily:
+/// whenever the formula has a model that breaks one of them, a clause ruling
+/// out the digits of the model in the cells involved in that rule is added, and
+/// the formula is solved again.
+pub fn solve(sudoku: &mut Sudoku, constraints: &Constraints) -> Result<(), SolveError> {
+    let mut cnf = Cnf::for_sudoku(sudoku, constraints);
+    match solve_lazily(&mut cnf, sudoku, constraints) {
+        Some((solution, _)) => {
+            *sudoku = solution;
+            Ok(())
+        }
+        None => Err(SolveError::Infeasible),
+    }
+}
+
+/// Decides whether the board has exactly one solution that follows
+/// `constraints`: after a first solution is found, a clause ruling out
+/// exactly that solution is added, and the formula is solved again. If it is
+/// then unsatisfiable, this is a proof that the first solution is unique.
+pub fn check_unique(sudoku: &Sudoku, constraints: &Constraints) -> Uniqueness {
+    let mut cnf = Cnf::for_sudoku(sudoku, constraints);
+
+    let (first_solution, first) = match solve_lazily(&mut cnf, sudoku, constraints) {
+        Some(solution) => solution,
+        None => return Uniqueness::None,
+    };
+
+    let blocking = first
+        .iter()
+        .enumerate()
+        .filter(|(_, &value)| value)
+        .map(|(var, _)| -((var + 1) as Literal))
+        .collect();
+    cnf.clauses.push(blocking);
+
+    match solve_lazily(&mut cnf, sudoku, constraints) {
+        Some((second_solution, _)) => Uniqueness::Multiple(first_solution, second_solution),
+        None => Uniqueness::Unique(first_solution),
+    }
+}
+
+/// A solution of the formula that follows every constraint, along with its
+/// model, if there is one. Clauses ruling out the models that break a
+/// constraint are added to the formula along the way.
+fn solve_lazily(
+    cnf: &mut Cnf,
+    sudoku: &Sudoku,
+    constraints: &Constraints,
+) -> Option<(Sudoku, Vec<bool>)> {
+    let side = sudoku.side();
+    loop {
+        let model = match dpll::solve(cnf) {
+            SatResult::Satisfiable(model) => model,
+            SatResult::Unsatisfiable => return None,
+        };
+        let mut solution = sudoku.clone();
+        set_from_model(&mut solution, &model);
+
+        let broken = match constraints.validate(&solution) {
+            Ok(()) => return Some((solution, model)),
+            Err(broken) => broken,
+        };
+        for constraint in broken {
+            let blocking = (0..side * side)
+                .map(|raw| (raw / side, raw % side))
+                .filter(|&(r, c)| constraint.involves(r, c))
+                .map(|(r, c)| -Cnf::variable(side, r, c, solution.get(r, c).unwrap() - 1))
+                .collect();
+            cnf.clauses.push(blocking);
+        }
+    }
+}
+
+fn set_from_model(sudoku: &mut Sudoku, model: &[bool]) {
+    let side = sudoku.side();
+    for (var, _) in model.iter().enumerate().filter(|(_, &value)| value) {
+        let (raw, d) = (var / side, var % side);
+        sudoku.set_raw(raw, SudokuCell::digit(d + 1));
+    }
+}
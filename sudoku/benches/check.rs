@@ -0,0 +1,64 @@
+//! Times the conflict detection of `sudoku::check` on solved boards, where
+//! every pair of peers has to be looked at, and on boards with a conflict.
+//!
+//! Run with `cargo bench -p sudoku`.
+
+use std::hint::black_box;
+use std::time::Instant;
+use sudoku::{check, Sudoku, SudokuCell};
+
+/// A solved board with boxes of `box_side` by `box_side`.
+fn solved(box_side: usize) -> Sudoku {
+    let side = box_side * box_side;
+    let mut sudoku = Sudoku::empty(side);
+    for r in 0..side {
+        for c in 0..side {
+            let digit = (box_side * (r % box_side) + r / box_side + c) % side + 1;
+            sudoku.set(r, c, SudokuCell::digit(digit));
+        }
+    }
+    sudoku
+}
+
+fn time(name: &str, iterations: u32, mut f: impl FnMut()) {
+    let started = Instant::now();
+    for _ in 0..iterations {
+        f();
+    }
+    let per_iteration = started.elapsed() / iterations;
+    println!("{:<40} {:>12?}", name, per_iteration);
+}
+
+fn main() {
+    for box_side in [3, 4, 5] {
+        let side = box_side * box_side;
+        let solved = solved(box_side);
+        assert!(!check::has_conflicts(&solved));
+
+        let mut conflicting = solved.clone();
+        let last = side * side - 1;
+        conflicting.set_raw(last, conflicting.get_raw(last - 1));
+
+        time(
+            &format!("conflicts, solved {}x{}", side, side),
+            1000,
+            || {
+                black_box(check::conflicts(black_box(&solved)));
+            },
+        );
+        time(
+            &format!("has_conflicts, solved {}x{}", side, side),
+            1000,
+            || {
+                black_box(check::has_conflicts(black_box(&solved)));
+            },
+        );
+        time(
+            &format!("conflicts, one conflict {}x{}", side, side),
+            1000,
+            || {
+                black_box(check::conflicts(black_box(&conflicting)));
+            },
+        );
+    }
+}
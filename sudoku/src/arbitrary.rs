@@ -0,0 +1,137 @@
+//! Strategies for generating boards with [proptest], available with the
+//! `proptest` feature.
+//!
+//! The boards are generated from a fixed solved board, by shuffling its
+//! digits, its bands and the rows within them, its stacks and the columns
+//! within them, and by transposing it, all of which keep it solved.
+
+use crate::{Sudoku, SudokuCell, SudokuCellValue};
+use proptest::prelude::*;
+
+/// Complete, valid boards with boxes of `box_side` by `box_side`.
+pub fn solved(box_side: usize) -> impl Strategy<Value = Sudoku> {
+    let side = box_side * box_side;
+    let permutation = |n: usize| Just((0..n).collect::<Vec<_>>()).prop_shuffle();
+    (
+        permutation(side),
+        permutation(box_side),
+        prop::collection::vec(permutation(box_side), box_side),
+        permutation(box_side),
+        prop::collection::vec(permutation(box_side), box_side),
+        any::<bool>(),
+    )
+        .prop_map(move |(digits, bands, rows, stacks, columns, transpose)| {
+            let mut sudoku = Sudoku::empty(side);
+            for r in 0..side {
+                for c in 0..side {
+                    // The cell of the fixed board that ends up at (r, c)
+                    let rr = bands[r / box_side] * box_side + rows[r / box_side][r % box_side];
+                    let cc = stacks[c / box_side] * box_side + columns[c / box_side][c % box_side];
+                    let (rr, cc) = if transpose { (cc, rr) } else { (rr, cc) };
+                    let digit = (box_side * (rr % box_side) + rr / box_side + cc) % side;
+                    sudoku.set(r, c, SudokuCell::digit(digits[digit] + 1));
+                }
+            }
+            sudoku
+        })
+}
+
+/// Boards with no conflicts, but with some cells (possibly none, or all)
+/// left empty; these need not have a unique solution.
+pub fn partial(box_side: usize) -> impl Strategy<Value = Sudoku> {
+    let side = box_side * box_side;
+    (
+        solved(box_side),
+        prop::collection::vec(any::<bool>(), side * side),
+    )
+        .prop_map(|(mut sudoku, empty)| {
+            for (raw, _) in empty.into_iter().enumerate().filter(|&(_, empty)| empty) {
+                sudoku.set_raw(raw, SudokuCell::Empty);
+            }
+            sudoku
+        })
+}
+
+/// Boards, possibly with empty cells, with at least one pair of cells in the
+/// same row that hold the same digit.
+pub fn invalid(box_side: usize) -> impl Strategy<Value = Sudoku> {
+    let side = box_side * box_side;
+    (
+        solved(box_side),
+        0..side * side,
+        1..side,
+        prop::collection::vec(any::<bool>(), side * side),
+    )
+        .prop_map(move |(mut sudoku, raw, offset, empty)| {
+            let (r, c) = (raw / side, raw % side);
+            let twin = r * side + (c + offset) % side;
+            sudoku.set_raw(twin, sudoku.get_raw(raw));
+            for (other, _) in empty.into_iter().enumerate().filter(|&(_, empty)| empty) {
+                if other != raw && other != twin {
+                    sudoku.set_raw(other, SudokuCell::Empty);
+                }
+            }
+            debug_assert!(sudoku.get_raw(raw).value().is_some());
+            sudoku
+        })
+}
+
+/// Generates 4x4 and 9x9 boards, which are complete and valid, partial and
+/// valid, or invalid, in equal proportion.
+impl Arbitrary for Sudoku {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Sudoku>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (2_usize..=3)
+            .prop_flat_map(|box_side| {
+                prop_oneof![
+                    solved(box_side).boxed(),
+                    partial(box_side).boxed(),
+                    invalid(box_side).boxed(),
+                ]
+            })
+            .boxed()
+    }
+}
+
+#[cfg(all(test, feature = "proptest"))]
+mod tests {
+    use super::*;
+    use crate::parsing;
+    use crate::verify_solution;
+
+    proptest! {
+        #[test]
+        fn printed_boards_parse_back(sudoku in any::<Sudoku>()) {
+            let parsed = parsing::sudoku::parse(sudoku.to_string().as_bytes()).unwrap();
+            prop_assert_eq!(parsed.side, sudoku.side);
+            prop_assert_eq!(parsed.values, sudoku.values);
+        }
+
+        #[test]
+        fn sdm_lines_parse_back(sudoku in (2_usize..=4).prop_flat_map(partial)) {
+            let parsed = parsing::sdm::parse(parsing::sdm::write(&sudoku).as_bytes()).unwrap();
+            prop_assert_eq!(parsed.len(), 1);
+            prop_assert_eq!(&parsed[0].values, &sudoku.values);
+        }
+
+        #[test]
+        fn solved_boards_solve_their_partial_boards(
+            (solution, empty) in solved(3).prop_flat_map(|solution| {
+                (Just(solution), prop::collection::vec(any::<bool>(), 81))
+            })
+        ) {
+            let mut puzzle = solution.clone();
+            for (raw, _) in empty.into_iter().enumerate().filter(|&(_, empty)| empty) {
+                puzzle.set_raw(raw, SudokuCell::Empty);
+            }
+            prop_assert_eq!(verify_solution(&puzzle, &solution), Ok(()));
+        }
+
+        #[test]
+        fn invalid_boards_are_not_solutions(sudoku in invalid(3)) {
+            prop_assert!(verify_solution(&Sudoku::empty(9), &sudoku).is_err());
+        }
+    }
+}
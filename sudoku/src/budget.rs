@@ -0,0 +1,66 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Limits on how long a solver may run before it gives up, shared by the
+/// solvers, which fail once it is spent.
+///
+/// What a step is depends on the solver: a digit tried by the backtracking
+/// search, a move proposed by the annealer, or a sweep over the constraints
+/// of the projection solver. The default budget is unlimited.
+#[derive(Debug, Clone, Default)]
+pub struct Budget {
+    /// Maximum wall-clock time.
+    pub max_time: Option<Duration>,
+    /// Maximum number of steps.
+    pub max_steps: Option<usize>,
+    /// Flag that, when set from elsewhere, cancels the solver.
+    pub cancel: Option<Arc<AtomicBool>>,
+}
+
+impl Budget {
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_time(mut self, max_time: Duration) -> Self {
+        self.max_time = Some(max_time);
+        self
+    }
+
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = Some(max_steps);
+        self
+    }
+
+    pub fn with_cancel_flag(mut self, cancel: Arc<AtomicBool>) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    /// Whether the cancel flag has been set.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel
+            .as_ref()
+            .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+    }
+
+    /// Whether the budget is spent, given when the solver started and how
+    /// many steps it has taken so far, or the cancel flag has been set.
+    pub fn is_exhausted(&self, started: &Instant, steps: usize) -> bool {
+        if self.is_cancelled() {
+            return true;
+        }
+        if let Some(max_steps) = self.max_steps {
+            if steps >= max_steps {
+                return true;
+            }
+        }
+        if let Some(max_time) = self.max_time {
+            if started.elapsed() >= max_time {
+                return true;
+            }
+        }
+        false
+    }
+}
@@ -0,0 +1,74 @@
+//! Detection of conflicts, i.e., equal digits in the same row, column or
+//! box.
+
+use crate::{Sudoku, SudokuCellValue, UnitKind};
+
+/// Two cells in the same row, column or box that hold the same digit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Conflict {
+    /// The raw indices of the two cells, the lower one first.
+    pub cells: (usize, usize),
+    pub value: usize,
+    /// The unit the two cells are found in, checking rows first, then
+    /// columns, then boxes; two cells in the same row and box are a row
+    /// conflict.
+    pub unit: UnitKind,
+}
+
+/// Every conflict on the board, ordered by their first cell. Empty cells
+/// conflict with nothing.
+pub fn conflicts(sudoku: &Sudoku) -> Vec<Conflict> {
+    let mut conflicts = vec![];
+    find_conflicts(sudoku, |conflict| {
+        conflicts.push(conflict);
+        false
+    });
+    conflicts
+}
+
+/// Whether there is any conflict on the board, which is faster to find out
+/// than listing them all.
+pub fn has_conflicts(sudoku: &Sudoku) -> bool {
+    find_conflicts(sudoku, |_| true)
+}
+
+/// Calls `on_conflict` with each conflict, until it returns `true`; returns
+/// whether it did.
+fn find_conflicts(sudoku: &Sudoku, mut on_conflict: impl FnMut(Conflict) -> bool) -> bool {
+    let side = sudoku.side();
+    let box_side = sudoku.box_side();
+
+    for raw in 0..side * side {
+        let value = match sudoku.get_raw(raw).value() {
+            Some(value) => value,
+            None => continue,
+        };
+        let (r, c) = (raw / side, raw % side);
+
+        // Only look at the peers after this cell, so that each pair is only
+        // seen once: the rest of its row, the rest of its column, and the
+        // cells of its box in the rows below, that are not in its column.
+        let row = (c + 1..side).map(|cc| (r * side + cc, UnitKind::Row));
+        let column = (r + 1..side).map(|rr| (rr * side + c, UnitKind::Column));
+        let box_left = (c / box_side) * box_side;
+        let box_below = (r + 1..(r / box_side + 1) * box_side)
+            .flat_map(|rr| (box_left..box_left + box_side).map(move |cc| (rr, cc)))
+            .filter(|&(_, cc)| cc != c)
+            .map(|(rr, cc)| (rr * side + cc, UnitKind::Box));
+
+        for (peer, unit) in row.chain(column).chain(box_below) {
+            if sudoku.get_raw(peer).value() == Some(value) {
+                let conflict = Conflict {
+                    cells: (raw, peer),
+                    value,
+                    unit,
+                };
+                if on_conflict(conflict) {
+                    return true;
+                }
+            }
+        }
+    }
+
+    false
+}
@@ -0,0 +1,95 @@
+use super::{fmt_cell, sum, Constraint};
+use crate::{Sudoku, SudokuCellValue};
+use std::fmt::Display;
+
+/// The direction in which a [`LittleKiller`] diagonal runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Diagonal {
+    DownRight,
+    DownLeft,
+    UpRight,
+    UpLeft,
+}
+
+impl Diagonal {
+    fn step(self) -> (isize, isize) {
+        match self {
+            Diagonal::DownRight => (1, 1),
+            Diagonal::DownLeft => (1, -1),
+            Diagonal::UpRight => (-1, 1),
+            Diagonal::UpLeft => (-1, -1),
+        }
+    }
+}
+
+impl Display for Diagonal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Diagonal::DownRight => write!(f, "down-right"),
+            Diagonal::DownLeft => write!(f, "down-left"),
+            Diagonal::UpRight => write!(f, "up-right"),
+            Diagonal::UpLeft => write!(f, "up-left"),
+        }
+    }
+}
+
+/// A little killer clue: the digits along a diagonal, from a cell on the
+/// edge of the board to the opposite edge, add up to `sum`. Digits may repeat
+/// along the diagonal, as long as the usual rules allow it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LittleKiller {
+    start: (usize, usize),
+    direction: Diagonal,
+    sum: usize,
+    cells: Vec<(usize, usize)>,
+}
+
+impl LittleKiller {
+    /// The clue for the diagonal starting at `start` and running in
+    /// `direction` until the edge of a board of `side` by `side` cells.
+    pub fn new(start: (usize, usize), direction: Diagonal, sum: usize, side: usize) -> Self {
+        let (dr, dc) = direction.step();
+        let cells = std::iter::successors(Some(start), |&(r, c)| {
+            let next = (r.checked_add_signed(dr)?, c.checked_add_signed(dc)?);
+            Some(next).filter(|&(r, c)| r < side && c < side)
+        })
+        .collect();
+        LittleKiller {
+            start,
+            direction,
+            sum,
+            cells,
+        }
+    }
+
+    pub fn sum(&self) -> usize {
+        self.sum
+    }
+
+    pub fn cells(&self) -> &[(usize, usize)] {
+        &self.cells
+    }
+}
+
+impl Constraint for LittleKiller {
+    fn is_broken(&self, sudoku: &Sudoku) -> bool {
+        let values = self
+            .cells
+            .iter()
+            .map(|&(r, c)| sudoku.get(r, c).value())
+            .collect::<Vec<_>>();
+        sum::is_unreachable(&values, self.sum, 1..=sudoku.side(), false)
+    }
+
+    fn involves(&self, row: usize, column: usize) -> bool {
+        self.cells.contains(&(row, column))
+    }
+}
+
+impl Display for LittleKiller {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "little-killer ")?;
+        fmt_cell(f, self.start)?;
+        write!(f, " {} {}", self.direction, self.sum)
+    }
+}
@@ -0,0 +1,124 @@
+//! Variant constraints, i.e., rules that a board must follow on top of the
+//! usual row, column and box ones.
+//!
+//! Cells are given as `(row, column)` pairs, counting from 0.
+
+use crate::Sudoku;
+use std::fmt::{Debug, Display};
+use std::sync::Arc;
+
+mod little_killer;
+mod non_consecutive;
+mod parity;
+mod sandwich;
+mod sum;
+mod thermo;
+
+pub use little_killer::{Diagonal, LittleKiller};
+pub use non_consecutive::NonConsecutive;
+pub use parity::{Parity, ParityCells};
+pub use sandwich::{Line, Sandwich};
+pub use thermo::Thermo;
+
+/// A rule on the digits of a board.
+pub trait Constraint: Debug + Display + Send + Sync {
+    /// Whether the board, which may have empty cells, already breaks the
+    /// rule, no matter how its empty cells are filled in.
+    fn is_broken(&self, sudoku: &Sudoku) -> bool;
+
+    /// Whether the rule concerns the given cell, i.e., whether changing the
+    /// cell can change whether the rule is broken.
+    fn involves(&self, row: usize, column: usize) -> bool;
+
+    /// Whether the rule is broken, given that it was not before the given
+    /// cell was changed. Constraints that can tell by only looking around
+    /// the cell should override this.
+    fn is_broken_at(&self, sudoku: &Sudoku, row: usize, column: usize) -> bool {
+        self.involves(row, column) && self.is_broken(sudoku)
+    }
+
+    /// Whether the rule allows `digit` in the given cell at all, regardless
+    /// of the rest of the board.
+    fn allows(&self, _row: usize, _column: usize, _digit: usize, _side: usize) -> bool {
+        true
+    }
+
+    /// How many times the rule is broken on a filled board, as counted
+    /// towards the energy of a stochastic solver; by default, 1 if it is
+    /// broken at all. Rules that can be broken in many places at once should
+    /// count each of them, so that fixing any one of them counts as progress.
+    fn violations(&self, sudoku: &Sudoku) -> usize {
+        usize::from(self.is_broken(sudoku))
+    }
+}
+
+/// A set of variant constraints on a board.
+#[derive(Debug, Clone, Default)]
+pub struct Constraints {
+    constraints: Vec<Arc<dyn Constraint>>,
+    /// Whether the [`NonConsecutive`] rule applies too.
+    pub non_consecutive: bool,
+}
+
+impl Constraints {
+    /// No constraints beyond the usual ones.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push<C: Constraint + 'static>(&mut self, constraint: C) {
+        self.constraints.push(Arc::new(constraint));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.constraints.is_empty() && !self.non_consecutive
+    }
+
+    /// Every constraint, including the global rules that are turned on.
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Constraint> {
+        let non_consecutive = self
+            .non_consecutive
+            .then_some(&NonConsecutive as &dyn Constraint);
+        self.constraints
+            .iter()
+            .map(|constraint| constraint.as_ref())
+            .chain(non_consecutive)
+    }
+
+    /// Checks the board, which may have empty cells, against every
+    /// constraint, and lists those it already breaks.
+    pub fn validate(&self, sudoku: &Sudoku) -> Result<(), Vec<&dyn Constraint>> {
+        let broken = self
+            .iter()
+            .filter(|constraint| constraint.is_broken(sudoku))
+            .collect::<Vec<_>>();
+        if broken.is_empty() {
+            Ok(())
+        } else {
+            Err(broken)
+        }
+    }
+
+    /// Whether the board breaks any constraint, given that it broke none
+    /// before the cell at raw index `raw` was changed.
+    pub fn is_broken_at(&self, sudoku: &Sudoku, raw: usize) -> bool {
+        let (row, column) = (raw / sudoku.side(), raw % sudoku.side());
+        self.iter()
+            .any(|constraint| constraint.is_broken_at(sudoku, row, column))
+    }
+
+    /// Whether every constraint allows `digit` in the given cell.
+    pub fn allows(&self, row: usize, column: usize, digit: usize, side: usize) -> bool {
+        self.iter()
+            .all(|constraint| constraint.allows(row, column, digit, side))
+    }
+}
+
+/// Writes a cell as `r<row>c<column>`, counting from 1, as in the
+/// `.constraints` format.
+pub(crate) fn fmt_cell(
+    f: &mut std::fmt::Formatter<'_>,
+    (row, column): (usize, usize),
+) -> std::fmt::Result {
+    write!(f, "r{}c{}", row + 1, column + 1)
+}
@@ -0,0 +1,72 @@
+use super::Constraint;
+use crate::{Sudoku, SudokuCellValue};
+use std::fmt::Display;
+
+/// The non-consecutive rule: no two orthogonally adjacent cells hold
+/// consecutive digits. It applies to the whole board, and is turned on with
+/// [`Constraints::non_consecutive`](super::Constraints::non_consecutive).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NonConsecutive;
+
+impl NonConsecutive {
+    /// Whether the given cell holds a digit consecutive to one of its
+    /// orthogonal neighbours.
+    fn is_broken_around(&self, sudoku: &Sudoku, row: usize, column: usize) -> bool {
+        let side = sudoku.side();
+        let value = match sudoku.get(row, column).value() {
+            Some(value) => value,
+            None => return false,
+        };
+        let neighbours = [
+            (row.wrapping_sub(1), column),
+            (row + 1, column),
+            (row, column.wrapping_sub(1)),
+            (row, column + 1),
+        ];
+        neighbours
+            .into_iter()
+            .filter(|&(r, c)| r < side && c < side)
+            .filter_map(|(r, c)| sudoku.get(r, c).value())
+            .any(|other| value.abs_diff(other) == 1)
+    }
+}
+
+impl Constraint for NonConsecutive {
+    fn is_broken(&self, sudoku: &Sudoku) -> bool {
+        let side = sudoku.side();
+        (0..side * side).any(|raw| self.is_broken_around(sudoku, raw / side, raw % side))
+    }
+
+    fn is_broken_at(&self, sudoku: &Sudoku, row: usize, column: usize) -> bool {
+        self.is_broken_around(sudoku, row, column)
+    }
+
+    fn involves(&self, _row: usize, _column: usize) -> bool {
+        true
+    }
+
+    /// The number of pairs of orthogonally adjacent cells that hold
+    /// consecutive digits.
+    fn violations(&self, sudoku: &Sudoku) -> usize {
+        let side = sudoku.side();
+        let digit = |r: usize, c: usize| sudoku.get(r, c).value();
+        let consecutive = |a: Option<usize>, b: Option<usize>| match (a, b) {
+            (Some(a), Some(b)) => a.abs_diff(b) == 1,
+            _ => false,
+        };
+        (0..side)
+            .flat_map(|r| (0..side).map(move |c| (r, c)))
+            .map(|(r, c)| {
+                let right = c + 1 < side && consecutive(digit(r, c), digit(r, c + 1));
+                let down = r + 1 < side && consecutive(digit(r, c), digit(r + 1, c));
+                usize::from(right) + usize::from(down)
+            })
+            .sum()
+    }
+}
+
+impl Display for NonConsecutive {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "non-consecutive")
+    }
+}
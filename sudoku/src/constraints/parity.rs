@@ -0,0 +1,66 @@
+use super::{fmt_cell, Constraint};
+use crate::{Sudoku, SudokuCellValue};
+use std::fmt::Display;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Parity {
+    Even,
+    Odd,
+}
+
+impl Parity {
+    pub fn of(digit: usize) -> Self {
+        if digit.is_multiple_of(2) {
+            Parity::Even
+        } else {
+            Parity::Odd
+        }
+    }
+}
+
+/// Cells that may only hold even, or only odd, digits; usually shaded as
+/// squares and circles, respectively.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParityCells {
+    pub parity: Parity,
+    pub cells: Vec<(usize, usize)>,
+}
+
+impl ParityCells {
+    pub fn new(parity: Parity, cells: Vec<(usize, usize)>) -> Self {
+        ParityCells { parity, cells }
+    }
+}
+
+impl Constraint for ParityCells {
+    fn is_broken(&self, sudoku: &Sudoku) -> bool {
+        self.cells
+            .iter()
+            .any(|&(r, c)| match sudoku.get(r, c).value() {
+                Some(value) => Parity::of(value) != self.parity,
+                None => false,
+            })
+    }
+
+    fn involves(&self, row: usize, column: usize) -> bool {
+        self.cells.contains(&(row, column))
+    }
+
+    fn allows(&self, row: usize, column: usize, digit: usize, _side: usize) -> bool {
+        !self.involves(row, column) || Parity::of(digit) == self.parity
+    }
+}
+
+impl Display for ParityCells {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.parity {
+            Parity::Even => write!(f, "even")?,
+            Parity::Odd => write!(f, "odd")?,
+        }
+        for &cell in &self.cells {
+            write!(f, " ")?;
+            fmt_cell(f, cell)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,77 @@
+use super::{sum, Constraint};
+use crate::{Sudoku, SudokuCellValue};
+use std::fmt::Display;
+
+/// Whether a [`Sandwich`] clue is on a row or a column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Line {
+    Row,
+    Column,
+}
+
+/// A sandwich clue: the digits between the 1 and the largest digit of a
+/// row or column add up to `sum`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sandwich {
+    pub line: Line,
+    /// Which row or column, counting from 0.
+    pub index: usize,
+    pub sum: usize,
+}
+
+impl Sandwich {
+    pub fn new(line: Line, index: usize, sum: usize) -> Self {
+        Sandwich { line, index, sum }
+    }
+
+    /// The cells of the row or column, in order.
+    fn cells(&self, side: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..side).map(move |i| match self.line {
+            Line::Row => (self.index, i),
+            Line::Column => (i, self.index),
+        })
+    }
+}
+
+impl Constraint for Sandwich {
+    fn is_broken(&self, sudoku: &Sudoku) -> bool {
+        let side = sudoku.side();
+        let values = self
+            .cells(side)
+            .map(|(r, c)| sudoku.get(r, c).value())
+            .collect::<Vec<_>>();
+
+        // The digits between the crusts are distinct, and neither 1 nor the
+        // largest digit.
+        let fillings = 2..=side - 1;
+
+        let crusts = (
+            values.iter().position(|&v| v == Some(1)),
+            values.iter().position(|&v| v == Some(side)),
+        );
+        let (left, right) = match crusts {
+            (Some(a), Some(b)) => (a.min(b), a.max(b)),
+            // Without both crusts the filling could be any length.
+            _ => return self.sum > fillings.sum::<usize>(),
+        };
+
+        sum::is_unreachable(&values[left + 1..right], self.sum, fillings, true)
+    }
+
+    fn involves(&self, row: usize, column: usize) -> bool {
+        match self.line {
+            Line::Row => row == self.index,
+            Line::Column => column == self.index,
+        }
+    }
+}
+
+impl Display for Sandwich {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let line = match self.line {
+            Line::Row => "row",
+            Line::Column => "column",
+        };
+        write!(f, "sandwich {} {} {}", line, self.index + 1, self.sum)
+    }
+}
@@ -0,0 +1,27 @@
+//! Bounds on sums of digits, for constraints that give the sum of some
+//! cells.
+
+use std::ops::RangeInclusive;
+
+/// Whether cells holding `values`, of which the empty ones can take any of
+/// `digits`, can no longer add up to `total`. If the digits must be
+/// `distinct`, the empty cells are bounded by the least and greatest sums
+/// of distinct digits, which prunes sooner.
+pub(crate) fn is_unreachable(
+    values: &[Option<usize>],
+    total: usize,
+    digits: RangeInclusive<usize>,
+    distinct: bool,
+) -> bool {
+    let known = values.iter().flatten().sum::<usize>();
+    let empty = values.iter().filter(|v| v.is_none()).count();
+    let (least, greatest) = if distinct {
+        (
+            digits.clone().take(empty).sum::<usize>(),
+            digits.rev().take(empty).sum::<usize>(),
+        )
+    } else {
+        (empty * digits.start(), empty * digits.end())
+    };
+    known + least > total || known + greatest < total
+}
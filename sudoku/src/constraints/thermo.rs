@@ -0,0 +1,84 @@
+use super::{fmt_cell, Constraint};
+use crate::{Sudoku, SudokuCellValue};
+use std::fmt::Display;
+
+/// A thermometer: the digits strictly increase along a path of cells,
+/// starting from the bulb.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Thermo {
+    cells: Vec<(usize, usize)>,
+}
+
+impl Thermo {
+    /// A thermometer along `cells`, the bulb first.
+    ///
+    /// # Panics
+    ///
+    /// If there are fewer than two cells, or two consecutive cells of the
+    /// path do not touch, orthogonally or diagonally.
+    pub fn new(cells: Vec<(usize, usize)>) -> Self {
+        assert!(cells.len() >= 2, "A thermometer needs at least two cells.");
+        for pair in cells.windows(2) {
+            let ((r, c), (rr, cc)) = (pair[0], pair[1]);
+            assert!(
+                (r, c) != (rr, cc) && r.abs_diff(rr) <= 1 && c.abs_diff(cc) <= 1,
+                "Consecutive cells of a thermometer must touch."
+            );
+        }
+        Thermo { cells }
+    }
+
+    pub fn cells(&self) -> &[(usize, usize)] {
+        &self.cells
+    }
+}
+
+impl Constraint for Thermo {
+    fn is_broken(&self, sudoku: &Sudoku) -> bool {
+        let side = sudoku.side();
+        // Filled cells must leave room for the empty cells between them to
+        // increase too. It is enough to compare each filled cell to the
+        // previous filled one.
+        let mut previous: Option<(usize, usize)> = None;
+        for (i, &(r, c)) in self.cells.iter().enumerate() {
+            let value = match sudoku.get(r, c).value() {
+                Some(value) => value,
+                None => continue,
+            };
+            if !self.allows(r, c, value, side) {
+                return true;
+            }
+            if let Some((j, previous)) = previous {
+                if value < previous + (i - j) {
+                    return true;
+                }
+            }
+            previous = Some((i, value));
+        }
+        false
+    }
+
+    fn involves(&self, row: usize, column: usize) -> bool {
+        self.cells.contains(&(row, column))
+    }
+
+    /// The `i`th cell of the thermometer needs at least `i` smaller digits
+    /// before it, and as many larger digits after it as there are cells left.
+    fn allows(&self, row: usize, column: usize, digit: usize, side: usize) -> bool {
+        match self.cells.iter().position(|&cell| cell == (row, column)) {
+            Some(i) => digit > i && digit + (self.cells.len() - 1 - i) <= side,
+            None => true,
+        }
+    }
+}
+
+impl Display for Thermo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "thermo")?;
+        for &cell in &self.cells {
+            write!(f, " ")?;
+            fmt_cell(f, cell)?;
+        }
+        Ok(())
+    }
+}
@@ -1,14 +1,62 @@
 use std::fmt::Display;
+use std::num::NonZeroU8;
 
+#[cfg(feature = "proptest")]
+pub mod arbitrary;
+mod budget;
+pub mod check;
+pub mod constraints;
 pub mod parsing;
-
-#[derive(Debug, Clone)]
+mod shared;
+pub mod solver;
+mod sudoku9;
+mod units;
+mod verify;
+
+pub use budget::Budget;
+pub use shared::SharedSudoku;
+pub use solver::{NoProgress, ProgressSink, SolveError, Solver};
+pub use sudoku9::Sudoku9;
+pub use units::{Unit, UnitKind};
+pub use verify::{verify_solution, Mismatch};
+
+/// The contents of a cell. Digits are stored as a `NonZeroU8`, so that a
+/// cell takes up a single byte.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SudokuCell {
     Empty,
-    Digit(usize),
+    Digit(NonZeroU8),
 }
 
 impl SudokuCell {
+    /// A cell holding `digit`.
+    ///
+    /// # Panics
+    ///
+    /// If `digit` is 0, or does not fit in a byte.
+    pub fn digit(digit: usize) -> Self {
+        match u8::try_from(digit).ok().and_then(NonZeroU8::new) {
+            Some(digit) => SudokuCell::Digit(digit),
+            None => panic!("{} is not a valid sudoku digit.", digit),
+        }
+    }
+
+    /// The byte a cell is stored as in a [`Sudoku`]; 0 for an empty cell,
+    /// and the digit otherwise.
+    fn to_byte(self) -> u8 {
+        match self {
+            SudokuCell::Empty => 0,
+            SudokuCell::Digit(d) => d.get(),
+        }
+    }
+
+    fn from_byte(byte: u8) -> Self {
+        match NonZeroU8::new(byte) {
+            None => SudokuCell::Empty,
+            Some(d) => SudokuCell::Digit(d),
+        }
+    }
+
     pub fn is_empty(&self) -> bool {
         match self {
             SudokuCell::Empty => true,
@@ -26,31 +74,25 @@ impl SudokuCellValue for SudokuCell {
     fn value(&self) -> Option<usize> {
         match self {
             SudokuCell::Empty => None,
-            SudokuCell::Digit(d) => Some(*d),
+            SudokuCell::Digit(d) => Some(d.get() as usize),
         }
     }
 
     fn unwrap(self) -> usize {
         match self {
             SudokuCell::Empty => panic!("Tried to unwrap an empty sudoku cell."),
-            SudokuCell::Digit(d) => d,
+            SudokuCell::Digit(d) => d.get() as usize,
         }
     }
 }
 
 impl SudokuCellValue for &SudokuCell {
     fn value(&self) -> Option<usize> {
-        match self {
-            SudokuCell::Empty => None,
-            SudokuCell::Digit(d) => Some(*d),
-        }
+        (**self).value()
     }
 
     fn unwrap(self) -> usize {
-        match self {
-            SudokuCell::Empty => panic!("Tried to unwrap an empty sudoku cell."),
-            SudokuCell::Digit(d) => *d,
-        }
+        (*self).unwrap()
     }
 }
 
@@ -61,8 +103,8 @@ impl TryFrom<char> for SudokuCell {
         if value == '_' {
             return Ok(SudokuCell::Empty);
         }
-        if let Some(d) = value.to_digit(10) {
-            return Ok(SudokuCell::Digit(d as usize));
+        if let Some(d) = value.to_digit(10).and_then(|d| NonZeroU8::new(d as u8)) {
+            return Ok(SudokuCell::Digit(d));
         }
         return Err(value);
     }
@@ -74,19 +116,42 @@ impl TryFrom<String> for SudokuCell {
     fn try_from(value: String) -> Result<Self, Self::Error> {
         if value.chars().all(|c| c == '_') {
             Ok(SudokuCell::Empty)
-        } else if let Ok(value) = value.parse::<usize>() {
-            Ok(SudokuCell::Digit(value))
         } else {
-            Err(value)
+            value
+                .parse::<NonZeroU8>()
+                .map(SudokuCell::Digit)
+                .map_err(|_| value)
+        }
+    }
+}
+
+/// Why [`Sudoku::set_checked`] refused to set a cell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SetError {
+    OutOfBounds { row: usize, column: usize },
+    DigitOutOfRange { digit: usize },
+}
+
+impl Display for SetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SetError::OutOfBounds { row, column } => {
+                write!(f, "There is no cell at row {}, column {}.", row, column)
+            }
+            SetError::DigitOutOfRange { digit } => {
+                write!(f, "{} is not a digit of this board.", digit)
+            }
         }
     }
 }
 
+impl std::error::Error for SetError {}
+
 #[derive(Debug, Clone)]
 pub struct Sudoku {
     side: usize,
     box_side: usize,
-    values: Vec<SudokuCell>, // Row-major
+    values: Vec<u8>, // Row-major, see SudokuCell::to_byte
 }
 
 impl Sudoku {
@@ -94,7 +159,7 @@ impl Sudoku {
         Sudoku {
             side,
             box_side: (side as f32).sqrt() as usize,
-            values: vec![SudokuCell::Empty; side * side],
+            values: vec![SudokuCell::Empty.to_byte(); side * side],
         }
     }
 
@@ -108,20 +173,40 @@ impl Sudoku {
 
     pub fn set(&mut self, row: usize, column: usize, value: SudokuCell) {
         let index = row * self.side + column;
-        self.values[index] = value;
+        self.values[index] = value.to_byte();
+    }
+
+    /// Like [`Sudoku::set`], but refuses coordinates outside of the board,
+    /// and digits outside of `1..=side`.
+    pub fn set_checked(
+        &mut self,
+        row: usize,
+        column: usize,
+        value: SudokuCell,
+    ) -> Result<(), SetError> {
+        if row >= self.side || column >= self.side {
+            return Err(SetError::OutOfBounds { row, column });
+        }
+        if let Some(digit) = value.value() {
+            if digit > self.side {
+                return Err(SetError::DigitOutOfRange { digit });
+            }
+        }
+        self.set(row, column, value);
+        Ok(())
     }
 
-    pub fn get(&self, row: usize, column: usize) -> &SudokuCell {
+    pub fn get(&self, row: usize, column: usize) -> SudokuCell {
         let index = row * self.side + column;
-        &self.values[index]
+        SudokuCell::from_byte(self.values[index])
     }
 
     pub fn set_raw(&mut self, index: usize, value: SudokuCell) {
-        self.values[index] = value;
+        self.values[index] = value.to_byte();
     }
 
-    pub fn get_raw(&self, index: usize) -> &SudokuCell {
-        &self.values[index]
+    pub fn get_raw(&self, index: usize) -> SudokuCell {
+        SudokuCell::from_byte(self.values[index])
     }
 
     pub fn swap_raw(&mut self, raw_a: usize, raw_b: usize) {
@@ -131,11 +216,11 @@ impl Sudoku {
 
 impl Display for Sudoku {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for (i, cell) in self.values.iter().enumerate() {
+        for (i, &cell) in self.values.iter().enumerate() {
             if i % self.side == 0 && i > 0 {
                 write!(f, "\n")?;
             }
-            match cell {
+            match SudokuCell::from_byte(cell) {
                 SudokuCell::Empty => write!(f, "_ ")?,
                 SudokuCell::Digit(d) => write!(f, "{} ", d)?,
             }
@@ -0,0 +1,43 @@
+use crate::Sudoku;
+use std::path::Path;
+
+/// The puzzles in a directory of .sudoku files, in the order of their file
+/// names, or in an .sdm collection, each with a name to report it by: the
+/// file name, or, for an .sdm collection, its file name and the number of
+/// the puzzle in it, as in `top95.sdm#12`.
+pub fn read_collection(path: &Path) -> Result<Vec<(String, Sudoku)>, String> {
+    let name = |path: &Path| {
+        path.file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default()
+    };
+
+    if path.is_dir() {
+        let entries = std::fs::read_dir(path)
+            .map_err(|e| format!("Could not read {}, with error {}", name(path), e))?;
+        let mut paths = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "sudoku"))
+            .collect::<Vec<_>>();
+        paths.sort();
+        paths
+            .into_iter()
+            .map(|path| {
+                let reader = std::fs::File::open(&path)
+                    .map_err(|e| format!("Could not open {}, with error {}", name(&path), e))?;
+                let sudoku = super::sudoku::parse(reader)
+                    .map_err(|e| format!("In {}:\n{}", name(&path), e))?;
+                Ok((name(&path), sudoku))
+            })
+            .collect()
+    } else {
+        let reader = std::fs::File::open(path)
+            .map_err(|e| format!("Could not open {}, with error {}", name(path), e))?;
+        let sudokus = super::sdm::parse(reader)?;
+        Ok(sudokus
+            .into_iter()
+            .enumerate()
+            .map(|(i, sudoku)| (format!("{}#{}", name(path), i + 1), sudoku))
+            .collect())
+    }
+}
@@ -0,0 +1,222 @@
+use super::*;
+use crate::constraints::{
+    Constraints, Diagonal, Line, LittleKiller, Parity, ParityCells, Sandwich, Thermo,
+};
+use std::io::Read;
+
+/// Parses a `.constraints` file, for a board of `side` by `side` cells.
+pub fn parse<R: Read>(reader: R, side: usize) -> Result<Constraints, String> {
+    let mut parser = Parser::new(CharReader::new(reader));
+    let mut constraints = Constraints::new();
+
+    while !parser.try_match_eof().with_default_err_msgs(&parser)? {
+        // This will run once per line
+        parser.eat_space().with_default_err_msgs(&parser)?;
+
+        // If we see an '#', just discard everything until a newline is found
+        if parser.try_match('#').with_default_err_msgs(&parser)? {
+            parser
+                .discard_predicate(|&c| c != '\n')
+                .with_default_err_msgs(&parser)?;
+            parser.try_match('\n').with_default_err_msgs(&parser)?;
+            continue;
+        }
+
+        let words = match_words(&mut parser)?;
+        let (keyword, arguments) = match words.split_first() {
+            Some(split) => split,
+            None => continue, // Empty line
+        };
+
+        match keyword.as_str() {
+            "thermo" => {
+                let cells = arguments
+                    .iter()
+                    .map(|word| parse_cell(word, side))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| parser.err(e))?;
+                if cells.len() < 2 {
+                    return Err(parser.err("A thermo needs at least two cells.".to_string()));
+                }
+                if cells.windows(2).any(|pair| !touch(pair[0], pair[1])) {
+                    return Err(parser.err(
+                        "Consecutive cells of a thermo must touch, orthogonally or diagonally."
+                            .to_string(),
+                    ));
+                }
+                constraints.push(Thermo::new(cells));
+            }
+            "even" | "odd" => {
+                let parity = match keyword.as_str() {
+                    "even" => Parity::Even,
+                    _ => Parity::Odd,
+                };
+                let cells = arguments
+                    .iter()
+                    .map(|word| parse_cell(word, side))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| parser.err(e))?;
+                if cells.is_empty() {
+                    return Err(parser.err(format!("Expected cells after '{}'.", keyword)));
+                }
+                constraints.push(ParityCells::new(parity, cells));
+            }
+            "sandwich" => {
+                let (line, index, sum) = match arguments {
+                    [line, index, sum] => (line, index, sum),
+                    _ => {
+                        return Err(parser.err(
+                            "A sandwich takes a row or column, its number, and a sum.".to_string(),
+                        ))
+                    }
+                };
+                let line = match line.as_str() {
+                    "row" => Line::Row,
+                    "column" => Line::Column,
+                    other => {
+                        return Err(parser.err(format!(
+                            "Expected 'row' or 'column', but found '{}'.",
+                            other
+                        )))
+                    }
+                };
+                let index = parse_number(index)
+                    .filter(|index| (1..=side).contains(index))
+                    .ok_or_else(|| {
+                        parser.err(format!(
+                            "Expected a number from 1 to {}, but found '{}'.",
+                            side, index
+                        ))
+                    })?;
+                let greatest = (2..side).sum::<usize>();
+                let sum = parse_number(sum)
+                    .filter(|&sum| sum <= greatest)
+                    .ok_or_else(|| {
+                        parser.err(format!(
+                            "Expected a sum from 0 to {}, but found '{}'.",
+                            greatest, sum
+                        ))
+                    })?;
+                constraints.push(Sandwich::new(line, index - 1, sum));
+            }
+            "little-killer" => {
+                let (start, direction, sum) = match arguments {
+                    [start, direction, sum] => (start, direction, sum),
+                    _ => {
+                        return Err(parser.err(
+                            "A little killer takes a cell, a direction, and a sum.".to_string(),
+                        ))
+                    }
+                };
+                let start = parse_cell(start, side).map_err(|e| parser.err(e))?;
+                let direction = match direction.as_str() {
+                    "down-right" => Diagonal::DownRight,
+                    "down-left" => Diagonal::DownLeft,
+                    "up-right" => Diagonal::UpRight,
+                    "up-left" => Diagonal::UpLeft,
+                    other => {
+                        return Err(parser.err(format!(
+                            concat!(
+                                "Expected 'down-right', 'down-left', 'up-right' or 'up-left', ",
+                                "but found '{}'."
+                            ),
+                            other
+                        )))
+                    }
+                };
+                let sum = parse_number(sum)
+                    .ok_or_else(|| parser.err(format!("Expected a sum, but found '{}'.", sum)))?;
+                let clue = LittleKiller::new(start, direction, sum, side);
+                let (row, column) = start;
+                if row != 0 && column != 0 && row != side - 1 && column != side - 1 {
+                    return Err(parser.err(format!(
+                        concat!(
+                            "A little killer must start on the edge of the board, ",
+                            "but r{}c{} is not on it."
+                        ),
+                        row + 1,
+                        column + 1
+                    )));
+                }
+                let length = clue.cells().len();
+                if length < 2 {
+                    return Err(parser.err(format!(
+                        concat!(
+                            "A little killer from r{}c{} must run into the board, ",
+                            "but {} runs out of it."
+                        ),
+                        row + 1,
+                        column + 1,
+                        direction
+                    )));
+                }
+                if sum < length || sum > length * side {
+                    return Err(parser.err(format!(
+                        "The {} cells of this diagonal cannot add up to {}.",
+                        length, sum
+                    )));
+                }
+                constraints.push(clue);
+            }
+            other => {
+                return Err(parser.err(format!("I don't know the constraint '{}'.", other)));
+            }
+        }
+    }
+
+    Ok(constraints)
+}
+
+/// Collects the whitespace separated words until the end of the line, and
+/// consumes the line break.
+fn match_words<I>(
+    parser: &mut Parser<Peekable<I>, I, CharReaderError>,
+) -> Result<Vec<String>, String>
+where
+    I: Iterator<Item = Result<char, CharReaderError>>,
+{
+    let mut words = vec![];
+    loop {
+        parser.eat_space().with_default_err_msgs(parser)?;
+        if parser.try_match_eof().with_default_err_msgs(parser)? {
+            break;
+        }
+        parser.try_match('\r').with_default_err_msgs(parser)?;
+        if parser.try_match('\n').with_default_err_msgs(parser)? {
+            break;
+        }
+        words.push(
+            parser
+                .collect_predicate(|c| !c.is_whitespace())
+                .with_default_err_msgs(parser)?,
+        );
+    }
+    Ok(words)
+}
+
+/// Parses a cell written as `r<row>c<column>`, counting from 1.
+fn parse_cell(word: &str, side: usize) -> Result<(usize, usize), String> {
+    let malformed = || format!("Expected a cell like r1c2, but found '{}'.", word);
+    let lowercase = word.to_lowercase();
+    let (row, column) = lowercase
+        .strip_prefix('r')
+        .and_then(|rest| rest.split_once('c'))
+        .ok_or_else(malformed)?;
+    let row = row.parse::<usize>().map_err(|_| malformed())?;
+    let column = column.parse::<usize>().map_err(|_| malformed())?;
+    if row == 0 || column == 0 || row > side || column > side {
+        return Err(format!(
+            "There is no cell {} on a {}x{} board.",
+            word, side, side
+        ));
+    }
+    Ok((row - 1, column - 1))
+}
+
+fn parse_number(word: &str) -> Option<usize> {
+    word.parse::<usize>().ok()
+}
+
+fn touch((r, c): (usize, usize), (rr, cc): (usize, usize)) -> bool {
+    (r, c) != (rr, cc) && r.abs_diff(rr) <= 1 && c.abs_diff(cc) <= 1
+}
@@ -2,8 +2,14 @@ use self::chars_reader::{CharReader, CharReaderError};
 use std::{convert::Infallible, iter::Peekable, marker::PhantomData};
 
 pub mod chars_reader;
+mod collection;
+pub mod constraints;
+pub mod schedule;
+pub mod sdm;
 pub mod sudoku;
 
+pub use collection::read_collection;
+
 #[derive(Debug)]
 pub enum ParseError {
     NotUtf8,
@@ -0,0 +1,311 @@
+use std::io::Read;
+use crate::parsing::chars_reader::CharReader;
+use crate::parsing::{self, AllowEof, DefaultParseError};
+
+pub mod presets;
+
+#[derive(Clone)]
+pub struct Schedule {
+    pub temperatures: Vec<f64>,
+    pub rounds: Vec<usize>,
+}
+
+impl Schedule {
+    pub fn builder() -> ScheduleBuilder {
+        ScheduleBuilder::default()
+    }
+
+    pub fn run(&self) -> impl Iterator<Item = &f64> {
+        self.temperatures
+            .iter()
+            .zip(self.rounds.iter())
+            .map(|(t, &r)| (0..r).map(move |_| t))
+            .flatten()
+    }
+
+    /// Like [`Schedule::run`], but consumes the schedule, so that the
+    /// iterator can outlive it (e.g., be moved into another thread).
+    pub fn into_run(self) -> impl Iterator<Item = f64> + 'static {
+        self.temperatures
+            .into_iter()
+            .zip(self.rounds)
+            .flat_map(|(t, r)| std::iter::repeat_n(t, r))
+    }
+
+    /// Total number of iterations in the schedule, i.e., the length of
+    /// [`Schedule::run`].
+    pub fn total_iterations(&self) -> usize {
+        self.rounds.iter().sum()
+    }
+
+    /// The same schedule, with the number of rounds of each temperature
+    /// multiplied by `factor`, and rounded up, so that no temperature is left
+    /// out.
+    pub fn with_scaled_rounds(&self, factor: f64) -> Schedule {
+        Schedule {
+            temperatures: self.temperatures.clone(),
+            rounds: self
+                .rounds
+                .iter()
+                .map(|&rounds| (rounds as f64 * factor).ceil() as usize)
+                .collect(),
+        }
+    }
+}
+
+/// Writes the schedule in .schedule format, one (temperature, rounds) pair per
+/// line, such that it parses back into the same schedule.
+impl std::fmt::Display for Schedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "# Temperature & iterations")?;
+        for (temperature, rounds) in self.temperatures.iter().zip(self.rounds.iter()) {
+            // The shortest exponent representation round-trips exactly, but
+            // the format requires a decimal part in the mantissa.
+            let temperature = format!("{:e}", temperature);
+            let temperature = match temperature.split_once('e') {
+                Some((mantissa, exponent)) if !mantissa.contains('.') => {
+                    format!("{}.0e{}", mantissa, exponent)
+                }
+                _ => temperature,
+            };
+            writeln!(f, "{} {}", temperature, rounds)?;
+        }
+        Ok(())
+    }
+}
+
+/// Builds a [`Schedule`] programmatically, e.g.
+///
+/// ```
+/// # use sudoku::parsing::schedule::Schedule;
+/// let schedule = Schedule::builder()
+///     .geometric(10.0, 0.95, 200, 100)
+///     .plateau(0.5, 10_000)
+///     .build();
+/// ```
+#[derive(Default)]
+pub struct ScheduleBuilder {
+    temperatures: Vec<f64>,
+    rounds: Vec<usize>,
+}
+
+impl ScheduleBuilder {
+    /// Hold `temperature` for `rounds` iterations.
+    pub fn plateau(mut self, temperature: f64, rounds: usize) -> Self {
+        self.temperatures.push(temperature);
+        self.rounds.push(rounds);
+        self
+    }
+
+    /// Append `steps` temperatures, starting at `initial` and each being
+    /// `rate` times the previous one, held for `rounds_per_step` iterations
+    /// each. This is the same as the `geometric` directive of the .schedule
+    /// format.
+    ///
+    /// Panics if `rate` is not in (0, 1].
+    pub fn geometric(
+        mut self,
+        initial: f64,
+        rate: f64,
+        steps: usize,
+        rounds_per_step: usize,
+    ) -> Self {
+        assert!(
+            rate > 0. && rate <= 1.,
+            "Geometric rates must be in (0., 1.]."
+        );
+        push_geometric(
+            &mut self.temperatures,
+            &mut self.rounds,
+            initial,
+            rate,
+            steps,
+            rounds_per_step,
+        );
+        self
+    }
+
+    /// Append everything in `block`, `times` times over.
+    pub fn repeat(mut self, times: usize, block: ScheduleBuilder) -> Self {
+        for _ in 0..times {
+            self.temperatures.extend_from_slice(&block.temperatures);
+            self.rounds.extend_from_slice(&block.rounds);
+        }
+        self
+    }
+
+    pub fn build(self) -> Schedule {
+        Schedule {
+            temperatures: self.temperatures,
+            rounds: self.rounds,
+        }
+    }
+}
+
+fn push_geometric(
+    temperatures: &mut Vec<f64>,
+    rounds: &mut Vec<usize>,
+    initial: f64,
+    rate: f64,
+    steps: usize,
+    rounds_per_step: usize,
+) {
+    let mut temperature = initial;
+    for _ in 0..steps {
+        temperatures.push(temperature);
+        rounds.push(rounds_per_step);
+        temperature *= rate;
+    }
+}
+
+pub fn parse<R: Read>(from: R) -> Result<Schedule, String> {
+    let mut parser = parsing::Parser::new(CharReader::new(from));
+
+    let mut temperatures = vec![];
+    let mut rounds = vec![];
+
+    // Open repeat blocks, as (repetitions, index of the first entry in the
+    // block, line the block was opened in).
+    let mut open_blocks: Vec<(usize, usize, usize)> = vec![];
+
+    while !parser.try_match_eof().with_default_err_msgs(&parser)? {
+        // This will run once per line
+
+        // Consume initial whitespace
+        parser.eat_space().with_default_err_msgs(&parser)?;
+        if parser.try_match_eof().with_default_err_msgs(&parser)? {
+            break;
+        }
+
+        // If we see an '#', just discard everything until a newline is found
+        if parser.try_match('#').with_default_err_msgs(&parser)? {
+            parser
+                .discard_predicate(|&c| c != '\n')
+                .with_default_err_msgs(&parser)?;
+            parser
+                .expect('\n')
+                .eof_ok()
+                .with_default_err_msgs(&parser)?;
+            continue;
+        }
+
+        // A repeat block repeats everything up to the matching closing brace
+        // the given number of times. Blocks may be nested.
+        if parser.try_match_str("repeat").with_default_err_msgs(&parser)? {
+            parser.eat_space().with_default_err_msgs(&parser)?;
+            let repetitions = parser.expect_integer().with_default_err_msgs(&parser)?;
+            parser.eat_space().with_default_err_msgs(&parser)?;
+            parser.expect('{').with_default_err_msgs(&parser)?;
+            open_blocks.push((repetitions, temperatures.len(), parser.line()));
+
+            parser.eat_space().with_default_err_msgs(&parser)?;
+            parser.try_match('\n').with_default_err_msgs(&parser)?;
+            continue;
+        }
+
+        if parser.try_match('}').with_default_err_msgs(&parser)? {
+            let (repetitions, start, _) = match open_blocks.pop() {
+                Some(block) => block,
+                None => {
+                    return Err(format!(
+                        "Line {} closes a repeat block that was never opened.",
+                        parser.line()
+                    ))
+                }
+            };
+
+            let end = temperatures.len();
+            if repetitions == 0 {
+                temperatures.truncate(start);
+                rounds.truncate(start);
+            } else {
+                for _ in 1..repetitions {
+                    temperatures.extend_from_within(start..end);
+                    rounds.extend_from_within(start..end);
+                }
+            }
+
+            parser.eat_space().with_default_err_msgs(&parser)?;
+            parser.try_match('\n').with_default_err_msgs(&parser)?;
+            continue;
+        }
+
+        // A geometric directive expands into a ladder of temperatures
+        //  T0, T0 * rate, T0 * rate^2, ...
+        // each held for the same number of rounds.
+        if parser
+            .try_match_str("geometric")
+            .with_default_err_msgs(&parser)?
+        {
+            parser.eat_space().with_default_err_msgs(&parser)?;
+            let initial = parser.expect_float().with_default_err_msgs(&parser)?;
+            parser.eat_space().with_default_err_msgs(&parser)?;
+            let rate = parser.expect_float().with_default_err_msgs(&parser)?;
+            parser.eat_space().with_default_err_msgs(&parser)?;
+            let steps = parser.expect_integer().with_default_err_msgs(&parser)?;
+            parser.eat_space().with_default_err_msgs(&parser)?;
+            let rounds_per_step = parser.expect_integer().with_default_err_msgs(&parser)?;
+
+            if rate <= 0. || rate > 1. {
+                return Err(format!(
+                    concat!(
+                        "Geometric rates must be in (0., 1.].\n",
+                        "Line {} has {}."
+                    ),
+                    parser.line(),
+                    rate
+                ));
+            }
+
+            push_geometric(
+                &mut temperatures,
+                &mut rounds,
+                initial,
+                rate,
+                steps,
+                rounds_per_step,
+            );
+
+            parser.eat_space().with_default_err_msgs(&parser)?;
+            parser.try_match('\n').with_default_err_msgs(&parser)?;
+            continue;
+        }
+
+        // Match a temperature and a number of iterations.
+        let temperature = parser.expect_float().with_default_err_msgs(&parser)?;
+        /*if temperature < 0. || temperature > 1. {
+            return Err(format!(
+                concat!(
+                    "Temperatures must be between 0. and 1.\n",
+                    "Line {} has {}."
+                ),
+                parser.line(),
+                temperature
+            ));
+        }*/
+        temperatures.push(temperature);
+        parser.eat_space().with_default_err_msgs(&parser)?;
+        rounds.push(parser.expect_integer().with_default_err_msgs(&parser)?);
+
+        // Eat trailing whitespace
+        parser.eat_space().with_default_err_msgs(&parser)?;
+
+        parser.try_match('\n').with_default_err_msgs(&parser)?;
+    }
+
+    if let Some((_, _, line)) = open_blocks.pop() {
+        return Err(format!(
+            "The repeat block opened in line {} is never closed.",
+            line
+        ));
+    }
+
+    if temperatures.len() == 0 {
+        return Err("Empty schedule file.".to_string());
+    }
+
+    Ok(Schedule {
+        temperatures,
+        rounds,
+    })
+}
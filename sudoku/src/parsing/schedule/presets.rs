@@ -0,0 +1,24 @@
+//! Reasonable default schedules, for when no hand-tuned schedule is at hand.
+//!
+//! The 9x9 preset is the geometric schedule used in the benchmarks in
+//! `performance/`; the others are scaled from it by hand.
+
+use super::Schedule;
+
+/// A default schedule for a board of the given side.
+///
+/// There are tuned presets for 4x4, 9x9 and 16x16 boards; for other sizes the
+/// 9x9 preset is scaled with the number of cells.
+pub fn for_side(side: usize) -> Schedule {
+    match side {
+        4 => Schedule::builder().geometric(2., 0.95, 100, 50).build(),
+        9 => Schedule::builder().geometric(20., 0.99, 500, 200).build(),
+        16 => Schedule::builder().geometric(40., 0.995, 1_000, 1_000).build(),
+        _ => {
+            let cells = (side * side) as f64;
+            Schedule::builder()
+                .geometric(20. * cells / 81., 0.99, 500, (200. * cells / 81.) as usize)
+                .build()
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use super::*;
+use crate::{Sudoku, SudokuCell, SudokuCellValue};
+use std::io::Read;
+
+/// Parses an `.sdm` collection of boards, as used by many published puzzle
+/// collections: one board per line, written as its cells in reading order,
+/// without separators, and with `.` or `0` for empty cells. Since the digits
+/// of larger boards can take more than one character, their cells are
+/// separated by spaces instead. Empty lines are ignored.
+pub fn parse<R: Read>(reader: R) -> Result<Vec<Sudoku>, String> {
+    let mut parser = Parser::new(CharReader::new(reader));
+    let mut sudokus = vec![];
+
+    while !parser.try_match_eof().with_default_err_msgs(&parser)? {
+        // This will run once per line
+        let line = parser
+            .collect_predicate(|&c| c != '\n')
+            .with_default_err_msgs(&parser)?;
+        let line = line.trim();
+        if !line.is_empty() {
+            sudokus.push(parse_line(line).map_err(|e| parser.err(e))?);
+        }
+        parser.try_match('\n').with_default_err_msgs(&parser)?;
+    }
+
+    Ok(sudokus)
+}
+
+/// Writes a board as a line of an `.sdm` collection, as read by [`parse`],
+/// with `.` for empty cells, and without the line break. The cells of boards
+/// larger than 9 by 9 are separated by spaces.
+pub fn write(sudoku: &Sudoku) -> String {
+    let side = sudoku.side();
+    let separator = if side > 9 { " " } else { "" };
+    (0..side * side)
+        .map(|raw| match sudoku.get_raw(raw).value() {
+            Some(d) => d.to_string(),
+            None => ".".to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+fn parse_line(line: &str) -> Result<Sudoku, String> {
+    let tokens = if line.contains(char::is_whitespace) {
+        line.split_whitespace().collect::<Vec<_>>()
+    } else {
+        line.char_indices()
+            .map(|(i, c)| &line[i..i + c.len_utf8()])
+            .collect()
+    };
+    let cells = tokens.len();
+    let side = (cells as f32).sqrt() as usize;
+    let box_size = (side as f32).sqrt() as usize;
+    if side * side != cells || box_size * box_size != side {
+        return Err(format!(
+            "I counted {} cells, which is not the number of cells of a board.",
+            cells
+        ));
+    }
+
+    let mut sudoku = Sudoku::empty(side);
+    for (raw, token) in tokens.into_iter().enumerate() {
+        let d = match token {
+            "." | "0" => SudokuCell::Empty,
+            token => SudokuCell::try_from(token.to_string())
+                .map_err(|c| format!("Sorry, I don't know how to read '{}' as a cell.", c))?,
+        };
+        if let Some(d) = d.value() {
+            if d > side {
+                return Err(format!(
+                    "The board has {side} by {side} cells, but you wrote {d} in one of them. Please use values from 1 to {side}.",
+                ));
+            }
+        }
+        sudoku.set_raw(raw, d);
+    }
+    Ok(sudoku)
+}
@@ -5,9 +5,59 @@ use std::io::Read;
 pub fn parse<R: Read>(reader: R) -> Result<Sudoku, String> {
     let mut parser = Parser::new(CharReader::new(reader));
 
+    let sudoku = parse_board(&mut parser)?;
+
+    // If after eating all the remaining whitespace we are not at EOF, then
+    // the file is misformatted.
+    parser.eat_space().with_default_err_msgs(&parser)?;
+    parser.expect_eof().map_err(|err| match err {
+        ParseError::UnexpectedEof | ParseError::UnexpectedChar(_) | ParseError::ExpectedEof => {
+            parser.err(
+                concat!(
+                    "Finished parsing the sudoku puzzle, ",
+                    "but there's non-whitespace remaining in the file.",
+                    "Is your board not square?"
+                )
+                .to_string(),
+            )
+        }
+        _ => parser.default_err_msg(err),
+    })?;
+
+    Ok(sudoku)
+}
+
+/// Parses several boards in the .sudoku format, one after the other, as in
+/// concatenated .sudoku files. Boards may be separated by empty lines, and
+/// need not all be the same size.
+pub fn parse_many<R: Read>(reader: R) -> Result<Vec<Sudoku>, String> {
+    let mut parser = Parser::new(CharReader::new(reader));
+    let mut sudokus = vec![];
+
+    loop {
+        // Skip the empty lines before the next board, if any.
+        parser.eat_space().with_default_err_msgs(&parser)?;
+        parser.try_match('\r').with_default_err_msgs(&parser)?;
+        if parser.try_match('\n').with_default_err_msgs(&parser)? {
+            continue;
+        }
+        if parser.try_match_eof().with_default_err_msgs(&parser)? {
+            break;
+        }
+        sudokus.push(parse_board(&mut parser)?);
+    }
+
+    Ok(sudokus)
+}
+
+/// Parses a single board, leaving the parser at the end of its last line.
+fn parse_board<I>(parser: &mut Parser<Peekable<I>, I, CharReaderError>) -> Result<Sudoku, String>
+where
+    I: Iterator<Item = Result<char, CharReaderError>>,
+{
     // Read the first line. This will give a hint as to the size of the board.
     let mut first_line = Vec::<String>::new();
-    match_line(&mut parser, |_i, c| {
+    match_line(parser, |_i, c| {
         first_line.push(c);
         Ok(())
     })?;
@@ -58,7 +108,7 @@ pub fn parse<R: Read>(reader: R) -> Result<Sudoku, String> {
     // Parse the rest of the lines;
     // We expect (dimensions - 1) lines remaining!
     for line in 1..side {
-        match_line(&mut parser, |i, c| {
+        match_line(parser, |i, c| {
             if i >= side {
                 return Err(format!("There are too many elements on line {}!", line));
             }
@@ -77,23 +127,6 @@ pub fn parse<R: Read>(reader: R) -> Result<Sudoku, String> {
         })?;
     }
 
-    // If after eating all the remaining whitespace we are not at EOF, then
-    // the file is misformatted.
-    parser.eat_space().with_default_err_msgs(&parser)?;
-    parser.expect_eof().map_err(|err| match err {
-        ParseError::UnexpectedEof | ParseError::UnexpectedChar(_) | ParseError::ExpectedEof => {
-            parser.err(
-                concat!(
-                    "Finished parsing the sudoku puzzle, ",
-                    "but there's non-whitespace remaining in the file.",
-                    "Is your board not square?"
-                )
-                .to_string(),
-            )
-        }
-        _ => parser.default_err_msg(err),
-    })?;
-
     Ok(sudoku)
 }
 
@@ -0,0 +1,67 @@
+use crate::{Sudoku, SudokuCell};
+use std::fmt::Display;
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// A copy-on-write handle to a [`Sudoku`].
+///
+/// Cloning a `SharedSudoku` only clones a reference to the same board, and
+/// the board itself is only copied when one of the clones changes it while
+/// others still hold it. This makes it cheap to fork a board across threads,
+/// when most forks only ever read it.
+#[derive(Debug, Clone)]
+pub struct SharedSudoku(Arc<Sudoku>);
+
+impl SharedSudoku {
+    pub fn new(sudoku: Sudoku) -> Self {
+        SharedSudoku(Arc::new(sudoku))
+    }
+
+    pub fn set(&mut self, row: usize, column: usize, value: SudokuCell) {
+        Arc::make_mut(&mut self.0).set(row, column, value);
+    }
+
+    pub fn set_raw(&mut self, index: usize, value: SudokuCell) {
+        Arc::make_mut(&mut self.0).set_raw(index, value);
+    }
+
+    pub fn swap_raw(&mut self, raw_a: usize, raw_b: usize) {
+        Arc::make_mut(&mut self.0).swap_raw(raw_a, raw_b);
+    }
+
+    /// A mutable reference to the board, which is copied first if it is
+    /// shared.
+    pub fn make_mut(&mut self) -> &mut Sudoku {
+        Arc::make_mut(&mut self.0)
+    }
+
+    /// Whether other handles to the same board exist.
+    pub fn is_shared(&self) -> bool {
+        Arc::strong_count(&self.0) > 1
+    }
+
+    /// The board, which is copied if it is shared.
+    pub fn into_inner(self) -> Sudoku {
+        Arc::try_unwrap(self.0).unwrap_or_else(|shared| (*shared).clone())
+    }
+}
+
+impl Deref for SharedSudoku {
+    type Target = Sudoku;
+
+    fn deref(&self) -> &Sudoku {
+        &self.0
+    }
+}
+
+impl From<Sudoku> for SharedSudoku {
+    fn from(sudoku: Sudoku) -> Self {
+        SharedSudoku::new(sudoku)
+    }
+}
+
+impl Display for SharedSudoku {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
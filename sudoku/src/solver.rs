@@ -0,0 +1,69 @@
+use crate::Sudoku;
+
+/// A configured sudoku solving algorithm.
+///
+/// Each solver crate provides a type implementing this trait, which holds the
+/// options of the solver, so that solvers can be used interchangeably.
+pub trait Solver {
+    /// What is reported about a successful solve.
+    type Report;
+
+    /// Solves `sudoku` in place. What is left on the board when solving fails
+    /// depends on the solver.
+    fn solve(&self, sudoku: &mut Sudoku) -> Result<Self::Report, SolveError>;
+}
+
+/// Why a [`Solver`] did not solve a board.
+///
+/// Solvers may report failures in more detail through their own functions;
+/// this is what they have in common.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolveError {
+    /// The board has no solution, e.g. because some of its clues conflict.
+    Infeasible,
+    /// The solver settled on a state that is not a solution, and could not
+    /// get out of it.
+    Glassed,
+    /// The solver ran through all the iterations it was given without
+    /// finding a solution.
+    IterationsExhausted,
+    /// The state the solver was asked to start from cannot be used with the
+    /// board.
+    BadHint { reason: String },
+    /// The solver ran out of budget, or was cancelled through its cancel
+    /// flag.
+    Cancelled,
+}
+
+impl std::fmt::Display for SolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SolveError::Infeasible => write!(f, "The input is infeasible."),
+            SolveError::Glassed => {
+                write!(f, "The solver got stuck in a state that is not a solution.")
+            }
+            SolveError::IterationsExhausted => {
+                write!(f, "The solver ran out of iterations without a solution.")
+            }
+            SolveError::BadHint { reason } => write!(f, "Bad hint: {}", reason),
+            SolveError::Cancelled => write!(f, "The solver ran out of budget and was cancelled."),
+        }
+    }
+}
+
+impl std::error::Error for SolveError {}
+
+/// Receives progress reports from a running solver, each a `P`, which is the
+/// type the solver reports in, e.g. `backtrack::Progress`.
+///
+/// The method has a no-op default implementation, so that a sink can ignore
+/// the reports of some solvers.
+pub trait ProgressSink<P> {
+    /// Called by the solver whenever it has something to report.
+    fn on_progress(&mut self, _progress: &P) {}
+}
+
+/// A [`ProgressSink`] that ignores all reports, of any solver.
+pub struct NoProgress;
+
+impl<P> ProgressSink<P> for NoProgress {}
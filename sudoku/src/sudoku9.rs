@@ -0,0 +1,110 @@
+use crate::{Sudoku, SudokuCell};
+use std::fmt::Display;
+
+/// A 9x9 board, with its size and box geometry known at compile time.
+///
+/// This has the same interface as [`Sudoku`], but is stored inline, and its
+/// peers are precomputed, so solvers can specialize on the common 9x9 case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sudoku9 {
+    values: [u8; 81], // Row-major, see SudokuCell::to_byte
+}
+
+impl Sudoku9 {
+    pub const SIDE: usize = 9;
+    pub const BOX_SIDE: usize = 3;
+
+    /// For each cell (by raw index), the raw indices of the 20 other cells in
+    /// its row, column or box.
+    pub const PEERS: [[u8; 20]; 81] = peers();
+
+    pub fn empty() -> Self {
+        Sudoku9 { values: [0; 81] }
+    }
+
+    pub fn side(&self) -> usize {
+        Self::SIDE
+    }
+
+    pub fn box_side(&self) -> usize {
+        Self::BOX_SIDE
+    }
+
+    pub fn set(&mut self, row: usize, column: usize, value: SudokuCell) {
+        self.set_raw(row * Self::SIDE + column, value);
+    }
+
+    pub fn get(&self, row: usize, column: usize) -> SudokuCell {
+        self.get_raw(row * Self::SIDE + column)
+    }
+
+    pub fn set_raw(&mut self, index: usize, value: SudokuCell) {
+        self.values[index] = value.to_byte();
+    }
+
+    pub fn get_raw(&self, index: usize) -> SudokuCell {
+        SudokuCell::from_byte(self.values[index])
+    }
+
+    pub fn swap_raw(&mut self, raw_a: usize, raw_b: usize) {
+        self.values.swap(raw_a, raw_b);
+    }
+
+    /// The index of the box the cell at raw index `index` is in, counting
+    /// boxes in reading order.
+    pub const fn box_of(index: usize) -> usize {
+        let (row, column) = (index / Self::SIDE, index % Self::SIDE);
+        (row / Self::BOX_SIDE) * Self::BOX_SIDE + column / Self::BOX_SIDE
+    }
+}
+
+const fn peers() -> [[u8; 20]; 81] {
+    let mut peers = [[0; 20]; 81];
+    let mut cell = 0;
+    while cell < 81 {
+        let mut found = 0;
+        let mut other = 0;
+        while other < 81 {
+            let same_row = cell / Sudoku9::SIDE == other / Sudoku9::SIDE;
+            let same_column = cell % Sudoku9::SIDE == other % Sudoku9::SIDE;
+            let same_box = Sudoku9::box_of(cell) == Sudoku9::box_of(other);
+            if other != cell && (same_row || same_column || same_box) {
+                peers[cell][found] = other as u8;
+                found += 1;
+            }
+            other += 1;
+        }
+        cell += 1;
+    }
+    peers
+}
+
+impl TryFrom<&Sudoku> for Sudoku9 {
+    /// The side of the board, which was not 9.
+    type Error = usize;
+
+    fn try_from(sudoku: &Sudoku) -> Result<Self, Self::Error> {
+        if sudoku.side != Self::SIDE {
+            return Err(sudoku.side);
+        }
+        let mut values = [0; 81];
+        values.copy_from_slice(&sudoku.values);
+        Ok(Sudoku9 { values })
+    }
+}
+
+impl From<&Sudoku9> for Sudoku {
+    fn from(sudoku: &Sudoku9) -> Self {
+        Sudoku {
+            side: Sudoku9::SIDE,
+            box_side: Sudoku9::BOX_SIDE,
+            values: sudoku.values.to_vec(),
+        }
+    }
+}
+
+impl Display for Sudoku9 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Sudoku::from(self).fmt(f)
+    }
+}
@@ -0,0 +1,49 @@
+use crate::Sudoku;
+
+/// Which kind of constraint group a [`Unit`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UnitKind {
+    Row,
+    Column,
+    Box,
+}
+
+/// A row, column or box of a board, each of which must hold every digit
+/// exactly once.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unit {
+    pub kind: UnitKind,
+    /// Which row, column or box this is; boxes are counted in reading order.
+    pub index: usize,
+    /// The raw indices of the cells of the unit, in reading order.
+    pub cells: Vec<usize>,
+}
+
+impl Sudoku {
+    /// Every row, then every column, then every box of the board.
+    pub fn units(&self) -> impl Iterator<Item = Unit> {
+        let side = self.side();
+        let box_side = self.box_side();
+        let rows = (0..side).map(move |r| Unit {
+            kind: UnitKind::Row,
+            index: r,
+            cells: (0..side).map(|c| r * side + c).collect(),
+        });
+        let columns = (0..side).map(move |c| Unit {
+            kind: UnitKind::Column,
+            index: c,
+            cells: (0..side).map(|r| r * side + c).collect(),
+        });
+        let boxes = (0..side).map(move |b| {
+            let (top, left) = ((b / box_side) * box_side, (b % box_side) * box_side);
+            Unit {
+                kind: UnitKind::Box,
+                index: b,
+                cells: (0..side)
+                    .map(|i| (top + i / box_side) * side + left + i % box_side)
+                    .collect(),
+            }
+        });
+        rows.chain(columns).chain(boxes)
+    }
+}
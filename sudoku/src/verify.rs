@@ -0,0 +1,115 @@
+use crate::{check, Sudoku, SudokuCellValue};
+use std::fmt::Display;
+
+/// Something wrong with a candidate solution, found by [`verify_solution`].
+///
+/// Rows and columns are counted from 0.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mismatch {
+    /// The solution is not the same size as the puzzle.
+    Size { puzzle: usize, solution: usize },
+    /// A cell of the solution is empty.
+    Empty { row: usize, column: usize },
+    /// A cell of the solution disagrees with the clue given for it.
+    Clue {
+        row: usize,
+        column: usize,
+        clue: usize,
+        value: usize,
+    },
+    /// Two cells in the same row, column or box hold the same digit.
+    Conflict {
+        cells: ((usize, usize), (usize, usize)),
+        value: usize,
+    },
+}
+
+impl Display for Mismatch {
+    /// Describes the mismatch, counting rows and columns from 1.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Mismatch::Size { puzzle, solution } => write!(
+                f,
+                "The solution is {}x{}, but the puzzle is {}x{}.",
+                solution, solution, puzzle, puzzle
+            ),
+            Mismatch::Empty { row, column } => {
+                write!(f, "Cell ({}, {}) is empty.", row + 1, column + 1)
+            }
+            Mismatch::Clue {
+                row,
+                column,
+                clue,
+                value,
+            } => write!(
+                f,
+                "Cell ({}, {}) is {}, but the clue there is {}.",
+                row + 1,
+                column + 1,
+                value,
+                clue
+            ),
+            Mismatch::Conflict {
+                cells: ((r, c), (rr, cc)),
+                value,
+            } => write!(
+                f,
+                "Cells ({}, {}) and ({}, {}) are both {}.",
+                r + 1,
+                c + 1,
+                rr + 1,
+                cc + 1,
+                value
+            ),
+        }
+    }
+}
+
+/// Checks that `solution` is complete, has no conflicting cells, and agrees
+/// with the clues of `puzzle`, returning everything that is wrong with it
+/// otherwise.
+pub fn verify_solution(puzzle: &Sudoku, solution: &Sudoku) -> Result<(), Vec<Mismatch>> {
+    if puzzle.side() != solution.side() {
+        return Err(vec![Mismatch::Size {
+            puzzle: puzzle.side(),
+            solution: solution.side(),
+        }]);
+    }
+
+    let side = solution.side();
+    let mut mismatches = vec![];
+
+    for (row, column) in (0..side).flat_map(|r| (0..side).map(move |c| (r, c))) {
+        let value = match solution.get(row, column).value() {
+            Some(value) => value,
+            None => {
+                mismatches.push(Mismatch::Empty { row, column });
+                continue;
+            }
+        };
+        if let Some(clue) = puzzle.get(row, column).value() {
+            if clue != value {
+                mismatches.push(Mismatch::Clue {
+                    row,
+                    column,
+                    clue,
+                    value,
+                });
+            }
+        }
+    }
+
+    for conflict in check::conflicts(solution) {
+        let (a, b) = conflict.cells;
+        mismatches.push(Mismatch::Conflict {
+            cells: ((a / side, a % side), (b / side, b % side)),
+            value: conflict.value,
+        });
+    }
+
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(mismatches)
+    }
+}